@@ -1,3 +1,5 @@
+#[cfg(feature = "async")]
+pub(crate) mod asyncbigwigread;
 #[cfg(feature = "read")]
 pub(crate) mod bbiread;
 #[cfg(feature = "write")]
@@ -13,6 +15,9 @@ pub(crate) mod bigwigread;
 #[cfg(feature = "write")]
 pub(crate) mod bigwigwrite;
 
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
 #[cfg(feature = "write")]
 use serde::{Deserialize, Serialize};
 
@@ -31,6 +36,18 @@ pub struct ZoomHeader {
     pub(crate) index_tree_offset: Option<u64>,
 }
 
+impl ZoomHeader {
+    /// The offset of this zoom level's data section.
+    pub fn data_offset(&self) -> u64 {
+        self.data_offset
+    }
+
+    /// The offset of this zoom level's index.
+    pub fn index_offset(&self) -> u64 {
+        self.index_offset
+    }
+}
+
 /// A single zoom item
 #[derive(Copy, Clone, Debug)]
 pub struct ZoomRecord {
@@ -40,6 +57,39 @@ pub struct ZoomRecord {
     pub summary: Summary,
 }
 
+impl ZoomRecord {
+    /// The mean value over the covered bases of this bin, or `NAN` if no
+    /// bases are covered.
+    pub fn mean(&self) -> f64 {
+        self.summary.mean()
+    }
+
+    /// The minimum value in this bin, or `NAN` if no bases are covered.
+    pub fn min_val(&self) -> f64 {
+        if self.summary.bases_covered == 0 {
+            return f64::NAN;
+        }
+        self.summary.min_val
+    }
+
+    /// The maximum value in this bin, or `NAN` if no bases are covered.
+    pub fn max_val(&self) -> f64 {
+        if self.summary.bases_covered == 0 {
+            return f64::NAN;
+        }
+        self.summary.max_val
+    }
+
+    /// The fraction of the bases in this bin that are covered, or `NAN` for
+    /// an empty bin.
+    pub fn coverage(&self) -> f64 {
+        if self.end <= self.start {
+            return f64::NAN;
+        }
+        self.summary.bases_covered as f64 / f64::from(self.end - self.start)
+    }
+}
+
 /// A summary of a section of data (may be an entire file)
 #[derive(Copy, Clone, Debug)]
 pub struct Summary {
@@ -51,8 +101,55 @@ pub struct Summary {
     pub sum_squares: f64,
 }
 
+impl Summary {
+    /// The mean value over the covered bases, or `NAN` if no bases are
+    /// covered.
+    pub fn mean(&self) -> f64 {
+        if self.bases_covered == 0 {
+            return f64::NAN;
+        }
+        self.sum / self.bases_covered as f64
+    }
+
+    /// The (sample) standard deviation of the value over the covered bases,
+    /// or `NAN` if no bases are covered.
+    pub fn std_dev(&self) -> f64 {
+        match self.bases_covered {
+            0 => f64::NAN,
+            1 => 0.0,
+            n => {
+                let n = n as f64;
+                let var = (self.sum_squares - (self.sum * self.sum) / n) / (n - 1.0);
+                var.max(0.0).sqrt()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "totalItems: {} basesCovered: {} min: {} max: {} sum: {} sumSquares: {} mean: {} std: {}",
+            self.total_items,
+            self.bases_covered,
+            self.min_val,
+            self.max_val,
+            self.sum,
+            self.sum_squares,
+            self.mean(),
+            self.std_dev(),
+        )
+    }
+}
+
 /// Represents a single value in a bigWig file
-#[derive(Copy, Clone, Debug, PartialEq)]
+///
+/// Two `Value`s are equal if they have the same coordinates and values,
+/// where (unlike for `f32`) `NaN` values are equal to each other. This makes
+/// `Value` `Eq` and `Hash`. For comparing values with some tolerance, see
+/// [`Value::approx_eq`].
+#[derive(Copy, Clone, Debug)]
 #[cfg_attr(feature = "write", derive(Serialize, Deserialize))]
 pub struct Value {
     pub start: u32,
@@ -60,8 +157,48 @@ pub struct Value {
     pub value: f32,
 }
 
+impl Value {
+    /// Whether this has the same coordinates as `other`, and a value within
+    /// `epsilon` of it (or both values are `NaN`).
+    pub fn approx_eq(&self, other: &Value, epsilon: f32) -> bool {
+        self.start == other.start
+            && self.end == other.end
+            && ((self.value.is_nan() && other.value.is_nan())
+                || (self.value - other.value).abs() <= epsilon)
+    }
+
+    /// The bits of the value, such that equal values have equal bits.
+    fn value_bits(&self) -> u32 {
+        if self.value.is_nan() {
+            f32::NAN.to_bits()
+        } else if self.value == 0.0 {
+            0
+        } else {
+            self.value.to_bits()
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        self.start == other.start
+            && self.end == other.end
+            && (self.value == other.value || (self.value.is_nan() && other.value.is_nan()))
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.start.hash(state);
+        self.end.hash(state);
+        self.value_bits().hash(state);
+    }
+}
+
 /// Represents a single entry in a bigBed file
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct BedEntry {
     pub start: u32,
     pub end: u32,
@@ -69,12 +206,14 @@ pub struct BedEntry {
 }
 
 /// The type of bbi file
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum BBIFile {
     BigWig,
     BigBed,
 }
 
+#[cfg(feature = "async")]
+pub use asyncbigwigread::*;
 #[cfg(feature = "read")]
 pub use bbiread::*;
 #[cfg(feature = "write")]
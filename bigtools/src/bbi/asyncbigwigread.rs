@@ -0,0 +1,314 @@
+/*!
+Provides an async interface for reading bigWig files, using tokio's
+[`AsyncRead`] and [`AsyncSeek`].
+
+This is useful when serving many concurrent queries (e.g. from a web server),
+where the blocking [`BigWigRead`][crate::BigWigRead] would require a thread
+per query. The cir tree traversal and block decoding is shared with
+`BigWigRead`; only the io is async.
+
+## Example
+```rust,no_run
+# use std::error::Error;
+# use futures::StreamExt;
+# use bigtools::AsyncBigWigRead;
+# async fn run() -> Result<(), Box<dyn Error>> {
+let file = tokio::fs::File::open("test.bigWig").await?;
+let mut bwread = AsyncBigWigRead::open(file).await?;
+let values = bwread.get_interval("chr17", 0, 59899).await?;
+let mut values = std::pin::pin!(values);
+while let Some(value) = values.next().await {
+    println!("{:?}", value?);
+}
+# Ok(())
+# }
+```
+*/
+use std::collections::VecDeque;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+use byteordered::Endianness;
+use futures::stream::{self, Stream};
+use itertools::Either;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::bbi::{BBIFile, Value};
+use crate::bbiread::{
+    file_type_from_magic, nodes_overlapping, read_block_data, read_cir_tree_header, read_info,
+    read_node, BBIFileInfo, BBIReadError, BBIReadOptions, Block, ChromInfo,
+};
+use crate::bigwigread::{block_values_from_data, skip_unknown_section, BigWigReadOpenError};
+
+/// Something that can be asynchronously read from and seeked in.
+pub trait AsyncSeekableRead: AsyncRead + AsyncSeek + Unpin {}
+impl<T> AsyncSeekableRead for T where T: AsyncRead + AsyncSeek + Unpin {}
+
+/// The struct used to asynchronously read a bigWig file
+pub struct AsyncBigWigRead<R> {
+    info: BBIFileInfo,
+    read: R,
+    pub options: BBIReadOptions,
+    skipped_blocks: Vec<Block>,
+}
+
+impl<R: AsyncSeekableRead> AsyncBigWigRead<R> {
+    /// Opens a new `AsyncBigWigRead` for a given type that implements both
+    /// `AsyncRead` and `AsyncSeek`
+    pub async fn open(mut read: R) -> Result<Self, BigWigReadOpenError> {
+        let header = read_at(&mut read, 0, 64).await?;
+        let magic = u32::from_be_bytes(header[0..4].try_into().unwrap());
+        let endianness = match file_type_from_magic(magic) {
+            Some((BBIFile::BigWig, endianness)) => endianness,
+            _ => return Err(BigWigReadOpenError::NotABigWig),
+        };
+        let zoom_levels = match endianness {
+            Endianness::Big => u16::from_be_bytes([header[6], header[7]]),
+            Endianness::Little => u16::from_le_bytes([header[6], header[7]]),
+        };
+
+        // `read_info` only reads the header and zoom headers, then the
+        // chromosome tree. So, we read those two sections into memory, and
+        // parse them like usual. The chromosome tree doesn't store its size,
+        // so we read until whatever section comes next.
+        let headers = read_at(&mut read, 0, 64 + 24 * zoom_levels as usize).await?;
+        let read_u64 = |offset: usize| {
+            let bytes: [u8; 8] = headers[offset..offset + 8].try_into().unwrap();
+            match endianness {
+                Endianness::Big => u64::from_be_bytes(bytes),
+                Endianness::Little => u64::from_le_bytes(bytes),
+            }
+        };
+        let chrom_tree_offset = read_u64(8);
+        // The data, index, autosql, and summary offsets, then the zoom data
+        // and index offsets
+        let section_offsets = [16, 24, 40, 48]
+            .into_iter()
+            .chain((0..zoom_levels as usize).flat_map(|z| [64 + z * 24 + 8, 64 + z * 24 + 16]))
+            .map(read_u64);
+        let file_len = read.seek(SeekFrom::End(0)).await?;
+        let chrom_tree_end = section_offsets
+            .filter(|offset| *offset > chrom_tree_offset)
+            .fold(file_len, u64::min);
+        let chrom_tree_len = chrom_tree_end.saturating_sub(chrom_tree_offset) as usize;
+        let chrom_tree = read_at(&mut read, chrom_tree_offset, chrom_tree_len).await?;
+        let mut sections = SectionsCursor {
+            sections: vec![(0, headers), (chrom_tree_offset, chrom_tree)],
+            position: 0,
+        };
+        let info = read_info(&mut sections)?;
+
+        Ok(AsyncBigWigRead {
+            info,
+            read,
+            options: BBIReadOptions::default(),
+            skipped_blocks: vec![],
+        })
+    }
+
+    /// Gets the info about this file
+    pub fn info(&self) -> &BBIFileInfo {
+        &self.info
+    }
+
+    /// The blocks that were skipped because they have an unknown section type.
+    /// See [`BigWigRead::skipped_blocks`](crate::BigWigRead::skipped_blocks).
+    pub fn skipped_blocks(&self) -> &[Block] {
+        &self.skipped_blocks
+    }
+
+    /// Gets the chromosomes present in this file
+    pub fn chroms(&self) -> &[ChromInfo] {
+        &self.info.chrom_info
+    }
+
+    /// For a given chromosome, start, and end, returns a `Stream` of the
+    /// intersecting `Value`s. The index is searched up front, but blocks are
+    /// only read (and decompressed) as the stream is polled. The query is
+    /// interpreted the same as with `BigWigRead::get_interval`.
+    pub async fn get_interval(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<impl Stream<Item = Result<Value, BBIReadError>> + '_, BBIReadError> {
+        let chrom = self.info.chrom_id(chrom_name)?;
+        if start > end {
+            return Err(BBIReadError::InvalidInterval { start, end });
+        }
+        let blocks = self.search_cir_tree(chrom, start, end).await?;
+
+        let state = (self, blocks.into_iter(), Vec::new().into_iter());
+        Ok(stream::unfold(
+            state,
+            move |(bigwig, mut blocks, mut values)| async move {
+                loop {
+                    if let Some(value) = values.next() {
+                        return Some((Ok(value), (bigwig, blocks, values)));
+                    }
+                    let block = blocks.next()?;
+                    match bigwig.block_values(&block, chrom, start, end).await {
+                        Ok(block_values) => values = block_values.into_iter(),
+                        Err(e) => return Some((Err(e), (bigwig, blocks, values))),
+                    }
+                }
+            },
+        ))
+    }
+
+    /// Reads and decodes the `Value`s of a block that overlap `start` and
+    /// `end` on `chrom`.
+    async fn block_values(
+        &mut self,
+        block: &Block,
+        chrom: u32,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<Value>, BBIReadError> {
+        let raw = read_at(&mut self.read, block.offset, block.size as usize).await?;
+        let in_memory = Block {
+            offset: 0,
+            size: block.size,
+        };
+        let data = read_block_data(&self.info, &mut Cursor::new(raw), &in_memory)?;
+        let values = block_values_from_data(&self.info, data, chrom, start, end);
+        let values = skip_unknown_section(values, &self.options, block, &mut self.skipped_blocks)?;
+        Ok(values.into_iter().flatten().collect())
+    }
+
+    /// Finds the blocks of the full data index that overlap `start` and `end`
+    /// on `chrom_ix`. Each node is read asynchronously, then parsed the same
+    /// as with `BigWigRead`.
+    async fn search_cir_tree(
+        &mut self,
+        chrom_ix: u32,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<Block>, BBIReadError> {
+        let endianness = self.info.header.endianness;
+        let index_offset = self.info.header.full_index_offset;
+        if self.info.header.full_index_tree_offset.is_none() {
+            let header = read_at(&mut self.read, index_offset, 48).await?;
+            let items_per_slot = read_cir_tree_header(endianness, &mut Cursor::new(header))
+                .map_err(|e| match e {
+                    Either::Left(_) => BBIReadError::UnknownMagic,
+                    Either::Right(e) => BBIReadError::IoError(e),
+                })?;
+            self.info.header.full_index_tree_offset = Some(index_offset + 48);
+            self.info.header.items_per_slot = Some(items_per_slot);
+        }
+
+        let mut blocks = vec![];
+        let mut remaining_childblocks = VecDeque::from([index_offset + 48]);
+        while let Some(node_offset) = remaining_childblocks.pop_front() {
+            let mut node = read_at(&mut self.read, node_offset, 4).await?;
+            let count = match endianness {
+                Endianness::Big => u16::from_be_bytes([node[2], node[3]]),
+                Endianness::Little => u16::from_le_bytes([node[2], node[3]]),
+            };
+            // Leaf items are 32 bytes; non-leaf items don't have a data size,
+            // so are 24
+            let item_size = if node[0] == 1 { 32 } else { 24 };
+            node.extend(
+                read_at(&mut self.read, node_offset + 4, count as usize * item_size).await?,
+            );
+            let iter = read_node(&mut Cursor::new(node), 0, endianness)?;
+            let (new_childblocks, node_blocks) = nodes_overlapping(iter, chrom_ix, start, end);
+            for child in new_childblocks.into_iter().rev() {
+                remaining_childblocks.push_front(child);
+            }
+            blocks.extend(node_blocks);
+        }
+        Ok(blocks)
+    }
+}
+
+/// An in-memory reader over a few sections of a file, each stored with its
+/// offset. Reads outside of the sections hit EOF.
+struct SectionsCursor {
+    sections: Vec<(u64, Vec<u8>)>,
+    position: u64,
+}
+
+impl Read for SectionsCursor {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let position = self.position;
+        let section = self
+            .sections
+            .iter()
+            .find(|(offset, data)| *offset <= position && position < *offset + data.len() as u64);
+        let Some((offset, data)) = section else {
+            return Ok(0);
+        };
+        let data = &data[(position - offset) as usize..];
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for SectionsCursor {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = match pos {
+            SeekFrom::Start(s) => s,
+            SeekFrom::Current(s) => self.position.checked_add_signed(s).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "Invalid seek position")
+            })?,
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Cannot seek from the end of file sections",
+                ))
+            }
+        };
+        Ok(self.position)
+    }
+}
+
+/// Reads `len` bytes at `offset`.
+async fn read_at<R: AsyncSeekableRead>(
+    read: &mut R,
+    offset: u64,
+    len: usize,
+) -> io::Result<Vec<u8>> {
+    read.seek(SeekFrom::Start(offset)).await?;
+    let mut bytes = vec![0u8; len];
+    read.read_exact(&mut bytes).await?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BigWigRead;
+    use futures::TryStreamExt;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_async_matches_sync() -> Result<(), Box<dyn std::error::Error>> {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("resources/test/valid.bigWig");
+
+        let mut bwread = BigWigRead::open_file(&dir)?;
+        let sync_intervals: Vec<_> = bwread
+            .get_interval("chr17", 0, 83257441)?
+            .collect::<Result<_, _>>()?;
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let async_intervals: Vec<_> = runtime.block_on(async {
+            let bytes = std::fs::read(&dir)?;
+            let mut bwread = AsyncBigWigRead::open(Cursor::new(bytes)).await?;
+            assert_eq!(bwread.chroms()[0].name, "chr17");
+            let values = bwread
+                .get_interval("chr17", 0, 83257441)
+                .await?
+                .try_collect()
+                .await?;
+            Ok::<_, Box<dyn std::error::Error>>(values)
+        })?;
+        assert!(!async_intervals.is_empty());
+        assert_eq!(sync_intervals, async_intervals);
+
+        Ok(())
+    }
+}
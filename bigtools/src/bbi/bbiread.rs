@@ -1,8 +1,10 @@
 use std::borrow::BorrowMut;
+use std::cell::RefCell;
 use std::collections::hash_map::Entry;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fs::File;
 use std::io::{self, Read, Seek, SeekFrom};
+use std::path::PathBuf;
 use std::vec::Vec;
 
 use byteordered::Endianness;
@@ -22,6 +24,7 @@ use crate::{BigBedRead, BigWigRead};
 
 use self::internal::BBIReadInternal;
 
+/// A block of (possibly compressed) data in a bbi file
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Block {
     pub(crate) offset: u64,
@@ -29,11 +32,84 @@ pub struct Block {
 }
 
 impl Block {
+    /// The absolute offset of this block from the start of the file
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// The size of this block in the file (compressed, if the file is
+    /// compressed)
     pub fn size(&self) -> u64 {
         self.size
     }
 }
 
+/// The default for `BBIReadOptions::max_coalesce_gap`
+pub const DEFAULT_MAX_COALESCE_GAP: u64 = 4096;
+
+/// The default for `BBIReadOptions::max_coalesce_bytes`
+pub const DEFAULT_MAX_COALESCE_BYTES: u64 = 1 << 20;
+
+/// Options that affect how data is read from a bbi file
+#[derive(Copy, Clone, Debug)]
+pub struct BBIReadOptions {
+    /// Consecutive blocks that are separated by at most this many bytes are
+    /// read with a single I/O operation, rather than one per block. Any bytes
+    /// in the gaps between blocks are read and discarded.
+    pub max_coalesce_gap: u64,
+    /// Blocks are only read together while they span at most this many bytes
+    /// of the file (including gaps), so that a large query over densely
+    /// packed blocks doesn't read and decompress them all at once. A block
+    /// larger than this is still read on its own.
+    pub max_coalesce_bytes: u64,
+    /// If `true`, bigWig blocks with an unknown section type are skipped
+    /// rather than returning an error. This may be useful for reading files
+    /// with corrupt blocks or blocks from a future version of the format.
+    /// The skipped blocks are recorded, see `BigWigRead::skipped_blocks`.
+    pub skip_unknown_sections: bool,
+    /// The maximum number of values that methods returning a value per base
+    /// (like `BigWigRead::values`) will allocate. Larger requests return
+    /// `BBIReadError::TooManyValues`, rather than potentially running out of
+    /// memory. If `None` (the default), there is no limit.
+    pub max_values: Option<usize>,
+}
+
+impl Default for BBIReadOptions {
+    fn default() -> Self {
+        BBIReadOptions {
+            max_coalesce_gap: DEFAULT_MAX_COALESCE_GAP,
+            max_coalesce_bytes: DEFAULT_MAX_COALESCE_BYTES,
+            skip_unknown_sections: false,
+            max_values: None,
+        }
+    }
+}
+
+/// Takes the next run of blocks from `blocks` that can be read together. Each
+/// block after the first must start after the previous block ends, at most
+/// `options.max_coalesce_gap` bytes after it, and the run must span at most
+/// `options.max_coalesce_bytes` bytes (unless it's a single block).
+pub(crate) fn next_coalesced_blocks(
+    blocks: &mut std::vec::IntoIter<Block>,
+    options: &BBIReadOptions,
+) -> Vec<Block> {
+    let mut run: Vec<Block> = blocks.next().into_iter().collect();
+    while let (Some(first), Some(prev), Some(next)) =
+        (run.first(), run.last(), blocks.as_slice().first())
+    {
+        let prev_end = prev.offset + prev.size;
+        if next.offset < prev_end
+            || next.offset - prev_end > options.max_coalesce_gap
+            || next.offset + next.size - first.offset > options.max_coalesce_bytes
+        {
+            break;
+        }
+        run.push(*next);
+        blocks.next();
+    }
+    run
+}
+
 /// Header info for a bbi file
 ///
 /// Note that info on internal properties like file offsets are not public.
@@ -50,9 +126,14 @@ pub struct BBIHeader {
     pub(crate) full_data_offset: u64,
     pub(crate) full_index_offset: u64,
     pub(crate) full_index_tree_offset: Option<u64>,
+    /// The maximum number of items per block, from the full data index
+    /// header. Only known once the full data index has been read.
+    pub(crate) items_per_slot: Option<u32>,
     pub(crate) auto_sql_offset: u64,
     pub(crate) total_summary_offset: u64,
     pub(crate) uncompress_buf_size: u32,
+    /// The offset of the extended header (for bigBeds, version 4+), or 0.
+    pub(crate) extension_offset: u64,
 }
 
 impl BBIHeader {
@@ -63,6 +144,23 @@ impl BBIHeader {
     pub fn primary_data_size(&self) -> u64 {
         self.full_index_offset - self.full_data_offset
     }
+
+    /// The offset of the full data section. The section starts with the
+    /// number of items in the file.
+    pub fn data_offset(&self) -> u64 {
+        self.full_data_offset
+    }
+
+    /// The offset of the full data index.
+    pub fn index_offset(&self) -> u64 {
+        self.full_index_offset
+    }
+
+    /// The offset of the total summary, or `None` if there isn't one (for
+    /// version 1 files).
+    pub fn summary_offset(&self) -> Option<u64> {
+        (self.total_summary_offset != 0).then_some(self.total_summary_offset)
+    }
 }
 
 /// Information on a chromosome in a bbi file
@@ -73,6 +171,14 @@ pub struct ChromInfo {
     pub(crate) id: u32,
 }
 
+impl ChromInfo {
+    /// The id of this chromosome in the chromosome tree. Block headers and
+    /// index entries refer to chromosomes by this id.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+}
+
 impl PartialEq for ChromInfo {
     fn eq(&self, other: &ChromInfo) -> bool {
         self.name == other.name
@@ -101,14 +207,99 @@ impl From<ChromIdNotFound> for BBIReadError {
 }
 
 impl BBIFileInfo {
+    /// The chromosome named `chrom_name`
+    pub(crate) fn chrom_info_named(&self, chrom_name: &str) -> Option<&ChromInfo> {
+        self.chrom_info.iter().find(|c| c.name == chrom_name)
+    }
+
     pub(crate) fn chrom_id(&self, chrom_name: &str) -> Result<u32, ChromIdNotFound> {
-        let chrom_info = &self.chrom_info;
-        let chrom = chrom_info.iter().find(|&x| x.name == chrom_name);
+        let chrom = self.chrom_info_named(chrom_name);
         match chrom {
             Some(c) => Ok(c.id),
             None => Err(ChromIdNotFound(chrom_name.to_owned())),
         }
     }
+
+    /// The mapping of chromosome names to the ids used in the file's blocks
+    /// and index, as stored in the chromosome tree.
+    pub fn chrom_ids(&self) -> HashMap<String, u32> {
+        self.chrom_info
+            .iter()
+            .map(|c| (c.name.clone(), c.id))
+            .collect()
+    }
+
+    /// Whether any of the data, index, or summary offsets in the header point
+    /// past the end of a file of `file_len` bytes (e.g. because the file was
+    /// only partially downloaded).
+    pub(crate) fn is_truncated(&self, file_len: u64) -> bool {
+        let header = &self.header;
+        let zoom_offsets = self
+            .zoom_headers
+            .iter()
+            .flat_map(|z| [z.data_offset, z.index_offset]);
+        [
+            header.chromosome_tree_offset,
+            header.full_data_offset,
+            header.full_index_offset,
+            header.auto_sql_offset,
+            header.total_summary_offset,
+        ]
+        .into_iter()
+        .chain(zoom_offsets)
+        .any(|offset| offset >= file_len)
+    }
+
+    /// The offset of the full data section. See [`BBIHeader::data_offset`].
+    pub fn data_offset(&self) -> u64 {
+        self.header.data_offset()
+    }
+
+    /// The offset of the total summary. See [`BBIHeader::summary_offset`].
+    pub fn summary_offset(&self) -> Option<u64> {
+        self.header.summary_offset()
+    }
+
+    /// The headers of the zoom levels in this file, in the order they are
+    /// stored.
+    pub fn zoom_headers(&self) -> &[ZoomHeader] {
+        &self.zoom_headers
+    }
+
+    /// The reduction levels of the zoom levels in this file, sorted from
+    /// finest to coarsest.
+    pub fn zoom_levels(&self) -> Vec<u32> {
+        let mut levels: Vec<u32> = self
+            .zoom_headers
+            .iter()
+            .map(|z| z.reduction_level)
+            .collect();
+        levels.sort_unstable();
+        levels
+    }
+
+    /// The finest zoom level with at most `max_bins` records for a region of
+    /// `length` bases (i.e. with a reduction level of at least
+    /// `length / max_bins`). If every zoom level has more records, this is the
+    /// coarsest zoom level. Returns `None` if there are no zoom levels.
+    pub fn zoom_level_for_bins(&self, length: u32, max_bins: u32) -> Option<u32> {
+        let levels = self.zoom_levels();
+        levels
+            .iter()
+            .copied()
+            .find(|level| length.div_ceil(*level) <= max_bins)
+            .or(levels.last().copied())
+    }
+
+    /// The coarsest zoom level with a reduction level at or below `desired`,
+    /// or `None` if all zoom levels are coarser (or there are none).
+    pub fn closest_zoom_level(&self, desired: u32) -> Option<u32> {
+        self.zoom_headers
+            .iter()
+            .map(|z| z.reduction_level)
+            .filter(|r| *r <= desired)
+            .max()
+    }
 }
 
 #[derive(Error, Debug)]
@@ -123,8 +314,10 @@ pub(crate) enum BBIFileReadInfoError {
 
 #[derive(Error, Debug)]
 pub enum CirTreeSearchError {
-    #[error("The passed chromosome ({}) was incorrect.", .0)]
+    #[error("The chromosome ({}) is not in the file.", .0)]
     InvalidChromosome(String),
+    #[error("The passed interval was invalid: start ({start}) is greater than end ({end}).")]
+    InvalidInterval { start: u32, end: u32 },
     #[error("Error occurred: {}", .0)]
     IoError(#[from] io::Error),
 }
@@ -132,22 +325,74 @@ pub enum CirTreeSearchError {
 /// Possible errors encountered when reading a bbi file
 #[derive(Error, Debug)]
 pub enum BBIReadError {
-    #[error("The passed chromosome ({}) was incorrect.", .0)]
+    /// The chromosome isn't in the file. Queries for a missing chromosome
+    /// always return this, rather than an empty (or all `NaN`) result, which
+    /// would be indistinguishable from a region without data.
+    #[error("The chromosome ({}) is not in the file.", .0)]
     InvalidChromosome(String),
+    #[error("The passed interval was invalid: start ({start}) is greater than end ({end}).")]
+    InvalidInterval { start: u32, end: u32 },
     #[error("Invalid magic (likely a bug).")]
     UnknownMagic,
     #[error("The file was invalid: {}", .0)]
     InvalidFile(String),
+    #[error("A block had an unknown section type: {}", .0)]
+    InvalidSection(u8),
+    #[error("Unable to decompress block: {}", .0)]
+    Decompression(io::Error),
+    #[error("{requested} values were requested, but at most {cap} are allowed (see `BBIReadOptions::max_values`).")]
+    TooManyValues { requested: usize, cap: usize },
+    #[error(
+        "The passed buffer has a length of {actual}, but the region has a length of {expected}."
+    )]
+    InvalidBufferLength { expected: usize, actual: usize },
+    #[error("The file doesn't have an extra index for the field: {}", .0)]
+    MissingExtraIndex(String),
     #[error("Error parsing bed-like data.")]
     BedValueError(#[from] BedValueError),
     #[error("Error occurred: {}", .0)]
-    IoError(#[from] io::Error),
+    IoError(io::Error),
+}
+
+/// The error wrapped by the `io::Error` returned when a block fails to
+/// decompress, so that it can be told apart from other io errors.
+#[derive(Error, Debug)]
+#[error("{}", .0)]
+pub(crate) struct DecompressionError(String);
+
+impl From<io::Error> for BBIReadError {
+    fn from(value: io::Error) -> Self {
+        if value
+            .get_ref()
+            .is_some_and(|inner| inner.is::<DecompressionError>())
+        {
+            BBIReadError::Decompression(value)
+        } else {
+            BBIReadError::IoError(value)
+        }
+    }
+}
+
+impl From<BBIReadError> for io::Error {
+    fn from(value: BBIReadError) -> Self {
+        match value {
+            BBIReadError::Decompression(e) | BBIReadError::IoError(e) => e,
+            BBIReadError::BedValueError(BedValueError::IoError(e)) => e,
+            e @ (BBIReadError::InvalidChromosome(_) | BBIReadError::InvalidInterval { .. }) => {
+                io::Error::new(io::ErrorKind::InvalidInput, e)
+            }
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
 }
 
 impl From<CirTreeSearchError> for BBIReadError {
     fn from(value: CirTreeSearchError) -> Self {
         match value {
             CirTreeSearchError::InvalidChromosome(chrom) => BBIReadError::InvalidChromosome(chrom),
+            CirTreeSearchError::InvalidInterval { start, end } => {
+                BBIReadError::InvalidInterval { start, end }
+            }
             CirTreeSearchError::IoError(e) => BBIReadError::IoError(e),
         }
     }
@@ -245,12 +490,14 @@ pub(crate) mod internal {
                     .seek(SeekFrom::Start(index_offset))
                     .map_err(|e| FullDataCirTreeError::IoError(e))?;
 
-                read_cir_tree_header(endianness, reader.raw_reader()).map_err(|e| match e {
-                    Either::Left(_) => FullDataCirTreeError::UnknownMagic,
-                    Either::Right(e) => FullDataCirTreeError::IoError(e),
-                })?;
+                let items_per_slot = read_cir_tree_header(endianness, reader.raw_reader())
+                    .map_err(|e| match e {
+                        Either::Left(_) => FullDataCirTreeError::UnknownMagic,
+                        Either::Right(e) => FullDataCirTreeError::IoError(e),
+                    })?;
 
                 info.header.full_index_tree_offset = Some(index_offset + 48);
+                info.header.items_per_slot = Some(items_per_slot);
             }
             Ok(CirTreeIndex(CirTreeIndexType::FullData, index_offset + 48))
         }
@@ -314,7 +561,7 @@ pub(crate) fn search_cir_tree<R: BBIFileRead>(
     end: u32,
 ) -> Result<Vec<Block>, CirTreeSearchError> {
     let chrom_ix = {
-        let chrom = info.chrom_info.iter().find(|&x| x.name == chrom_name);
+        let chrom = info.chrom_info_named(chrom_name);
         match chrom {
             Some(c) => c.id,
             None => {
@@ -325,6 +572,10 @@ pub(crate) fn search_cir_tree<R: BBIFileRead>(
         }
     };
 
+    if start > end {
+        return Err(CirTreeSearchError::InvalidInterval { start, end });
+    }
+
     let endianness = info.header.endianness;
 
     Ok(search_cir_tree_inner(
@@ -335,15 +586,17 @@ pub(crate) fn search_cir_tree<R: BBIFileRead>(
 #[derive(Debug)]
 pub(crate) struct UnknownMagic;
 
+/// Reads the header of a cir tree, returning the maximum number of items per
+/// block.
 pub(crate) fn read_cir_tree_header<R: Read + Seek>(
     endianness: Endianness,
     file: &mut R,
-) -> Result<(), Either<UnknownMagic, io::Error>> {
+) -> Result<u32, Either<UnknownMagic, io::Error>> {
     let mut header_data = BytesMut::zeroed(48);
     file.read_exact(&mut header_data)
         .map_err(|e| Either::Right(e))?;
 
-    match endianness {
+    let item_per_slot = match endianness {
         Endianness::Big => {
             let magic = header_data.get_u32();
             if magic != CIR_TREE_MAGIC {
@@ -357,8 +610,9 @@ pub(crate) fn read_cir_tree_header<R: Read + Seek>(
             let _end_chrom_idx = header_data.get_u32();
             let _end_base = header_data.get_u32();
             let _end_file_offset = header_data.get_u64();
-            let _item_per_slot = header_data.get_u32();
+            let item_per_slot = header_data.get_u32();
             let _reserved = header_data.get_u32();
+            item_per_slot
         }
         Endianness::Little => {
             let magic = header_data.get_u32_le();
@@ -373,11 +627,38 @@ pub(crate) fn read_cir_tree_header<R: Read + Seek>(
             let _end_chrom_idx = header_data.get_u32_le();
             let _end_base = header_data.get_u32_le();
             let _end_file_offset = header_data.get_u64_le();
-            let _item_per_slot = header_data.get_u32_le();
+            let item_per_slot = header_data.get_u32_le();
             let _reserved = header_data.get_u32_le();
+            item_per_slot
         }
     };
-    Ok(())
+    Ok(item_per_slot)
+}
+
+/// Reads the number of items (blocks) indexed by a cir tree from its header.
+pub(crate) fn read_cir_tree_item_count<R: Read>(
+    endianness: Endianness,
+    file: &mut R,
+) -> Result<u64, Either<UnknownMagic, io::Error>> {
+    let mut header_data = BytesMut::zeroed(16);
+    file.read_exact(&mut header_data).map_err(Either::Right)?;
+
+    let (magic, item_count) = match endianness {
+        Endianness::Big => {
+            let magic = header_data.get_u32();
+            let _blocksize = header_data.get_u32();
+            (magic, header_data.get_u64())
+        }
+        Endianness::Little => {
+            let magic = header_data.get_u32_le();
+            let _blocksize = header_data.get_u32_le();
+            (magic, header_data.get_u64_le())
+        }
+    };
+    if magic != CIR_TREE_MAGIC {
+        return Err(Either::Left(UnknownMagic));
+    }
+    Ok(item_count)
 }
 
 pub(crate) fn search_cir_tree_inner<R: BBIFileRead>(
@@ -411,6 +692,53 @@ pub(crate) fn search_cir_tree_inner<R: BBIFileRead>(
     Ok(blocks)
 }
 
+/// Like `search_cir_tree_inner`, but returns the leaf items of the blocks that
+/// overlap `start` and `end` on `chrom_ix`, sorted by offset, so that the
+/// region each block covers is known without reading it.
+pub(crate) fn search_cir_tree_leaves<R: BBIFileRead>(
+    endianness: Endianness,
+    file: &mut R,
+    at: u64,
+    chrom_ix: u32,
+    start: u32,
+    end: u32,
+) -> io::Result<Vec<CirTreeNodeLeaf>> {
+    let mut leaves = vec![];
+    let mut remaining_nodes = VecDeque::from([at]);
+    while let Some(node_offset) = remaining_nodes.pop_front() {
+        match read_node(file.raw_reader(), node_offset, endianness)? {
+            CirTreeNodeIterator::Leaf(items) => leaves.extend(items.filter(|item| {
+                overlaps(
+                    chrom_ix,
+                    start,
+                    end,
+                    item.start_chrom_ix,
+                    item.start_base,
+                    item.end_chrom_ix,
+                    item.end_base,
+                )
+            })),
+            CirTreeNodeIterator::NonLeaf(items) => remaining_nodes.extend(
+                items
+                    .filter(|item| {
+                        overlaps(
+                            chrom_ix,
+                            start,
+                            end,
+                            item.start_chrom_ix,
+                            item.start_base,
+                            item.end_chrom_ix,
+                            item.end_base,
+                        )
+                    })
+                    .map(|item| item.node_offset),
+            ),
+        }
+    }
+    leaves.sort_by_key(|leaf| leaf.data_offset);
+    Ok(leaves)
+}
+
 pub enum GenericBBIRead<R> {
     BigWig(BigWigRead<R>),
     BigBed(BigBedRead<R>),
@@ -458,6 +786,12 @@ pub enum GenericBBIFileOpenError {
     InvalidChroms,
     #[error("An error occurred: {}", .0)]
     IoError(#[from] io::Error),
+    /// An error when opening the file at `path` (from `open_file`)
+    #[error("Error when opening {}: {}", path.display(), source)]
+    FileError {
+        path: PathBuf,
+        source: Box<GenericBBIFileOpenError>,
+    },
 }
 
 impl From<BBIFileReadInfoError> for GenericBBIFileOpenError {
@@ -491,24 +825,35 @@ impl<R: BBIFileRead> GenericBBIRead<R> {
     pub fn open(mut read: R) -> Result<Self, GenericBBIFileOpenError> {
         let info = read_info(&mut read)?;
         match info.filetype {
-            BBIFile::BigWig => Ok(GenericBBIRead::BigWig(BigWigRead { info, read })),
-            BBIFile::BigBed => Ok(GenericBBIRead::BigBed(BigBedRead { info, read })),
+            BBIFile::BigWig => Ok(GenericBBIRead::BigWig(BigWigRead {
+                info,
+                read,
+                options: BBIReadOptions::default(),
+                skipped_blocks: vec![],
+                chrom_aliases: HashMap::new(),
+            })),
+            BBIFile::BigBed => Ok(GenericBBIRead::BigBed(BigBedRead {
+                info,
+                read,
+                autosql: None,
+            })),
         }
     }
 }
 
 impl GenericBBIRead<ReopenableFile> {
-    /// Opens a generic bbi file
+    /// Opens a generic bbi file. Any error is returned as
+    /// `GenericBBIFileOpenError::FileError`, with the path.
     pub fn open_file(path: &str) -> Result<Self, GenericBBIFileOpenError> {
+        let with_path = |source| GenericBBIFileOpenError::FileError {
+            path: path.into(),
+            source: Box::new(source),
+        };
         let reopen = ReopenableFile {
-            file: File::open(&path)?,
+            file: File::open(path).map_err(|e| with_path(e.into()))?,
             path: path.into(),
         };
-        let b = GenericBBIRead::open(reopen);
-        if b.is_err() {
-            eprintln!("Error when opening: {}", path);
-        }
-        b
+        GenericBBIRead::open(reopen).map_err(with_path)
     }
 }
 
@@ -517,6 +862,19 @@ pub trait BBIFileRead {
 
     fn get_block_data(&mut self, info: &BBIFileInfo, block: &Block) -> io::Result<Vec<u8>>;
 
+    /// Gets the data for multiple blocks. This may be more efficient than
+    /// calling `get_block_data` for each, if the blocks are close together.
+    fn get_blocks_data(
+        &mut self,
+        info: &BBIFileInfo,
+        blocks: &[Block],
+    ) -> io::Result<Vec<Vec<u8>>> {
+        blocks
+            .iter()
+            .map(|block| self.get_block_data(info, block))
+            .collect()
+    }
+
     fn blocks_for_cir_tree_node(
         &mut self,
         endianness: Endianness,
@@ -536,6 +894,14 @@ impl<S: SeekableRead> BBIFileRead for S {
         read_block_data(info, self, block)
     }
 
+    fn get_blocks_data(
+        &mut self,
+        info: &BBIFileInfo,
+        blocks: &[Block],
+    ) -> io::Result<Vec<Vec<u8>>> {
+        read_coalesced_block_data(info, self, blocks)
+    }
+
     fn blocks_for_cir_tree_node(
         &mut self,
         endianness: Endianness,
@@ -560,13 +926,87 @@ impl<S: SeekableRead> BBIFileRead for S {
 pub struct CachedBBIFileRead<S> {
     read: S,
     cir_tree_node_map: HashMap<u64, Either<Vec<CirTreeNodeLeaf>, Vec<CirTreeNodeNonLeaf>>>,
-    block_data: HashMap<Block, Vec<u8>>,
+    block_data: BlockCache,
+}
+
+/// The default number of decompressed blocks kept by a `CachedBBIFileRead`
+pub const DEFAULT_BLOCK_CACHE_CAPACITY: usize = 5000;
+
+/// A least-recently-used cache of decompressed block data
+#[derive(Clone)]
+struct BlockCache {
+    capacity: usize,
+    // Incremented on every access, used to order blocks by last use
+    tick: u64,
+    data: HashMap<Block, (u64, Vec<u8>)>,
+    last_used: BTreeMap<u64, Block>,
+}
+
+impl BlockCache {
+    fn new(capacity: usize) -> Self {
+        BlockCache {
+            capacity,
+            tick: 0,
+            data: HashMap::new(),
+            last_used: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, block: &Block) -> Option<&Vec<u8>> {
+        let (last_used, data) = self.data.get_mut(block)?;
+        self.last_used.remove(last_used);
+        self.tick += 1;
+        *last_used = self.tick;
+        self.last_used.insert(self.tick, *block);
+        Some(data)
+    }
+
+    fn insert(&mut self, block: Block, data: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((last_used, _)) = self.data.insert(block, (self.tick, data)) {
+            self.last_used.remove(&last_used);
+        }
+        self.last_used.insert(self.tick, block);
+        self.evict();
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.data.len() > self.capacity {
+            let (_, block) = self.last_used.pop_first().unwrap();
+            self.data.remove(&block);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.data.clear();
+        self.last_used.clear();
+    }
 }
 
 impl<S> CachedBBIFileRead<S> {
     pub fn inner_read(&self) -> &S {
         &self.read
     }
+
+    /// Sets the maximum number of decompressed blocks to cache, evicting the
+    /// least recently used blocks if there are more. A capacity of `0`
+    /// disables block caching.
+    pub fn set_block_cache_capacity(&mut self, capacity: usize) {
+        self.block_data.set_capacity(capacity);
+    }
+
+    /// Removes all cached decompressed blocks.
+    pub fn clear_block_cache(&mut self) {
+        self.block_data.clear();
+    }
 }
 
 impl<S: SeekableRead> CachedBBIFileRead<S> {
@@ -574,7 +1014,7 @@ impl<S: SeekableRead> CachedBBIFileRead<S> {
         CachedBBIFileRead {
             read,
             cir_tree_node_map: HashMap::new(),
-            block_data: HashMap::new(),
+            block_data: BlockCache::new(DEFAULT_BLOCK_CACHE_CAPACITY),
         }
     }
 }
@@ -584,10 +1024,7 @@ impl<S: SeekableRead> BBIFileRead for CachedBBIFileRead<S> {
 
     fn get_block_data(&mut self, info: &BBIFileInfo, block: &Block) -> io::Result<Vec<u8>> {
         if let Some(data) = self.block_data.get(block) {
-            return Ok(data.clone());
-        }
-        if self.block_data.len() >= 5000 {
-            self.block_data.clear();
+            return Ok(copy_block_data(data));
         }
         let data = read_block_data(info, &mut self.read, block)?;
         self.block_data.insert(*block, data.clone());
@@ -648,6 +1085,32 @@ impl<R: Reopen + SeekableRead> Reopen for CachedBBIFileRead<R> {
     }
 }
 
+/// Gets the file type and endianness from the magic, read as big-endian.
+pub(crate) fn file_type_from_magic(magic: u32) -> Option<(BBIFile, Endianness)> {
+    match magic {
+        _ if magic == BIGWIG_MAGIC.to_le() => Some((BBIFile::BigWig, Endianness::Big)),
+        _ if magic == BIGWIG_MAGIC.to_be() => Some((BBIFile::BigWig, Endianness::Little)),
+        _ if magic == BIGBED_MAGIC.to_le() => Some((BBIFile::BigBed, Endianness::Big)),
+        _ if magic == BIGBED_MAGIC.to_be() => Some((BBIFile::BigBed, Endianness::Little)),
+        _ => None,
+    }
+}
+
+impl BBIFile {
+    /// Detects whether `read` is a bigWig or bigBed from its magic number,
+    /// only reading the first four bytes. Returns `None` if it is neither
+    /// (including if it is shorter than four bytes).
+    pub fn detect<R: Read>(read: &mut R) -> io::Result<Option<BBIFile>> {
+        let mut magic = [0u8; 4];
+        match read.read_exact(&mut magic) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        Ok(file_type_from_magic(u32::from_be_bytes(magic)).map(|(filetype, _)| filetype))
+    }
+}
+
 pub(crate) fn read_info<R: BBIFileRead>(file: &mut R) -> Result<BBIFileInfo, BBIFileReadInfoError> {
     let mut file = file.raw_reader();
 
@@ -655,13 +1118,8 @@ pub(crate) fn read_info<R: BBIFileRead>(file: &mut R) -> Result<BBIFileInfo, BBI
     file.read_exact(&mut header_data)?;
 
     let magic = header_data.get_u32();
-    let (filetype, endianness) = match magic {
-        _ if magic == BIGWIG_MAGIC.to_le() => (BBIFile::BigWig, Endianness::Big),
-        _ if magic == BIGWIG_MAGIC.to_be() => (BBIFile::BigWig, Endianness::Little),
-        _ if magic == BIGBED_MAGIC.to_le() => (BBIFile::BigBed, Endianness::Big),
-        _ if magic == BIGBED_MAGIC.to_be() => (BBIFile::BigBed, Endianness::Little),
-        _ => return Err(BBIFileReadInfoError::UnknownMagic),
-    };
+    let (filetype, endianness) =
+        file_type_from_magic(magic).ok_or(BBIFileReadInfoError::UnknownMagic)?;
 
     let (
         version,
@@ -674,6 +1132,7 @@ pub(crate) fn read_info<R: BBIFileRead>(file: &mut R) -> Result<BBIFileInfo, BBI
         auto_sql_offset,
         total_summary_offset,
         uncompress_buf_size,
+        extension_offset,
     ) = match endianness {
         Endianness::Big => {
             let version = header_data.get_u16();
@@ -686,7 +1145,7 @@ pub(crate) fn read_info<R: BBIFileRead>(file: &mut R) -> Result<BBIFileInfo, BBI
             let auto_sql_offset = header_data.get_u64();
             let total_summary_offset = header_data.get_u64();
             let uncompress_buf_size = header_data.get_u32();
-            let _reserved = header_data.get_u64();
+            let extension_offset = header_data.get_u64();
 
             (
                 version,
@@ -699,6 +1158,7 @@ pub(crate) fn read_info<R: BBIFileRead>(file: &mut R) -> Result<BBIFileInfo, BBI
                 auto_sql_offset,
                 total_summary_offset,
                 uncompress_buf_size,
+                extension_offset,
             )
         }
         Endianness::Little => {
@@ -712,7 +1172,7 @@ pub(crate) fn read_info<R: BBIFileRead>(file: &mut R) -> Result<BBIFileInfo, BBI
             let auto_sql_offset = header_data.get_u64_le();
             let total_summary_offset = header_data.get_u64_le();
             let uncompress_buf_size = header_data.get_u32_le();
-            let _reserved = header_data.get_u64_le();
+            let extension_offset = header_data.get_u64_le();
 
             (
                 version,
@@ -725,6 +1185,7 @@ pub(crate) fn read_info<R: BBIFileRead>(file: &mut R) -> Result<BBIFileInfo, BBI
                 auto_sql_offset,
                 total_summary_offset,
                 uncompress_buf_size,
+                extension_offset,
             )
         }
     };
@@ -737,11 +1198,13 @@ pub(crate) fn read_info<R: BBIFileRead>(file: &mut R) -> Result<BBIFileInfo, BBI
         full_data_offset,
         full_index_offset,
         full_index_tree_offset: None,
+        items_per_slot: None,
         field_count,
         defined_field_count,
         auto_sql_offset,
         total_summary_offset,
         uncompress_buf_size,
+        extension_offset,
     };
 
     let zoom_headers = read_zoom_headers(file, &header)?;
@@ -952,12 +1415,12 @@ fn overlaps(
 
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct CirTreeNodeLeaf {
-    start_chrom_ix: u32,
-    start_base: u32,
-    end_chrom_ix: u32,
-    end_base: u32,
-    data_offset: u64,
-    data_size: u64,
+    pub(crate) start_chrom_ix: u32,
+    pub(crate) start_base: u32,
+    pub(crate) end_chrom_ix: u32,
+    pub(crate) end_base: u32,
+    pub(crate) data_offset: u64,
+    pub(crate) data_size: u64,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -1144,7 +1607,7 @@ fn cir_tree_non_leaf_items<R: SeekableRead>(
     endianness: Endianness,
     count: usize,
 ) -> io::Result<CirTreeNonLeafItemsIterator> {
-    let mut bytes = vec![0u8; (count as usize) * 32];
+    let mut bytes = vec![0u8; count * 24];
     file.read_exact(&mut bytes)?;
 
     Ok(CirTreeNonLeafItemsIterator {
@@ -1239,7 +1702,27 @@ pub(crate) fn read_node<R: SeekableRead>(
     Ok(iter)
 }
 
-fn nodes_overlapping<
+/// Reads every leaf item of the cir tree with its root node at `at`. Nodes
+/// are read breadth-first, so the items are in the order they are indexed.
+pub(crate) fn read_cir_tree_leaves<R: SeekableRead>(
+    endianness: Endianness,
+    file: &mut R,
+    at: u64,
+) -> io::Result<Vec<CirTreeNodeLeaf>> {
+    let mut leaves = vec![];
+    let mut remaining_nodes = VecDeque::from([at]);
+    while let Some(node_offset) = remaining_nodes.pop_front() {
+        match read_node(file, node_offset, endianness)? {
+            CirTreeNodeIterator::Leaf(items) => leaves.extend(items),
+            CirTreeNodeIterator::NonLeaf(items) => {
+                remaining_nodes.extend(items.map(|item| item.node_offset))
+            }
+        }
+    }
+    Ok(leaves)
+}
+
+pub(crate) fn nodes_overlapping<
     L: Iterator<Item = CirTreeNodeLeaf>,
     N: Iterator<Item = CirTreeNodeNonLeaf>,
 >(
@@ -1291,31 +1774,189 @@ fn nodes_overlapping<
     }
 }
 
+/// The most spare block data buffers kept for reuse by each thread.
+const MAX_SPARE_BLOCK_BUFFERS: usize = 16;
+
+/// Buffers with a larger capacity than this (e.g. from a large coalesced
+/// read) aren't kept for reuse, so that they don't stay allocated for the life
+/// of the thread.
+const MAX_SCRATCH_CAPACITY: usize = 1024 * 1024;
+
+/// Buffers that are reused between block reads, so that reading many blocks
+/// doesn't allocate (and zero) a new read and decompression buffer for each.
+/// The data of a block is decompressed (or read) straight into a spare buffer,
+/// which is handed out and can be given back with [`recycle_block_data`] once
+/// the block is decoded.
+struct BlockScratch {
+    decompressor: Decompressor,
+    raw: Vec<u8>,
+    spare: Vec<Vec<u8>>,
+}
+
+thread_local! {
+    static BLOCK_SCRATCH: RefCell<BlockScratch> = RefCell::new(BlockScratch {
+        decompressor: Decompressor::new(),
+        raw: vec![],
+        spare: vec![],
+    });
+}
+
+impl BlockScratch {
+    /// Takes an empty buffer to put the data of a block in.
+    fn take_buffer(&mut self) -> Vec<u8> {
+        let mut buf = self.spare.pop().unwrap_or_default();
+        buf.clear();
+        buf
+    }
+
+    /// Keeps `buf` for reuse, unless there are enough spare buffers already
+    /// or it is too large.
+    fn give_back(&mut self, buf: Vec<u8>) {
+        if self.spare.len() < MAX_SPARE_BLOCK_BUFFERS && buf.capacity() <= MAX_SCRATCH_CAPACITY {
+            self.spare.push(buf);
+        }
+    }
+
+    /// Reads `len` bytes into the raw buffer, then passes them to `f`.
+    fn with_raw<R: SeekableRead, T>(
+        &mut self,
+        read: &mut R,
+        len: usize,
+        f: impl FnOnce(&mut Self, &[u8]) -> io::Result<T>,
+    ) -> io::Result<T> {
+        let mut raw = std::mem::take(&mut self.raw);
+        raw.resize(len, 0);
+        let res = read.read_exact(&mut raw).and_then(|_| f(self, &raw));
+        if raw.capacity() <= MAX_SCRATCH_CAPACITY {
+            self.raw = raw;
+        }
+        res
+    }
+
+    /// Gets the data (uncompressed, if applicable) of a block from its raw
+    /// bytes.
+    fn block_data(&mut self, info: &BBIFileInfo, raw_data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut outbuf = self.take_buffer();
+        if info.header.uncompress_buf_size == 0 {
+            outbuf.extend_from_slice(raw_data);
+            return Ok(outbuf);
+        }
+        outbuf.resize(info.header.uncompress_buf_size as usize, 0);
+        // Some writers emit raw deflate blocks, without the zlib header. A raw
+        // deflate block can also happen to start like a zlib header, so fall
+        // back to raw deflate if zlib decoding fails.
+        let decompressed = if has_zlib_header(raw_data) {
+            self.decompressor
+                .zlib_decompress(raw_data, &mut outbuf)
+                .or_else(|_| self.decompressor.deflate_decompress(raw_data, &mut outbuf))
+        } else {
+            self.decompressor.deflate_decompress(raw_data, &mut outbuf)
+        };
+        match decompressed {
+            Ok(decompressed) => {
+                outbuf.truncate(decompressed);
+                Ok(outbuf)
+            }
+            Err(e) => {
+                self.give_back(outbuf);
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    DecompressionError(e.to_string()),
+                ))
+            }
+        }
+    }
+}
+
+/// Gives the data of a block, once it is no longer needed, back to the
+/// buffers of this thread that block data is read into.
+pub(crate) fn recycle_block_data(data: Vec<u8>) {
+    let _ = BLOCK_SCRATCH.try_with(|scratch| {
+        if let Ok(mut scratch) = scratch.try_borrow_mut() {
+            scratch.give_back(data);
+        }
+    });
+}
+
+/// Copies `data` into a reused block data buffer.
+fn copy_block_data(data: &[u8]) -> Vec<u8> {
+    let mut buf = BLOCK_SCRATCH
+        .try_with(|scratch| scratch.try_borrow_mut().map(|mut s| s.take_buffer()))
+        .ok()
+        .and_then(|buf| buf.ok())
+        .unwrap_or_default();
+    buf.extend_from_slice(data);
+    buf
+}
+
+/// Whether `data` starts with a zlib header: deflate compression (`CM` of
+/// `8`), and a header checksum that is a multiple of 31.
+fn has_zlib_header(data: &[u8]) -> bool {
+    match data {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && u16::from_be_bytes([*cmf, *flg]).is_multiple_of(31),
+        _ => false,
+    }
+}
+
 /// Gets the data (uncompressed, if applicable) from a given block
-fn read_block_data<R: SeekableRead>(
+pub(crate) fn read_block_data<R: SeekableRead>(
     info: &BBIFileInfo,
     read: &mut R,
     block: &Block,
 ) -> io::Result<Vec<u8>> {
-    let uncompress_buf_size = info.header.uncompress_buf_size as usize;
-
     read.seek(SeekFrom::Start(block.offset))?;
 
-    let mut raw_data = vec![0u8; block.size as usize];
-    read.read_exact(&mut raw_data)?;
-    let block_data: Vec<u8> = if uncompress_buf_size > 0 {
-        let mut decompressor = Decompressor::new();
-        let mut outbuf = vec![0; uncompress_buf_size];
-        let decompressed = decompressor
-            .zlib_decompress(&raw_data, &mut outbuf)
-            .unwrap();
-        outbuf.truncate(decompressed);
-        outbuf
-    } else {
-        raw_data
-    };
+    BLOCK_SCRATCH.with(|scratch| {
+        let mut scratch = scratch.borrow_mut();
+        if info.header.uncompress_buf_size == 0 {
+            // Read uncompressed data straight into the block's buffer
+            let mut data = scratch.take_buffer();
+            data.resize(block.size as usize, 0);
+            return match read.read_exact(&mut data) {
+                Ok(()) => Ok(data),
+                Err(e) => {
+                    scratch.give_back(data);
+                    Err(e)
+                }
+            };
+        }
+        scratch.with_raw(read, block.size as usize, |scratch, raw_data| {
+            scratch.block_data(info, raw_data)
+        })
+    })
+}
 
-    Ok(block_data)
+/// Reads the data for a set of blocks, sorted by offset, with a single read
+/// spanning from the start of the first block to the end of the last.
+fn read_coalesced_block_data<R: SeekableRead>(
+    info: &BBIFileInfo,
+    read: &mut R,
+    blocks: &[Block],
+) -> io::Result<Vec<Vec<u8>>> {
+    let (first, last) = match (blocks.first(), blocks.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Ok(vec![]),
+    };
+    if blocks.len() == 1 {
+        return Ok(vec![read_block_data(info, read, first)?]);
+    }
+    read.seek(SeekFrom::Start(first.offset))?;
+
+    let len = (last.offset + last.size - first.offset) as usize;
+    BLOCK_SCRATCH.with(|scratch| {
+        scratch
+            .borrow_mut()
+            .with_raw(read, len, |scratch, raw_data| {
+                blocks
+                    .iter()
+                    .map(|block| {
+                        let block_start = (block.offset - first.offset) as usize;
+                        let block_data = &raw_data[block_start..block_start + block.size as usize];
+                        scratch.block_data(info, block_data)
+                    })
+                    .collect()
+            })
+    })
 }
 
 pub(crate) fn get_zoom_block_values<B: BBIRead>(
@@ -1328,8 +1969,7 @@ pub(crate) fn get_zoom_block_values<B: BBIRead>(
 ) -> Result<std::vec::IntoIter<ZoomRecord>, BBIReadError> {
     let (read, info) = bbifile.reader_and_info();
     let data = read.get_block_data(info, &block)?;
-    let mut bytes = BytesMut::with_capacity(data.len());
-    bytes.extend_from_slice(&data);
+    let mut bytes = &data[..];
 
     let len = bytes.len();
     assert_eq!(len % (4 * 8), 0);
@@ -1395,6 +2035,7 @@ pub(crate) fn get_zoom_block_values<B: BBIRead>(
         }
     }
 
+    recycle_block_data(data);
     *known_offset = block.offset + block.size;
     Ok(records.into_iter())
 }
@@ -1484,3 +2125,71 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_coalesced_blocks() {
+        let block = |offset, size| Block { offset, size };
+        let options = BBIReadOptions {
+            max_coalesce_gap: 10,
+            max_coalesce_bytes: 100,
+            ..Default::default()
+        };
+        let mut blocks = vec![
+            // Contiguous, then a gap of 10
+            block(0, 40),
+            block(40, 20),
+            block(70, 30),
+            // Would make the run span 101 bytes
+            block(100, 1),
+            // A gap of 11
+            block(112, 10),
+            // Larger than the limit on its own
+            block(122, 200),
+        ]
+        .into_iter();
+        assert_eq!(
+            next_coalesced_blocks(&mut blocks, &options),
+            [block(0, 40), block(40, 20), block(70, 30)]
+        );
+        assert_eq!(
+            next_coalesced_blocks(&mut blocks, &options),
+            [block(100, 1)]
+        );
+        assert_eq!(
+            next_coalesced_blocks(&mut blocks, &options),
+            [block(112, 10)]
+        );
+        assert_eq!(
+            next_coalesced_blocks(&mut blocks, &options),
+            [block(122, 200)]
+        );
+        assert!(next_coalesced_blocks(&mut blocks, &options).is_empty());
+    }
+
+    #[test]
+    fn test_read_non_leaf_node() {
+        // A non-leaf node with two items, with nothing after it
+        let mut node = vec![0u8, 0];
+        node.extend(2u16.to_le_bytes());
+        for (start, end, offset) in [(0u32, 100u32, 1000u64), (100, 200, 2000)] {
+            node.extend(0u32.to_le_bytes());
+            node.extend(start.to_le_bytes());
+            node.extend(0u32.to_le_bytes());
+            node.extend(end.to_le_bytes());
+            node.extend(offset.to_le_bytes());
+        }
+        assert_eq!(node.len(), 4 + 2 * 24);
+        let mut file = io::Cursor::new(node);
+        let CirTreeNodeIterator::NonLeaf(items) =
+            read_node(&mut file, 0, Endianness::Little).unwrap()
+        else {
+            panic!("Expected a non-leaf node.");
+        };
+        let offsets: Vec<_> = items.map(|item| item.node_offset).collect();
+        assert_eq!(offsets, [1000, 2000]);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
 use std::fs::File;
 use std::future::Future;
@@ -6,7 +6,8 @@ use std::io::{self, BufWriter, Seek, SeekFrom, Write};
 use std::iter::Flatten;
 use std::vec;
 
-use byteorder::{NativeEndian, WriteBytesExt};
+use byteorder::WriteBytesExt;
+use byteordered::{Endian, Endianness};
 use crossbeam_channel::unbounded;
 use thiserror::Error;
 
@@ -72,8 +73,28 @@ pub enum InputSortType {
     //NONE,
 }
 
+/// How overlapping values on a chromosome are handled when writing a bigWig
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OnOverlap {
+    /// Writing fails with `BBIProcessError::InvalidInput`
+    #[default]
+    Error,
+    /// Overlapping values are split at their starts and ends, and each piece
+    /// takes the maximum of the values covering it
+    Max,
+    /// As `Max`, but each piece takes the sum of the values covering it
+    Sum,
+    /// As `Max`, but each piece takes the mean of the values covering it
+    Mean,
+    /// As `Max`, but each piece takes the value covering it that comes last
+    /// in the input
+    Last,
+}
+
 /// The default block size used when writing a bbi file
 pub const DEFAULT_BLOCK_SIZE: u32 = 256;
+/// The default zlib compression level used when writing a bbi file
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
 /// The default items per slot used when writing a bbi file
 pub const DEFAULT_ITEMS_PER_SLOT: u32 = 1024;
 
@@ -81,31 +102,432 @@ pub const DEFAULT_ITEMS_PER_SLOT: u32 = 1024;
 #[derive(Clone)]
 pub struct BBIWriteOptions {
     pub compress: bool,
+    /// The zlib compression level to use for data and zoom blocks: `0` writes
+    /// uncompressed blocks and `1`-`9` trade speed for file size. Levels above
+    /// `9` are treated as `9`. If `None`, the default level is used, unless
+    /// `compress` is `false`.
+    pub compression_level: Option<u32>,
+    /// The maximum number of items in a data or zoom block. A new block is
+    /// only started when the current block is full or at the start of a
+    /// chromosome (blocks can't span chromosomes), so every block but the
+    /// last of each chromosome is full, even for sparse data.
     pub items_per_slot: u32,
+    /// The maximum number of children of each node of the cir tree indices.
     pub block_size: u32,
     /// The initial zoom size to use when writing in a single pass.
     pub initial_zoom_size: u32,
     pub max_zooms: u32,
     /// The zooms sizes to use. Overrides both initial_zoom_size and max_zooms.
+    /// These must be non-zero and strictly increasing, the first must be at
+    /// least the average item size of the data, and the written file will
+    /// have exactly these zoom levels.
     pub manual_zoom_sizes: Option<Vec<u32>>,
     pub input_sort_type: InputSortType,
+    /// How overlapping values are handled. Only used when writing bigWigs.
+    pub on_overlap: OnOverlap,
     pub channel_size: usize,
     pub inmemory: bool,
+    /// The byte order the file is written in. Defaults to the native byte
+    /// order. Readers detect the byte order from the magic.
+    pub endianness: Endianness,
+    /// Pre-assigned chromosome ids (for example, to match another file),
+    /// rather than assigning ids in the order chromosomes are seen. Every
+    /// chromosome in the data must have an id, and every chromosome with an
+    /// id must be in the chromosome sizes; all of them are written to the
+    /// chromosome tree. The index requires the data to be sorted by id, so
+    /// chromosomes must be written in increasing order of their ids.
+    pub chrom_ids: Option<HashMap<String, u32>>,
+    /// If set, it is an error for a chromosome in the chromosome sizes to not
+    /// have any data. This catches naming mismatches (e.g. `chr1` vs `1`)
+    /// between the data and chromosome sizes. Chromosomes in the data but not
+    /// in the chromosome sizes are always an error.
+    pub strict_chroms: bool,
+    /// If set, zero-width values (bigWig) or entries (bigBed), where the
+    /// start equals the end, are dropped rather than written.
+    pub drop_zero_width: bool,
+    /// If set, zoom levels are computed in parallel on the runtime, with each
+    /// level accumulated by its own task over batches of values. This can
+    /// reduce write time for large files on a multi-threaded runtime; the
+    /// written file is identical either way. Only used when writing bigWigs.
+    pub parallel_zooms: bool,
+    /// If set, bigWig data blocks whose values all have the same span are
+    /// written as `variableStep` sections (type 2), or as `fixedStep`
+    /// sections (type 3) if the values are also evenly spaced, rather than
+    /// as `bedGraph` sections (type 1). This preserves the step and span of
+    /// regularly spaced data and makes the file smaller.
+    pub step_sections: bool,
 }
 
 impl Default for BBIWriteOptions {
     fn default() -> Self {
         BBIWriteOptions {
             compress: true,
+            compression_level: None,
             items_per_slot: DEFAULT_ITEMS_PER_SLOT,
             block_size: DEFAULT_BLOCK_SIZE,
             initial_zoom_size: 160,
             max_zooms: 10,
             manual_zoom_sizes: None,
             input_sort_type: InputSortType::ALL,
+            on_overlap: OnOverlap::default(),
             channel_size: 100,
             inmemory: false,
+            endianness: Endianness::native(),
+            chrom_ids: None,
+            strict_chroms: false,
+            drop_zero_width: false,
+            parallel_zooms: false,
+            step_sections: false,
+        }
+    }
+}
+
+impl BBIWriteOptions {
+    /// The zlib compression level that blocks are written with, where `0`
+    /// means uncompressed.
+    pub(crate) fn effective_compression_level(&self) -> u32 {
+        match (self.compression_level, self.compress) {
+            (Some(level), _) => level.min(9),
+            (None, true) => DEFAULT_COMPRESSION_LEVEL,
+            (None, false) => 0,
+        }
+    }
+
+    /// Checks that `chrom_ids`, if set, are unique and only for chromosomes
+    /// in `chrom_sizes`.
+    pub(crate) fn validate_chrom_ids<E: Error>(
+        &self,
+        chrom_sizes: &HashMap<String, u32>,
+    ) -> Result<(), BBIProcessError<E>> {
+        let Some(chrom_ids) = &self.chrom_ids else {
+            return Ok(());
+        };
+        if let Some(chrom) = chrom_ids.keys().find(|c| !chrom_sizes.contains_key(*c)) {
+            return Err(BBIProcessError::InvalidChromosome(format!(
+                "Chromosome with a pre-assigned id isn't in the chrom sizes: {}",
+                chrom
+            )));
         }
+        let unique_ids: HashSet<u32> = chrom_ids.values().copied().collect();
+        if unique_ids.len() != chrom_ids.len() {
+            return Err(BBIProcessError::InvalidInput(
+                "Pre-assigned chromosome ids must be unique.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// If `strict_chroms` is set, checks that every chromosome in
+    /// `chrom_sizes` was seen in the data.
+    pub(crate) fn check_unseen_chroms<E: Error>(
+        &self,
+        chrom_sizes: &HashMap<String, u32>,
+        seen_chroms: &HashSet<String>,
+    ) -> Result<(), BBIProcessError<E>> {
+        if !self.strict_chroms {
+            return Ok(());
+        }
+        let mut unseen: Vec<&str> = chrom_sizes
+            .keys()
+            .filter(|c| !seen_chroms.contains(*c))
+            .map(|c| c.as_str())
+            .collect();
+        if unseen.is_empty() {
+            return Ok(());
+        }
+        unseen.sort_unstable();
+        Err(BBIProcessError::InvalidChromosome(format!(
+            "Chromosomes in the chrom sizes don't have any data: {}",
+            unseen.join(", ")
+        )))
+    }
+
+    /// If `manual_zoom_sizes` is set, checks that the first zoom size is at
+    /// least the average item size of the data in `summary`. Smaller zoom
+    /// levels would have about as many records as the data itself.
+    ///
+    /// This depends on the data, so can only be checked once the data has
+    /// been processed. Callers must wait for any pending writes to finish
+    /// before returning this error.
+    pub(crate) fn check_first_zoom_size<E: Error>(
+        &self,
+        summary: &Summary,
+    ) -> Result<(), BBIProcessError<E>> {
+        let Some(average_size) = summary.bases_covered.checked_div(summary.total_items) else {
+            return Ok(());
+        };
+        let average_size = average_size.min(u64::from(u32::MAX)) as u32;
+        match self.manual_zoom_sizes.as_deref() {
+            Some([first, ..]) if *first < average_size => {
+                Err(BBIProcessError::InvalidInput(format!(
+                    "The first zoom size ({}) must be at least the average item size ({}).",
+                    first, average_size
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether a value or entry from `start` to `end` is dropped rather than
+    /// written (see `drop_zero_width`).
+    pub(crate) fn drops(&self, start: u32, end: u32) -> bool {
+        self.drop_zero_width && start == end
+    }
+
+    /// The `IdMap` to assign chromosome ids with.
+    pub(crate) fn chrom_id_map(&self) -> IdMap {
+        match &self.chrom_ids {
+            Some(chrom_ids) => IdMap::with_ids(chrom_ids.clone()),
+            None => IdMap::default(),
+        }
+    }
+
+    /// Creates a [`BBIWriteOptionsBuilder`], starting from the default options.
+    pub fn builder() -> BBIWriteOptionsBuilder {
+        BBIWriteOptionsBuilder::default()
+    }
+
+    /// Checks that the options can be used to write a file: `items_per_slot`
+    /// and `block_size` must be non-zero, and `manual_zoom_sizes`, if set,
+    /// must be non-zero, strictly increasing, and fit in the header.
+    pub(crate) fn validate(&self) -> Result<(), BBIWriteOptionsError> {
+        if self.items_per_slot == 0 {
+            return Err(BBIWriteOptionsError::InvalidOption(
+                "Items per slot must be greater than zero.".to_string(),
+            ));
+        }
+        if self.block_size == 0 {
+            return Err(BBIWriteOptionsError::InvalidOption(
+                "Block size must be greater than zero.".to_string(),
+            ));
+        }
+        let Some(zooms) = &self.manual_zoom_sizes else {
+            return Ok(());
+        };
+        if zooms.first() == Some(&0) {
+            return Err(BBIWriteOptionsError::InvalidOption(
+                "Zoom sizes must be greater than zero.".to_string(),
+            ));
+        }
+        if zooms.len() > MAX_ZOOM_LEVELS {
+            return Err(BBIWriteOptionsError::InvalidOption(format!(
+                "At most {} zoom sizes are supported: {:?}",
+                MAX_ZOOM_LEVELS, zooms
+            )));
+        }
+        if zooms.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(BBIWriteOptionsError::InvalidOption(format!(
+                "Zoom sizes must be strictly increasing: {:?}",
+                zooms
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Builds a [`BBIWriteOptions`], starting from the default options. Unlike
+/// constructing the options directly, the options are validated by `build`.
+///
+/// ```
+/// # use bigtools::{BBIWriteOptions, InputSortType};
+/// let options = BBIWriteOptions::builder()
+///     .compression_level(9)
+///     .manual_zoom_sizes(vec![100, 1000, 10000])
+///     .input_sort_type(InputSortType::START)
+///     .build()
+///     .unwrap();
+/// assert_eq!(options.manual_zoom_sizes, Some(vec![100, 1000, 10000]));
+/// ```
+#[derive(Clone, Default)]
+pub struct BBIWriteOptionsBuilder {
+    options: BBIWriteOptions,
+}
+
+impl BBIWriteOptionsBuilder {
+    /// Sets whether data and zoom blocks are compressed.
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.options.compress = compress;
+        self
+    }
+
+    /// Sets the zlib compression level. See [`BBIWriteOptions::compression_level`].
+    pub fn compression_level(mut self, level: u32) -> Self {
+        self.options.compression_level = Some(level);
+        self
+    }
+
+    /// Sets the maximum number of items in a data or zoom block.
+    pub fn items_per_slot(mut self, items_per_slot: u32) -> Self {
+        self.options.items_per_slot = items_per_slot;
+        self
+    }
+
+    /// Sets the maximum number of children of each cir tree index node.
+    pub fn block_size(mut self, block_size: u32) -> Self {
+        self.options.block_size = block_size;
+        self
+    }
+
+    /// Sets the initial zoom size used when writing in a single pass.
+    pub fn initial_zoom_size(mut self, initial_zoom_size: u32) -> Self {
+        self.options.initial_zoom_size = initial_zoom_size;
+        self
+    }
+
+    /// Sets the maximum number of zoom levels.
+    pub fn max_zooms(mut self, max_zooms: u32) -> Self {
+        self.options.max_zooms = max_zooms;
+        self
+    }
+
+    /// Sets the exact zoom sizes to write. See [`BBIWriteOptions::manual_zoom_sizes`].
+    pub fn manual_zoom_sizes(mut self, zoom_sizes: Vec<u32>) -> Self {
+        self.options.manual_zoom_sizes = Some(zoom_sizes);
+        self
+    }
+
+    /// Sets how the input is expected to be sorted.
+    pub fn input_sort_type(mut self, input_sort_type: InputSortType) -> Self {
+        self.options.input_sort_type = input_sort_type;
+        self
+    }
+
+    /// Sets how overlapping values are handled when writing bigWigs.
+    pub fn on_overlap(mut self, on_overlap: OnOverlap) -> Self {
+        self.options.on_overlap = on_overlap;
+        self
+    }
+
+    /// Sets the size of the channels used to send sections to be written.
+    pub fn channel_size(mut self, channel_size: usize) -> Self {
+        self.options.channel_size = channel_size;
+        self
+    }
+
+    /// Sets whether temporary data is kept in memory, rather than in
+    /// temporary files.
+    pub fn inmemory(mut self, inmemory: bool) -> Self {
+        self.options.inmemory = inmemory;
+        self
+    }
+
+    /// Sets the byte order the file is written in.
+    pub fn endianness(mut self, endianness: Endianness) -> Self {
+        self.options.endianness = endianness;
+        self
+    }
+
+    /// Sets pre-assigned chromosome ids. See [`BBIWriteOptions::chrom_ids`].
+    pub fn chrom_ids(mut self, chrom_ids: HashMap<String, u32>) -> Self {
+        self.options.chrom_ids = Some(chrom_ids);
+        self
+    }
+
+    /// Sets whether chromosomes without data are an error. See
+    /// [`BBIWriteOptions::strict_chroms`].
+    pub fn strict_chroms(mut self, strict_chroms: bool) -> Self {
+        self.options.strict_chroms = strict_chroms;
+        self
+    }
+
+    /// Sets whether zero-width values or entries are dropped. See
+    /// [`BBIWriteOptions::drop_zero_width`].
+    pub fn drop_zero_width(mut self, drop_zero_width: bool) -> Self {
+        self.options.drop_zero_width = drop_zero_width;
+        self
+    }
+
+    /// Sets whether bigWig zoom levels are computed in parallel. See
+    /// [`BBIWriteOptions::parallel_zooms`].
+    pub fn parallel_zooms(mut self, parallel_zooms: bool) -> Self {
+        self.options.parallel_zooms = parallel_zooms;
+        self
+    }
+
+    /// Sets whether uniformly spaced bigWig data is written as `fixedStep` or
+    /// `variableStep` sections. See [`BBIWriteOptions::step_sections`].
+    pub fn step_sections(mut self, step_sections: bool) -> Self {
+        self.options.step_sections = step_sections;
+        self
+    }
+
+    /// Validates and returns the options.
+    pub fn build(self) -> Result<BBIWriteOptions, BBIWriteOptionsError> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
+
+/// An error for invalid [`BBIWriteOptions`]
+#[derive(Error, Debug)]
+pub enum BBIWriteOptionsError {
+    #[error("{}", .0)]
+    InvalidOption(String),
+}
+
+/// A rough estimate of the size of a bbi file, before it is written. See
+/// [`BigWigWrite::estimate_size`][crate::BigWigWrite::estimate_size] and
+/// [`BigBedWrite::estimate_size`][crate::BigBedWrite::estimate_size].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct WriteSizeEstimate {
+    /// The number of full data blocks
+    pub estimated_blocks: u64,
+    /// The number of zoom records, summed over all zoom levels
+    pub estimated_zoom_records: u64,
+    /// The total size of the file, in bytes
+    pub estimated_bytes: u64,
+}
+
+/// Estimates the size of a bbi file with `num_records` items of about
+/// `item_size` (uncompressed) bytes each. This assumes that the records are
+/// non-overlapping and that zoom levels are those used for a single-pass write.
+pub(crate) fn estimate_size(
+    options: &BBIWriteOptions,
+    chrom_sizes: &HashMap<String, u32>,
+    num_records: u64,
+    item_size: u64,
+) -> WriteSizeEstimate {
+    const BLOCK_HEADER_SIZE: u64 = 24;
+    const ZOOM_RECORD_SIZE: u64 = 32;
+    const RTREE_ITEM_SIZE: u64 = 32;
+
+    let items_per_slot = u64::from(options.items_per_slot.max(1));
+    let estimated_blocks = num_records.div_ceil(items_per_slot);
+
+    let zoom_sizes: Vec<u32> = match &options.manual_zoom_sizes {
+        Some(zooms) => zooms.clone(),
+        None => std::iter::successors(Some(options.initial_zoom_size), |z| z.checked_mul(4))
+            .take(options.max_zooms as usize)
+            .collect(),
+    };
+    let mut estimated_zoom_records = 0;
+    let mut estimated_zoom_blocks = 0;
+    for size in zoom_sizes.iter().copied().filter(|z| *z != 0) {
+        // There's at most one zoom record per zoom-sized bin, and at most one
+        // per record.
+        let bins: u64 = chrom_sizes
+            .values()
+            .map(|length| u64::from(length.div_ceil(size)))
+            .sum();
+        let records = bins.min(num_records);
+        estimated_zoom_records += records;
+        estimated_zoom_blocks += records.div_ceil(items_per_slot);
+    }
+
+    let mut data_bytes = estimated_blocks * BLOCK_HEADER_SIZE
+        + num_records * item_size
+        + estimated_zoom_records * ZOOM_RECORD_SIZE;
+    if options.effective_compression_level() > 0 {
+        data_bytes /= 2; // Estimate as kent does
+    }
+    let index_bytes = (estimated_blocks + estimated_zoom_blocks) * RTREE_ITEM_SIZE;
+    let chrom_tree_bytes: u64 = chrom_sizes.keys().map(|chrom| chrom.len() as u64 + 8).sum();
+    let header_bytes = 64 + 24 * zoom_sizes.len() as u64 + 40;
+
+    WriteSizeEstimate {
+        estimated_blocks,
+        estimated_zoom_records,
+        estimated_bytes: header_bytes + chrom_tree_bytes + data_bytes + index_bytes,
     }
 }
 
@@ -122,6 +544,14 @@ pub enum BBIProcessError<SourceError: Error> {
     SourceError(SourceError),
 }
 
+impl<E: Error> From<BBIWriteOptionsError> for BBIProcessError<E> {
+    fn from(value: BBIWriteOptionsError) -> Self {
+        match value {
+            BBIWriteOptionsError::InvalidOption(e) => BBIProcessError::InvalidInput(e),
+        }
+    }
+}
+
 impl<E: Error> From<ProcessDataError> for BBIProcessError<E> {
     fn from(value: ProcessDataError) -> Self {
         match value {
@@ -171,42 +601,43 @@ pub(crate) fn write_info<W: Write + Seek + Send + 'static>(
     zoom_entries: Vec<ZoomHeader>,
     summary: Summary,
     data_count: u64,
+    endianness: Endianness,
 ) -> Result<(), ProcessDataError> {
     file.seek(SeekFrom::Start(0))?;
-    file.write_u32::<NativeEndian>(magic)?;
-    file.write_u16::<NativeEndian>(4)?;
-    file.write_u16::<NativeEndian>(num_zooms)?;
-    file.write_u64::<NativeEndian>(chrom_index_start)?;
-    file.write_u64::<NativeEndian>(full_data_offset)?;
-    file.write_u64::<NativeEndian>(index_start)?;
-    file.write_u16::<NativeEndian>(field_count)?; // fieldCount
-    file.write_u16::<NativeEndian>(defined_field_count)?; // definedFieldCount
-    file.write_u64::<NativeEndian>(auto_sql_offset)?; // autoSQLOffset
-    file.write_u64::<NativeEndian>(total_summary_offset)?;
-    file.write_u32::<NativeEndian>(uncompress_buf_size as u32)?;
-    file.write_u64::<NativeEndian>(0)?; // reserved
+    endianness.write_u32(&mut *file, magic)?;
+    endianness.write_u16(&mut *file, 4)?;
+    endianness.write_u16(&mut *file, num_zooms)?;
+    endianness.write_u64(&mut *file, chrom_index_start)?;
+    endianness.write_u64(&mut *file, full_data_offset)?;
+    endianness.write_u64(&mut *file, index_start)?;
+    endianness.write_u16(&mut *file, field_count)?; // fieldCount
+    endianness.write_u16(&mut *file, defined_field_count)?; // definedFieldCount
+    endianness.write_u64(&mut *file, auto_sql_offset)?; // autoSQLOffset
+    endianness.write_u64(&mut *file, total_summary_offset)?;
+    endianness.write_u32(&mut *file, uncompress_buf_size as u32)?;
+    endianness.write_u64(&mut *file, 0)?; // reserved
 
     debug_assert!(file.seek(SeekFrom::Current(0))? == 64);
 
     for zoom_entry in zoom_entries {
-        file.write_u32::<NativeEndian>(zoom_entry.reduction_level)?;
-        file.write_u32::<NativeEndian>(0)?;
-        file.write_u64::<NativeEndian>(zoom_entry.data_offset)?;
-        file.write_u64::<NativeEndian>(zoom_entry.index_offset)?;
+        endianness.write_u32(&mut *file, zoom_entry.reduction_level)?;
+        endianness.write_u32(&mut *file, 0)?;
+        endianness.write_u64(&mut *file, zoom_entry.data_offset)?;
+        endianness.write_u64(&mut *file, zoom_entry.index_offset)?;
     }
 
     file.seek(SeekFrom::Start(total_summary_offset))?;
-    file.write_u64::<NativeEndian>(summary.bases_covered)?;
-    file.write_f64::<NativeEndian>(summary.min_val)?;
-    file.write_f64::<NativeEndian>(summary.max_val)?;
-    file.write_f64::<NativeEndian>(summary.sum)?;
-    file.write_f64::<NativeEndian>(summary.sum_squares)?;
+    endianness.write_u64(&mut *file, summary.bases_covered)?;
+    endianness.write_f64(&mut *file, summary.min_val)?;
+    endianness.write_f64(&mut *file, summary.max_val)?;
+    endianness.write_f64(&mut *file, summary.sum)?;
+    endianness.write_f64(&mut *file, summary.sum_squares)?;
 
     file.seek(SeekFrom::Start(full_data_offset))?;
-    file.write_u64::<NativeEndian>(data_count)?;
+    endianness.write_u64(&mut *file, data_count)?;
 
     file.seek(SeekFrom::End(0))?;
-    file.write_u32::<NativeEndian>(magic)?;
+    endianness.write_u32(&mut *file, magic)?;
 
     Ok(())
 }
@@ -215,6 +646,7 @@ pub(crate) fn write_chrom_tree<W: Write + Seek + Send + 'static>(
     file: &mut BufWriter<W>,
     chrom_sizes: std::collections::HashMap<String, u32>,
     chrom_ids: &std::collections::HashMap<String, u32>,
+    endianness: Endianness,
 ) -> io::Result<()> {
     let mut chroms: Vec<(&String, &u32)> = chrom_ids.iter().collect();
     chroms.sort_by_key(|v| *v.1);
@@ -229,34 +661,35 @@ pub(crate) fn write_chrom_tree<W: Write + Seek + Send + 'static>(
         .map(|a| a.0.as_bytes().len() as u32)
         .fold(0, u32::max);
 
-    file.write_u32::<NativeEndian>(CHROM_TREE_MAGIC)?;
-    file.write_u32::<NativeEndian>(block_size)?;
-    file.write_u32::<NativeEndian>(max_bytes)?;
-    file.write_u32::<NativeEndian>(8)?; // size of Id (u32) + Size (u32)
-    file.write_u64::<NativeEndian>(item_count)?;
-    file.write_u64::<NativeEndian>(0)?; // Reserved
+    endianness.write_u32(&mut *file, CHROM_TREE_MAGIC)?;
+    endianness.write_u32(&mut *file, block_size)?;
+    endianness.write_u32(&mut *file, max_bytes)?;
+    endianness.write_u32(&mut *file, 8)?; // size of Id (u32) + Size (u32)
+    endianness.write_u64(&mut *file, item_count)?;
+    endianness.write_u64(&mut *file, 0)?; // Reserved
 
     // Assuming this is all one block right now
     // TODO: add non-leaf nodes and split blocks
     file.write_u8(1)?;
     file.write_u8(0)?;
-    file.write_u16::<NativeEndian>(item_count as u16)?;
+    endianness.write_u16(&mut *file, item_count as u16)?;
     for (chrom, id) in chroms {
         let key_bytes = &mut vec![0u8; max_bytes as usize];
         let chrom_bytes = chrom.as_bytes();
         key_bytes[..chrom_bytes.len()].copy_from_slice(chrom_bytes);
         file.write_all(key_bytes)?;
-        file.write_u32::<NativeEndian>(*id)?;
+        endianness.write_u32(&mut *file, *id)?;
         let length = chrom_sizes
             .get(&chrom[..])
             .expect(&format!("Expected length for chrom: {}", chrom));
-        file.write_u32::<NativeEndian>(*length)?;
+        endianness.write_u32(&mut *file, *length)?;
     }
     Ok(())
 }
 
 pub(crate) async fn encode_zoom_section(
-    compress: bool,
+    compression_level: u32,
+    endianness: Endianness,
     items_in_section: Vec<ZoomRecord>,
 ) -> io::Result<(SectionData, usize)> {
     use libdeflater::{CompressionLvl, Compressor};
@@ -268,18 +701,19 @@ pub(crate) async fn encode_zoom_section(
 
     let chrom = items_in_section[0].chrom;
     for item in items_in_section.iter() {
-        bytes.write_u32::<NativeEndian>(item.chrom)?;
-        bytes.write_u32::<NativeEndian>(item.start)?;
-        bytes.write_u32::<NativeEndian>(item.end)?;
-        bytes.write_u32::<NativeEndian>(item.summary.bases_covered as u32)?;
-        bytes.write_f32::<NativeEndian>(item.summary.min_val as f32)?;
-        bytes.write_f32::<NativeEndian>(item.summary.max_val as f32)?;
-        bytes.write_f32::<NativeEndian>(item.summary.sum as f32)?;
-        bytes.write_f32::<NativeEndian>(item.summary.sum_squares as f32)?;
+        endianness.write_u32(&mut bytes, item.chrom)?;
+        endianness.write_u32(&mut bytes, item.start)?;
+        endianness.write_u32(&mut bytes, item.end)?;
+        endianness.write_u32(&mut bytes, item.summary.bases_covered as u32)?;
+        endianness.write_f32(&mut bytes, item.summary.min_val as f32)?;
+        endianness.write_f32(&mut bytes, item.summary.max_val as f32)?;
+        endianness.write_f32(&mut bytes, item.summary.sum as f32)?;
+        endianness.write_f32(&mut bytes, item.summary.sum_squares as f32)?;
     }
 
-    let (out_bytes, uncompressed_buf_size) = if compress {
-        let mut compressor = Compressor::new(CompressionLvl::default());
+    let (out_bytes, uncompressed_buf_size) = if compression_level > 0 {
+        let level = CompressionLvl::new(compression_level as i32).unwrap_or_default();
+        let mut compressor = Compressor::new(level);
         let max_sz = compressor.zlib_compress_bound(bytes.len());
         let mut compressed_data = vec![0; max_sz];
         let actual_sz = compressor
@@ -390,6 +824,7 @@ fn write_tree<W: Write>(
     childnode_offset: u64,
     options: &BBIWriteOptions,
 ) -> io::Result<u64> {
+    let endianness = options.endianness;
     let non_leafnode_full_block_size: u64 =
         NODEHEADER_SIZE + NON_LEAFNODE_SIZE * u64::from(options.block_size);
     let leafnode_full_block_size: u64 =
@@ -422,21 +857,21 @@ fn write_tree<W: Write>(
         RTreeChildren::DataSections(sections) => {
             file.write_u8(1)?;
             file.write_u8(0)?;
-            file.write_u16::<NativeEndian>(sections.len() as u16)?;
+            endianness.write_u16(&mut *file, sections.len() as u16)?;
             for section in sections {
-                file.write_u32::<NativeEndian>(section.chrom)?;
-                file.write_u32::<NativeEndian>(section.start)?;
-                file.write_u32::<NativeEndian>(section.chrom)?;
-                file.write_u32::<NativeEndian>(section.end)?;
-                file.write_u64::<NativeEndian>(section.offset)?;
-                file.write_u64::<NativeEndian>(section.size)?;
+                endianness.write_u32(&mut *file, section.chrom)?;
+                endianness.write_u32(&mut *file, section.start)?;
+                endianness.write_u32(&mut *file, section.chrom)?;
+                endianness.write_u32(&mut *file, section.end)?;
+                endianness.write_u64(&mut *file, section.offset)?;
+                endianness.write_u64(&mut *file, section.size)?;
             }
             Ok(4 + sections.len() as u64 * 32)
         }
         RTreeChildren::Nodes(children) => {
             file.write_u8(0)?;
             file.write_u8(0)?;
-            file.write_u16::<NativeEndian>(children.len() as u16)?;
+            endianness.write_u16(&mut *file, children.len() as u16)?;
             let full_size = if (curr_level - 1) > 0 {
                 non_leafnode_full_block_size
             } else {
@@ -444,11 +879,11 @@ fn write_tree<W: Write>(
             };
             for (idx, child) in children.iter().enumerate() {
                 let child_offset: u64 = childnode_offset + idx as u64 * full_size;
-                file.write_u32::<NativeEndian>(child.start_chrom_idx)?;
-                file.write_u32::<NativeEndian>(child.start_base)?;
-                file.write_u32::<NativeEndian>(child.end_chrom_idx)?;
-                file.write_u32::<NativeEndian>(child.end_base)?;
-                file.write_u64::<NativeEndian>(child_offset)?;
+                endianness.write_u32(&mut *file, child.start_chrom_idx)?;
+                endianness.write_u32(&mut *file, child.start_base)?;
+                endianness.write_u32(&mut *file, child.end_chrom_idx)?;
+                endianness.write_u32(&mut *file, child.end_base)?;
+                endianness.write_u64(&mut *file, child_offset)?;
             }
             Ok(children.len() as u64 * full_size)
         }
@@ -462,31 +897,32 @@ pub(crate) fn write_rtreeindex<W: Write + Seek>(
     section_count: u64,
     options: &BBIWriteOptions,
 ) -> io::Result<()> {
+    let endianness = options.endianness;
     let mut index_offsets: Vec<u64> = vec![0u64; levels as usize];
 
     calculate_offsets(&mut index_offsets, &nodes, levels);
 
     let end_of_data = file.tell()?;
-    file.write_u32::<NativeEndian>(CIR_TREE_MAGIC)?;
-    file.write_u32::<NativeEndian>(options.block_size)?;
-    file.write_u64::<NativeEndian>(section_count)?;
+    endianness.write_u32(&mut *file, CIR_TREE_MAGIC)?;
+    endianness.write_u32(&mut *file, options.block_size)?;
+    endianness.write_u64(&mut *file, section_count)?;
     match &nodes {
         RTreeChildren::DataSections(sections) => {
-            file.write_u32::<NativeEndian>(sections.first().unwrap().chrom)?;
-            file.write_u32::<NativeEndian>(sections.first().unwrap().start)?;
-            file.write_u32::<NativeEndian>(sections.last().unwrap().chrom)?;
-            file.write_u32::<NativeEndian>(sections.last().unwrap().end)?;
+            endianness.write_u32(&mut *file, sections.first().unwrap().chrom)?;
+            endianness.write_u32(&mut *file, sections.first().unwrap().start)?;
+            endianness.write_u32(&mut *file, sections.last().unwrap().chrom)?;
+            endianness.write_u32(&mut *file, sections.last().unwrap().end)?;
         }
         RTreeChildren::Nodes(children) => {
-            file.write_u32::<NativeEndian>(children.first().unwrap().start_chrom_idx)?;
-            file.write_u32::<NativeEndian>(children.first().unwrap().start_base)?;
-            file.write_u32::<NativeEndian>(children.last().unwrap().end_chrom_idx)?;
-            file.write_u32::<NativeEndian>(children.last().unwrap().end_base)?;
+            endianness.write_u32(&mut *file, children.first().unwrap().start_chrom_idx)?;
+            endianness.write_u32(&mut *file, children.first().unwrap().start_base)?;
+            endianness.write_u32(&mut *file, children.last().unwrap().end_chrom_idx)?;
+            endianness.write_u32(&mut *file, children.last().unwrap().end_base)?;
         }
     }
-    file.write_u64::<NativeEndian>(end_of_data)?;
-    file.write_u32::<NativeEndian>(options.items_per_slot)?;
-    file.write_u32::<NativeEndian>(0)?;
+    endianness.write_u64(&mut *file, end_of_data)?;
+    endianness.write_u32(&mut *file, options.items_per_slot)?;
+    endianness.write_u32(&mut *file, 0)?;
 
     let mut next_offset = file.tell()?;
     for level in (0..=levels).rev() {
@@ -702,12 +1138,163 @@ pub(crate) struct InternalProcessData(
 );
 
 pub(crate) mod process_internal {
+    use std::future::Future;
+
+    use super::{BBIDataProcessor, BBIWriteOptions, OnOverlap, ProcessDataError};
+
     pub trait BBIDataProcessorCreate {
         type I;
         type Out;
         fn create(internal_data: Self::I) -> Self;
         fn destroy(self) -> Self::Out;
     }
+
+    /// A bigWig value or bigBed entry, covering `start` to `end`.
+    pub trait Interval {
+        fn start(&self) -> u32;
+        fn end(&self) -> u32;
+    }
+
+    /// Holds back the last kept value of a chromosome when values are dropped
+    /// (see [`BBIWriteOptions::drop_zero_width`]), so that each kept value is
+    /// processed with the next *kept* value (or `None` at the end of the
+    /// chromosome).
+    pub struct KeptValues<V>(Option<V>);
+
+    impl<V> Default for KeptValues<V> {
+        fn default() -> Self {
+            KeptValues(None)
+        }
+    }
+
+    impl<V: Clone> KeptValues<V> {
+        /// Takes the current value, returning the values that are ready to be
+        /// processed along with their next values.
+        pub fn push(&mut self, current: V, dropped: bool, last: bool) -> Vec<(V, Option<V>)> {
+            let mut ready = vec![];
+            if !dropped {
+                if let Some(prev) = self.0.replace(current.clone()) {
+                    ready.push((prev, Some(current)));
+                }
+            }
+            if last {
+                if let Some(prev) = self.0.take() {
+                    ready.push((prev, None));
+                }
+            }
+            ready
+        }
+    }
+
+    /// Resolves overlapping values before they are processed (see
+    /// [`BBIWriteOptions::on_overlap`]).
+    pub trait ResolveOverlaps<V> {
+        /// Whether `current_val` is resolved, rather than processed as is.
+        fn resolves(&self, on_overlap: OnOverlap, current_val: &V, next_val: Option<&V>) -> bool;
+
+        /// Takes the current value, returning the resolved values that are
+        /// ready to be processed, along with their next values.
+        fn push(
+            &mut self,
+            on_overlap: OnOverlap,
+            current_val: V,
+            next_val: Option<&V>,
+        ) -> Vec<(V, Option<V>)>;
+    }
+
+    /// bigBed entries may overlap, so they are never resolved.
+    impl<V: Clone> ResolveOverlaps<V> for () {
+        fn resolves(&self, _: OnOverlap, _: &V, _: Option<&V>) -> bool {
+            false
+        }
+
+        fn push(
+            &mut self,
+            _: OnOverlap,
+            current_val: V,
+            next_val: Option<&V>,
+        ) -> Vec<(V, Option<V>)> {
+            vec![(current_val, next_val.cloned())]
+        }
+    }
+
+    /// The values held back by a [`ProcessResolved`] before they are
+    /// processed: the last kept value, and the values with unresolved
+    /// overlaps.
+    pub struct PendingValues<V, O> {
+        kept: KeptValues<V>,
+        overlaps: O,
+    }
+
+    impl<V, O: Default> Default for PendingValues<V, O> {
+        fn default() -> Self {
+            PendingValues {
+                kept: KeptValues::default(),
+                overlaps: O::default(),
+            }
+        }
+    }
+
+    /// A data processor that processes each value once zero-width values are
+    /// dropped (see [`KeptValues`]) and overlaps are resolved (see
+    /// [`ResolveOverlaps`]). Implementing this implements
+    /// [`BBIDataProcessor`].
+    pub trait ProcessResolved: BBIDataProcessorCreate + Send {
+        type Value: Interval + Clone + Send + Sync + 'static;
+        type Overlaps: ResolveOverlaps<Self::Value> + Send;
+
+        /// The write options, and the values held back so far.
+        fn pending(
+            &mut self,
+        ) -> (
+            &BBIWriteOptions,
+            &mut PendingValues<Self::Value, Self::Overlaps>,
+        );
+
+        fn process_resolved(
+            &mut self,
+            current_val: Self::Value,
+            next_val: Option<&Self::Value>,
+        ) -> impl Future<Output = Result<(), ProcessDataError>> + Send;
+    }
+
+    impl<P: ProcessResolved> BBIDataProcessor for P {
+        type Value = P::Value;
+        async fn do_process(
+            &mut self,
+            current_val: Self::Value,
+            next_val: Option<&Self::Value>,
+        ) -> Result<(), ProcessDataError> {
+            let (options, pending) = self.pending();
+            if !options.drop_zero_width {
+                return process_kept(self, current_val, next_val).await;
+            }
+            let dropped = options.drops(current_val.start(), current_val.end());
+            for (val, next) in pending.kept.push(current_val, dropped, next_val.is_none()) {
+                process_kept(self, val, next.as_ref()).await?;
+            }
+            Ok(())
+        }
+    }
+
+    async fn process_kept<P: ProcessResolved>(
+        processor: &mut P,
+        current_val: P::Value,
+        next_val: Option<&P::Value>,
+    ) -> Result<(), ProcessDataError> {
+        let (options, pending) = processor.pending();
+        let on_overlap = options.on_overlap;
+        if !pending
+            .overlaps
+            .resolves(on_overlap, &current_val, next_val)
+        {
+            return processor.process_resolved(current_val, next_val).await;
+        }
+        for (val, next) in pending.overlaps.push(on_overlap, current_val, next_val) {
+            processor.process_resolved(val, next.as_ref()).await?;
+        }
+        Ok(())
+    }
 }
 
 /// Possible errors encountered when processing a value to a BBI file.
@@ -721,6 +1308,39 @@ pub enum ProcessDataError {
     IoError(#[from] io::Error),
 }
 
+/// The end of a zoom record of `size` bases starting at `start`. Positions are
+/// `u32`, so a record that would end past `u32::MAX` is an error.
+pub(crate) fn zoom_record_end(start: u32, size: u32) -> Result<u32, ProcessDataError> {
+    start.checked_add(size).ok_or_else(|| {
+        ProcessDataError::InvalidInput(format!(
+            "A zoom record of {} bases starting at {} would end past the maximum position ({}). Use smaller or fewer zoom levels.",
+            size,
+            start,
+            u32::MAX
+        ))
+    })
+}
+
+/// Checks that `chrom_id` is greater than `last_chrom_id`, the id of the
+/// previous chromosome written, and records it. The index stores the
+/// bounds of each node as the first and last section it covers, so they
+/// are only valid if sections are sorted by chromosome id.
+fn check_chrom_id_order(
+    last_chrom_id: &mut Option<u32>,
+    chrom: &str,
+    chrom_id: u32,
+) -> Result<(), ProcessDataError> {
+    if let Some(last) = last_chrom_id.replace(chrom_id) {
+        if chrom_id <= last {
+            return Err(ProcessDataError::InvalidInput(format!(
+                "Chromosomes must be written in increasing order of their ids, but {} (id {}) comes after a chromosome with id {}.",
+                chrom, chrom_id, last
+            )));
+        }
+    }
+    Ok(())
+}
+
 pub trait BBIDataProcessor: process_internal::BBIDataProcessorCreate {
     type Value: Send + 'static;
     fn do_process(
@@ -756,6 +1376,8 @@ pub(crate) fn write_vals<
     ),
     BBIProcessError<V::Error>,
 > {
+    options.validate()?;
+    options.validate_chrom_ids(chrom_sizes)?;
     let make_zoom = |size| {
         let section_iter = vec![];
         let (buf, write): (TempFileBuffer<File>, TempFileBufferWriter<File>) =
@@ -772,7 +1394,9 @@ pub(crate) fn write_vals<
     let zoom_sizes: Vec<u32> = zoom_sizes.into_iter().filter(|z| *z != 0).collect();
     let zooms_map: BTreeMap<u32, ZoomValue> = zoom_sizes.iter().copied().map(make_zoom).collect();
 
-    let mut chrom_ids = IdMap::default();
+    let mut chrom_ids = options.chrom_id_map();
+    let mut seen_chroms = HashSet::new();
+    let mut last_chrom_id = None;
 
     let mut summary: Option<Summary> = None;
     let (mut send, recv) = futures_mpsc::unbounded();
@@ -830,8 +1454,15 @@ pub(crate) fn write_vals<
                 )));
             }
         };
-        // Make a new id for the chromosome
-        let chrom_id = chrom_ids.get_id(&chrom);
+        // Make a new id for the chromosome (or get its pre-assigned id)
+        let chrom_id = chrom_ids.try_get_id(&chrom).ok_or_else(|| {
+            ProcessDataError::InvalidChromosome(format!(
+                "Input contains chromosome without a pre-assigned id: {}",
+                chrom
+            ))
+        })?;
+        check_chrom_id_order(&mut last_chrom_id, &chrom, chrom_id)?;
+        seen_chroms.insert(chrom.clone());
 
         let (zooms_channels, ftx) = setup_chrom(&mut send, &options, &runtime, &zoom_sizes);
 
@@ -864,6 +1495,7 @@ pub(crate) fn write_vals<
     };
 
     vals_iter.process_to_bbi(&runtime, &mut do_read, &mut advance)?;
+    options.check_unseen_chroms(chrom_sizes, &seen_chroms)?;
 
     drop(send);
 
@@ -878,6 +1510,7 @@ pub(crate) fn write_vals<
 
     let (file, max_uncompressed_buf_size, section_iter, zooms_map) =
         runtime.block_on(write_fut_handle).unwrap()?;
+    options.check_first_zoom_size(&summary_complete)?;
 
     let zoom_infos: Vec<ZoomInfo> = zooms_map
         .into_iter()
@@ -938,12 +1571,16 @@ pub(crate) fn write_vals_no_zoom<
     ),
     BBIProcessError<V::Error>,
 > {
+    options.validate()?;
+    options.validate_chrom_ids(chrom_sizes)?;
     let total_zoom_counts = std::iter::successors(Some(10), |z: &u64| Some((*z).saturating_mul(4)))
         .take_while(|z| *z < u64::MAX)
         .map(|z| (z, 0));
     let mut total_zoom_counts: BTreeMap<u64, u64> = BTreeMap::from_iter(total_zoom_counts);
 
-    let mut chrom_ids = IdMap::default();
+    let mut chrom_ids = options.chrom_id_map();
+    let mut seen_chroms = HashSet::new();
+    let mut last_chrom_id = None;
 
     let mut summary: Option<Summary> = None;
     let (send, recv) = futures_mpsc::unbounded();
@@ -969,8 +1606,15 @@ pub(crate) fn write_vals_no_zoom<
                 )));
             }
         };
-        // Make a new id for the chromosome
-        let chrom_id = chrom_ids.get_id(&chrom);
+        // Make a new id for the chromosome (or get its pre-assigned id)
+        let chrom_id = chrom_ids.try_get_id(&chrom).ok_or_else(|| {
+            ProcessDataError::InvalidChromosome(format!(
+                "Input contains chromosome without a pre-assigned id: {}",
+                chrom
+            ))
+        })?;
+        check_chrom_id_order(&mut last_chrom_id, &chrom, chrom_id)?;
+        seen_chroms.insert(chrom.clone());
 
         let ftx = setup_chrom();
 
@@ -1009,6 +1653,7 @@ pub(crate) fn write_vals_no_zoom<
     };
 
     vals_iter.process_to_bbi(&runtime, &mut do_read, &mut advance)?;
+    options.check_unseen_chroms(chrom_sizes, &seen_chroms)?;
 
     drop(send);
 
@@ -1023,6 +1668,7 @@ pub(crate) fn write_vals_no_zoom<
 
     let (file, max_uncompressed_buf_size, section_iter) =
         runtime.block_on(write_fut_handle).unwrap()?;
+    options.check_first_zoom_size(&summary_complete)?;
 
     let section_iter = section_iter.into_iter().flatten();
     Ok((
@@ -1088,7 +1734,7 @@ pub(crate) fn write_zoom_vals<
             .skip_while(|z| z.0 > min_first_zoom_size as u64)
             .skip_while(|z| {
                 let mut reduced_size = z.1 * 32;
-                if options.compress {
+                if options.effective_compression_level() > 0 {
                     reduced_size /= 2; // Estimate as kent does
                 }
                 reduced_size as u64 > data_size / 2
@@ -1119,10 +1765,13 @@ pub(crate) fn write_zoom_vals<
     let mut max_uncompressed_buf_size = 0;
 
     let mut do_read = |chrom: String| -> Result<P, ProcessDataError> {
-        // Make a new id for the chromosome
-        let chrom_id = *chrom_ids
-            .get(&chrom)
-            .expect("Should not have seen a new chrom.");
+        // The chromosome must have been seen in the first pass
+        let chrom_id = *chrom_ids.get(&chrom).ok_or_else(|| {
+            ProcessDataError::InvalidChromosome(format!(
+                "Chromosome {} was not seen in the first pass over the data. The data must be the same for each pass.",
+                chrom
+            ))
+        })?;
 
         let (zoom_infos, zooms_channels) = {
             let mut zoom_infos = Vec::with_capacity(zooms.len());
@@ -1300,7 +1949,7 @@ pub(crate) fn write_mid<W: Write + Seek + Send + 'static>(
     // Therefore, there is a higher likelihood that the udc file will only need one read for
     // chrom tree + full data index.
     let chrom_index_start = file.tell()?;
-    write_chrom_tree(file, chrom_sizes, &chrom_ids)?;
+    write_chrom_tree(file, chrom_sizes, &chrom_ids, options.endianness)?;
 
     let index_start = file.tell()?;
     let (nodes, levels, total_sections) = get_rtreeindex(sections_iter, &options);
@@ -8,14 +8,15 @@
 
 use std::collections::VecDeque;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufRead, BufReader, Read};
 use std::path::PathBuf;
 
+use flate2::read::MultiGzDecoder;
 use tokio::runtime::Runtime;
 
 use crate::bed::bedparser::{
-    parse_bed, parse_bedgraph, BedFileStream, BedInfallibleIteratorStream, BedIteratorStream,
-    BedValueError, Parser, StreamingBedValues,
+    BedFileStream, BedInfallibleIteratorStream, BedIteratorStream, BedStreamConfig, BedValueError,
+    Parser, StreamingBedValues,
 };
 use crate::utils::file_view::FileView;
 use crate::utils::streaming_linereader::StreamingLineReader;
@@ -35,13 +36,50 @@ impl<S: StreamingBedValues> BedParserStreamingIterator<S> {
     }
 }
 
+impl<B: BufRead> BedParserStreamingIterator<BedFileStream<BedEntry, B>> {
+    /// Like `from_bed_file`, but for any buffered reader (e.g. stdin or a
+    /// network stream), so that data doesn't need to be written to a file
+    /// first.
+    pub fn from_bed_bufread(bufread: B, allow_out_of_order_chroms: bool) -> Self {
+        BedParserStreamingIterator::new(
+            BedFileStream::from_bed_bufread(bufread),
+            allow_out_of_order_chroms,
+        )
+    }
+}
+
+impl<B: BufRead> BedParserStreamingIterator<BedFileStream<Value, B>> {
+    /// Like `from_bedgraph_file`, but for any buffered reader (e.g. stdin or a
+    /// network stream), so that data doesn't need to be written to a file
+    /// first.
+    pub fn from_bedgraph_bufread(bufread: B, allow_out_of_order_chroms: bool) -> Self {
+        BedParserStreamingIterator::new(
+            BedFileStream::from_bedgraph_bufread(bufread),
+            allow_out_of_order_chroms,
+        )
+    }
+}
+
 impl<R: Read> BedParserStreamingIterator<BedFileStream<BedEntry, BufReader<R>>> {
     pub fn from_bed_file(file: R, allow_out_of_order_chroms: bool) -> Self {
+        BedParserStreamingIterator::from_bed_bufread(
+            BufReader::new(file),
+            allow_out_of_order_chroms,
+        )
+    }
+}
+
+impl<R: Read> BedParserStreamingIterator<BedFileStream<BedEntry, BufReader<R>>> {
+    /// Like `from_bed_file`, but for bed files with the columns laid out as
+    /// in `config` (e.g. comma-delimited, or with the chrom, start, and end in
+    /// other columns).
+    pub fn from_bed_file_with_config(
+        file: R,
+        config: BedStreamConfig,
+        allow_out_of_order_chroms: bool,
+    ) -> Self {
         BedParserStreamingIterator::new(
-            BedFileStream {
-                bed: StreamingLineReader::new(BufReader::new(file)),
-                parse: parse_bed,
-            },
+            BedFileStream::from_bed_file_with_config(file, config),
             allow_out_of_order_chroms,
         )
     }
@@ -49,11 +87,30 @@ impl<R: Read> BedParserStreamingIterator<BedFileStream<BedEntry, BufReader<R>>>
 
 impl<R: Read> BedParserStreamingIterator<BedFileStream<Value, BufReader<R>>> {
     pub fn from_bedgraph_file(file: R, allow_out_of_order_chroms: bool) -> Self {
-        BedParserStreamingIterator::new(
-            BedFileStream {
-                bed: StreamingLineReader::new(BufReader::new(file)),
-                parse: parse_bedgraph,
-            },
+        BedParserStreamingIterator::from_bedgraph_bufread(
+            BufReader::new(file),
+            allow_out_of_order_chroms,
+        )
+    }
+}
+
+impl<R: Read> BedParserStreamingIterator<BedFileStream<BedEntry, BufReader<MultiGzDecoder<R>>>> {
+    /// Like `from_bed_file`, but for gzip-compressed input. Multi-member gzip
+    /// files (e.g. from `bgzip`) are decoded fully.
+    pub fn from_bed_gzip_file(file: R, allow_out_of_order_chroms: bool) -> Self {
+        BedParserStreamingIterator::from_bed_file(
+            MultiGzDecoder::new(file),
+            allow_out_of_order_chroms,
+        )
+    }
+}
+
+impl<R: Read> BedParserStreamingIterator<BedFileStream<Value, BufReader<MultiGzDecoder<R>>>> {
+    /// Like `from_bedgraph_file`, but for gzip-compressed input. Multi-member
+    /// gzip files (e.g. from `bgzip`) are decoded fully.
+    pub fn from_bedgraph_gzip_file(file: R, allow_out_of_order_chroms: bool) -> Self {
+        BedParserStreamingIterator::from_bedgraph_file(
+            MultiGzDecoder::new(file),
             allow_out_of_order_chroms,
         )
     }
@@ -246,10 +303,10 @@ impl<V: Send + 'static> BBIDataSource for BedParserParallelStreamingIterator<V>
                     Err(err) => return Err(BBIProcessError::SourceError(err.into())),
                 };
                 let file = FileView::new(file, curr.0, next.map(|n| n.0).unwrap_or(u64::MAX))?;
-                let mut stream = BedFileStream {
-                    bed: StreamingLineReader::new(BufReader::new(file)),
-                    parse: self.parse_fn,
-                };
+                let mut stream = BedFileStream::new(
+                    StreamingLineReader::new(BufReader::new(file)),
+                    self.parse_fn,
+                );
 
                 let mut p = start_processing(curr.1.clone())?;
                 let curr_chrom = curr.1.clone();
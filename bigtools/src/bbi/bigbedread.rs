@@ -1,7 +1,8 @@
 use std::borrow::BorrowMut;
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
-use std::path::Path;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::vec::Vec;
 
 use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
@@ -10,11 +11,12 @@ use bytes::{Buf, BytesMut};
 use itertools::Itertools;
 use thiserror::Error;
 
-use crate::bbi::{BBIFile, BedEntry};
+use crate::bbi::{BBIFile, BedEntry, CHROM_TREE_MAGIC};
 use crate::bbiread::{
     read_info, BBIFileInfo, BBIFileReadInfoError, BBIRead, BBIReadError, Block, ChromInfo,
     ZoomIntervalIter,
 };
+use crate::bed::autosql::parse::{parse_autosql, Field};
 use crate::internal::BBIReadInternal;
 use crate::utils::reopen::{Reopen, ReopenableFile, SeekableRead};
 use crate::{search_cir_tree, BBIFileRead, CachedBBIFileRead, Summary, ZoomIntervalError};
@@ -78,6 +80,36 @@ where
     }
 }
 
+/// A single entry in a bigBed file, with the `rest` split into its fields.
+/// See [`BigBedRead::get_interval_typed`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BedEntryFields {
+    pub start: u32,
+    pub end: u32,
+    pub fields: Vec<String>,
+}
+
+impl BedEntryFields {
+    /// Joins the fields with tabs, giving back the `rest` of the `BedEntry`
+    pub fn rest(&self) -> String {
+        self.fields.join("\t")
+    }
+}
+
+/// An extra index of a bigBed, on one of the fields after `chromEnd` (e.g.
+/// made with `bedToBigBed -extraIndex`). It maps each value of the field to
+/// the blocks with entries that have that value. See
+/// [`BigBedRead::search_extra_index`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtraIndex {
+    /// The index of the indexed field, where `chrom` is `0`
+    pub field_id: u16,
+    /// The name of the indexed field, from the autosql. `None` if the file
+    /// doesn't have autosql declaring the field.
+    pub field_name: Option<String>,
+    pub(crate) offset: u64,
+}
+
 /// Possible errors encountered when opening a bigBed file to read
 #[derive(Error, Debug)]
 pub enum BigBedReadOpenError {
@@ -87,6 +119,12 @@ pub enum BigBedReadOpenError {
     InvalidChroms,
     #[error("An error occurred: {}", .0)]
     IoError(#[from] io::Error),
+    /// An error when opening the file at `path` (from `open_file`)
+    #[error("Error when opening {}: {}", path.display(), source)]
+    FileError {
+        path: PathBuf,
+        source: Box<BigBedReadOpenError>,
+    },
 }
 
 impl From<BBIFileReadInfoError> for BigBedReadOpenError {
@@ -103,6 +141,7 @@ impl From<BBIFileReadInfoError> for BigBedReadOpenError {
 pub struct BigBedRead<R> {
     pub(super) info: BBIFileInfo,
     pub(super) read: R,
+    pub(super) autosql: Option<String>,
 }
 
 impl<R: Reopen> Reopen for BigBedRead<R> {
@@ -110,6 +149,7 @@ impl<R: Reopen> Reopen for BigBedRead<R> {
         Ok(BigBedRead {
             info: self.info.clone(),
             read: self.read.reopen()?,
+            autosql: self.autosql.clone(),
         })
     }
 }
@@ -147,24 +187,46 @@ impl<R> BigBedRead<R> {
         &self.info.chrom_info
     }
 
+    /// The mapping of chromosome names to the ids used within this bigBed.
+    /// See [`BBIFileInfo::chrom_ids`].
+    pub fn chrom_ids(&self) -> HashMap<String, u32> {
+        self.info.chrom_ids()
+    }
+
     /// Consumes this `BigWigRead`, returning the underlying reader.
     pub fn into_inner(self) -> R {
         self.read
     }
+
+    /// The reduction levels of the zoom levels in this bigBed, sorted from
+    /// finest to coarsest. These are the valid `reduction_level`s for
+    /// `get_zoom_interval`.
+    pub fn zoom_levels(&self) -> Vec<u32> {
+        self.info.zoom_levels()
+    }
+
+    /// The coarsest zoom level with a reduction level at or below `desired`
+    /// (i.e. the zoom level that is closest to, but not coarser than, a
+    /// desired resolution). Returns `None` if there is no such zoom level.
+    pub fn closest_zoom_level(&self, desired: u32) -> Option<u32> {
+        self.info.closest_zoom_level(desired)
+    }
 }
 
 impl BigBedRead<ReopenableFile> {
-    /// Opens a new `BigBedRead` from a given path as a file.
+    /// Opens a new `BigBedRead` from the file at `path`. Any error is returned
+    /// as `BigBedReadOpenError::FileError`, with the path.
     pub fn open_file(path: impl AsRef<Path>) -> Result<Self, BigBedReadOpenError> {
+        let path = path.as_ref();
+        let with_path = |source| BigBedReadOpenError::FileError {
+            path: path.to_owned(),
+            source: Box::new(source),
+        };
         let reopen = ReopenableFile {
-            file: File::open(&path)?,
-            path: path.as_ref().to_owned(),
+            file: File::open(path).map_err(|e| with_path(e.into()))?,
+            path: path.to_owned(),
         };
-        let b = BigBedRead::open(reopen);
-        if b.is_err() {
-            eprintln!("Error when opening: {:?}", path.as_ref());
-        }
-        b
+        BigBedRead::open(reopen).map_err(with_path)
     }
 }
 
@@ -173,16 +235,32 @@ where
     R: SeekableRead,
 {
     /// Converts this `BigBedRead`` to where the `BBIFileRead` caches index
-    /// access and block data
+    /// access and block data. Up to `DEFAULT_BLOCK_CACHE_CAPACITY` decompressed
+    /// blocks are kept, evicting the least recently used; see
+    /// `set_block_cache_capacity`.
     pub fn cached(self) -> BigBedRead<CachedBBIFileRead<R>> {
         let read = CachedBBIFileRead::new(self.read);
         BigBedRead {
             read,
             info: self.info,
+            autosql: self.autosql,
         }
     }
 }
 
+impl<R: SeekableRead> BigBedRead<CachedBBIFileRead<R>> {
+    /// Sets the maximum number of decompressed blocks to cache. See
+    /// [`CachedBBIFileRead::set_block_cache_capacity`].
+    pub fn set_block_cache_capacity(&mut self, capacity: usize) {
+        self.read.set_block_cache_capacity(capacity);
+    }
+
+    /// Removes all cached decompressed blocks.
+    pub fn clear_block_cache(&mut self) {
+        self.read.clear_block_cache();
+    }
+}
+
 impl<R: BBIFileRead> BigBedRead<R> {
     /// Opens a new `BigBedRead` for a given type that implements both `Read` and `Seek`
     pub fn open(mut read: R) -> Result<Self, BigBedReadOpenError> {
@@ -192,16 +270,29 @@ impl<R: BBIFileRead> BigBedRead<R> {
             _ => return Err(BigBedReadOpenError::NotABigBed),
         }
 
-        Ok(BigBedRead { info, read })
+        Ok(BigBedRead {
+            info,
+            read,
+            autosql: None,
+        })
     }
 
     /// Does *not* check if the passed `R` matches the provided info (including if the `R` is a bigBed at all!)
     pub fn with_info(info: BBIFileInfo, read: R) -> Self {
-        BigBedRead { info, read }
+        BigBedRead {
+            info,
+            read,
+            autosql: None,
+        }
     }
 
-    /// Reads the autosql from this bigBed
+    /// Reads the autosql from this bigBed. The autosql is stored verbatim in
+    /// the file, and is cached after the first call. Returns `None` if the
+    /// file has no autosql.
     pub fn autosql(&mut self) -> Result<Option<String>, BBIReadError> {
+        if let Some(autosql) = &self.autosql {
+            return Ok(Some(autosql.clone()));
+        }
         let auto_sql_offset = self.info.header.auto_sql_offset;
         if auto_sql_offset == 0 {
             return Ok(None);
@@ -211,12 +302,39 @@ impl<R: BBIFileRead> BigBedRead<R> {
         reader.seek(SeekFrom::Start(auto_sql_offset))?;
         let mut buffer = Vec::new();
         reader.read_until(b'\0', &mut buffer)?;
-        buffer.pop();
+        if buffer.pop() != Some(b'\0') {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Reached end of file while reading autosql.",
+            )
+            .into());
+        }
         let autosql = String::from_utf8(buffer)
             .map_err(|_| BBIReadError::InvalidFile("Invalid autosql: not UTF-8".to_owned()))?;
+        self.autosql = Some(autosql.clone());
         Ok(Some(autosql))
     }
 
+    /// Reads and parses the autosql from this bigBed, returning the fields of
+    /// its table declaration. These describe the columns of each entry,
+    /// starting with `chrom`, `chromStart`, and `chromEnd`, followed by the
+    /// fields in `BedEntry::rest`. Returns an empty `Vec` if the file has no
+    /// autosql.
+    pub fn autosql_fields(&mut self) -> Result<Vec<Field>, BBIReadError> {
+        let autosql = match self.autosql()? {
+            Some(autosql) => autosql,
+            None => return Ok(vec![]),
+        };
+        let mut declarations = parse_autosql(&autosql)
+            .map_err(|e| BBIReadError::InvalidFile(format!("Invalid autosql: {}", e)))?;
+        if declarations.len() > 1 {
+            return Err(BBIReadError::InvalidFile(
+                "Invalid autosql: unexpected extra declarations".to_owned(),
+            ));
+        }
+        Ok(declarations.pop().map(|d| d.fields).unwrap_or_default())
+    }
+
     pub fn item_count(&mut self) -> Result<u64, BBIReadError> {
         let header = self.info.header;
         let reader = self.reader().raw_reader();
@@ -228,6 +346,102 @@ impl<R: BBIFileRead> BigBedRead<R> {
         })
     }
 
+    /// Reads the extra indices of this bigBed from its extended header. Only
+    /// version 4+ bigBeds can have extra indices. Returns an empty `Vec` if
+    /// there are none.
+    pub fn extra_indices(&mut self) -> Result<Vec<ExtraIndex>, BBIReadError> {
+        let header = self.info.header;
+        if header.version < 4 || header.extension_offset == 0 {
+            return Ok(vec![]);
+        }
+        let fields = self.autosql_fields()?;
+        let reader = self.reader().raw_reader();
+        let mut reader = ByteOrdered::runtime(reader, header.endianness);
+        reader.seek(SeekFrom::Start(header.extension_offset))?;
+        let _extension_size = reader.read_u16()?;
+        let index_count = reader.read_u16()?;
+        let index_list_offset = reader.read_u64()?;
+        if index_count == 0 {
+            return Ok(vec![]);
+        }
+
+        reader.seek(SeekFrom::Start(index_list_offset))?;
+        let mut indices = Vec::with_capacity(index_count as usize);
+        for _ in 0..index_count {
+            let _index_type = reader.read_u16()?;
+            let field_count = reader.read_u16()?;
+            let offset = reader.read_u64()?;
+            let _reserved = reader.read_u32()?;
+            let mut field_ids = Vec::with_capacity(field_count as usize);
+            for _ in 0..field_count {
+                field_ids.push(reader.read_u16()?);
+                let _reserved = reader.read_u16()?;
+            }
+            // Like UCSC, only indices on a single field are supported
+            let field_id = match field_ids[..] {
+                [field_id] if field_id >= 3 => field_id,
+                [_] => {
+                    return Err(BBIReadError::InvalidFile(
+                        "Extra index on chrom, chromStart, or chromEnd.".to_owned(),
+                    ))
+                }
+                _ => {
+                    return Err(BBIReadError::InvalidFile(
+                        "Extra indices on multiple fields are not supported.".to_owned(),
+                    ))
+                }
+            };
+            indices.push(ExtraIndex {
+                field_id,
+                field_name: fields.get(field_id as usize).map(|f| f.name.clone()),
+                offset,
+            });
+        }
+        Ok(indices)
+    }
+
+    /// Finds the entries where `field` is `value`, using the extra index on
+    /// `field` (see [`BigBedRead::extra_indices`]). Returns the chromosome of
+    /// each entry along with the entry, in file order. Returns
+    /// `BBIReadError::MissingExtraIndex` if there's no index on `field`.
+    pub fn search_extra_index(
+        &mut self,
+        field: &str,
+        value: &str,
+    ) -> Result<Vec<(String, BedEntry)>, BBIReadError> {
+        let index = self
+            .extra_indices()?
+            .into_iter()
+            .find(|i| i.field_name.as_deref() == Some(field))
+            .ok_or_else(|| BBIReadError::MissingExtraIndex(field.to_owned()))?;
+        let endianness = self.info.header.endianness;
+        let reader = self.reader().raw_reader();
+        let mut blocks = search_bpt(reader, endianness, index.offset, value.as_bytes())?;
+        // Each entry in a block has its own item in the index
+        blocks.sort_by_key(|b| b.offset);
+        blocks.dedup_by_key(|b| b.offset);
+
+        let rest_field = index.field_id as usize - 3;
+        let mut entries = vec![];
+        for block in blocks {
+            for (chrom_id, entry) in read_block_entries(self, &block)? {
+                if entry.rest.split('\t').nth(rest_field) != Some(value) {
+                    continue;
+                }
+                let chrom = self
+                    .info
+                    .chrom_info
+                    .iter()
+                    .find(|c| c.id == chrom_id)
+                    .ok_or_else(|| {
+                        BBIReadError::InvalidFile(format!("Unknown chromosome id: {}", chrom_id))
+                    })?;
+                entries.push((chrom.name.clone(), entry));
+            }
+        }
+        Ok(entries)
+    }
+
     /// Gets a reference to the inner `R` type, in order to access any info
     pub fn inner_read(&self) -> &R {
         &self.read
@@ -281,13 +495,7 @@ impl<R: BBIFileRead> BigBedRead<R> {
         let cir_tree = self.full_data_cir_tree()?;
         let blocks = search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
         // TODO: this is only for asserting that the chrom is what we expect
-        let chrom_ix = self
-            .info()
-            .chrom_info
-            .iter()
-            .find(|&x| x.name == chrom_name)
-            .unwrap()
-            .id;
+        let chrom_ix = self.info().chrom_info_named(chrom_name).unwrap().id;
         Ok(BigBedIntervalIter {
             r: std::marker::PhantomData,
             bigbed: self,
@@ -300,6 +508,42 @@ impl<R: BBIFileRead> BigBedRead<R> {
         })
     }
 
+    /// For a given chromosome, start, and end, returns an `Iterator` of the
+    /// intersecting entries, with the `rest` of each entry split into its
+    /// fields. If this bigBed has autosql, the `rest` is split on tabs into at
+    /// most as many fields as are declared after `chromEnd` (so the last field
+    /// may contain tabs). Otherwise, the `rest` is split on every tab. In
+    /// either case, joining the fields with tabs gives back the `rest`. The
+    /// resulting iterator takes a mutable reference of this `BigBedRead`.
+    pub fn get_interval_typed<'a>(
+        &'a mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<impl Iterator<Item = Result<BedEntryFields, BBIReadError>> + 'a, BBIReadError>
+    where
+        R: 'a,
+    {
+        let num_fields = match self.autosql()? {
+            Some(_) => Some(self.autosql_fields()?.len().saturating_sub(3)),
+            None => None,
+        };
+        let entries = self.get_interval(chrom_name, start, end)?;
+        Ok(entries.map(move |entry| {
+            let entry = entry?;
+            let fields = match (entry.rest.is_empty(), num_fields) {
+                (true, _) | (false, Some(0)) => vec![],
+                (false, Some(n)) => entry.rest.splitn(n, '\t').map(str::to_owned).collect(),
+                (false, None) => entry.rest.split('\t').map(str::to_owned).collect(),
+            };
+            Ok(BedEntryFields {
+                start: entry.start,
+                end: entry.end,
+                fields,
+            })
+        }))
+    }
+
     /// For a given chromosome, start, and end, returns an `Iterator` of the
     /// intersecting `BedEntry`s. The resulting iterator takes this `BigBedRead`
     /// by value.
@@ -312,13 +556,7 @@ impl<R: BBIFileRead> BigBedRead<R> {
         let cir_tree = self.full_data_cir_tree()?;
         let blocks = search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
         // TODO: this is only for asserting that the chrom is what we expect
-        let chrom_ix = self
-            .info()
-            .chrom_info
-            .iter()
-            .find(|&x| x.name == chrom_name)
-            .unwrap()
-            .id;
+        let chrom_ix = self.info().chrom_info_named(chrom_name).unwrap().id;
         Ok(BigBedIntervalIter {
             r: std::marker::PhantomData,
             bigbed: self,
@@ -331,8 +569,31 @@ impl<R: BBIFileRead> BigBedRead<R> {
         })
     }
 
+    /// For a given chromosome, start, and end, returns an `Iterator` of the
+    /// intersecting `ZoomRecord`s of the finest zoom level with at most
+    /// `max_bins` records for the region. If every zoom level has more, the
+    /// coarsest is used. See [`BBIFileInfo::zoom_level_for_bins`].
+    pub fn get_zoom_interval_auto<'a>(
+        &'a mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        max_bins: u32,
+    ) -> Result<ZoomIntervalIter<BigBedRead<R>, &'a mut BigBedRead<R>>, ZoomIntervalError> {
+        let reduction_level = self
+            .info
+            .zoom_level_for_bins(end.saturating_sub(start), max_bins)
+            .ok_or(ZoomIntervalError::ReductionLevelNotFound)?;
+        self.get_zoom_interval(chrom_name, start, end, reduction_level)
+    }
+
     /// For a given chromosome, start, and end, returns an `Iterator` of the
     /// intersecting `ZoomRecord`s.
+    ///
+    /// Zoom records only exist where there is data, so if no records of the
+    /// zoom level intersect the region, the iterator is empty. An error is
+    /// only returned if the chromosome isn't in the file or `reduction_level`
+    /// isn't a zoom level.
     pub fn get_zoom_interval<'a>(
         &'a mut self,
         chrom_name: &str,
@@ -391,12 +652,34 @@ fn get_block_entries<R: BBIFileRead>(
     start: u32,
     end: u32,
 ) -> Result<std::vec::IntoIter<BedEntry>, BBIReadError> {
-    let data = bigbed.read.get_block_data(&bigbed.info, &block)?;
+    let mut entries: Vec<BedEntry> = Vec::new();
+    for (chrom_id, entry) in read_block_entries(bigbed, &block)? {
+        // FIXME: should this just return empty?
+        assert_eq!(
+            chrom_id, expected_chrom,
+            "BUG: bigBed had multiple chroms in a section"
+        );
+        if entry.end >= start && entry.start <= end {
+            entries.push(entry);
+        }
+    }
+
+    *known_offset = block.offset + block.size;
+    Ok(entries.into_iter())
+}
+
+/// Reads and decodes all the entries of a block, along with the id of the
+/// chromosome of each.
+fn read_block_entries<R: BBIFileRead>(
+    bigbed: &mut BigBedRead<R>,
+    block: &Block,
+) -> Result<Vec<(u32, BedEntry)>, BBIReadError> {
+    let data = bigbed.read.get_block_data(&bigbed.info, block)?;
     let mut bytes = BytesMut::with_capacity(data.len());
     bytes.extend_from_slice(&data);
-    let mut entries: Vec<BedEntry> = Vec::new();
+    let mut entries = Vec::new();
 
-    let mut read_entry = || -> Result<Option<BedEntry>, BBIReadError> {
+    let mut read_entry = || -> Result<Option<(u32, BedEntry)>, BBIReadError> {
         if bytes.len() < 12 {
             return Ok(None);
         }
@@ -411,33 +694,232 @@ fn get_block_entries<R: BBIFileRead>(
                 "Chrom start and end both equal 0.".to_owned(),
             ));
         }
-        // FIXME: should this just return empty?
-        assert_eq!(
-            chrom_id, expected_chrom,
-            "BUG: bigBed had multiple chroms in a section"
-        );
+        // The rest of the entry is null-terminated. If there is no terminator,
+        // this is the last entry in the block and the rest is everything left.
         let nul = bytes.iter().find_position(|b| **b == b'\0');
         let s = match nul {
             Some((pos, _)) => {
                 let b = bytes.split_to(pos);
                 bytes.get_u8();
-                b.to_vec()
+                b
             }
-            None => bytes.to_vec(),
+            None => bytes.split(),
         };
-        let rest = String::from_utf8(s).unwrap();
-        Ok(Some(BedEntry {
-            start: chrom_start,
-            end: chrom_end,
-            rest,
-        }))
+        let rest = String::from_utf8(s.to_vec()).map_err(|_| {
+            BBIReadError::InvalidFile("Bed entry contains invalid utf-8.".to_owned())
+        })?;
+        Ok(Some((
+            chrom_id,
+            BedEntry {
+                start: chrom_start,
+                end: chrom_end,
+                rest,
+            },
+        )))
     };
     while let Some(entry) = read_entry()? {
-        if entry.end >= start && entry.start <= end {
-            entries.push(entry);
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Finds the blocks for `key` in the B+ tree at `offset`, as used for extra
+/// indices. Keys are stored padded with zeros to the tree's key size, and may
+/// be repeated.
+fn search_bpt<R: SeekableRead>(
+    reader: &mut R,
+    endianness: byteordered::Endianness,
+    offset: u64,
+    key: &[u8],
+) -> Result<Vec<Block>, BBIReadError> {
+    let mut reader = ByteOrdered::runtime(reader, endianness);
+    reader.seek(SeekFrom::Start(offset))?;
+    if reader.read_u32()? != CHROM_TREE_MAGIC {
+        return Err(BBIReadError::InvalidFile(
+            "Invalid magic for extra index.".to_owned(),
+        ));
+    }
+    let _block_size = reader.read_u32()?;
+    let key_size = reader.read_u32()? as usize;
+    let val_size = reader.read_u32()?;
+    let _item_count = reader.read_u64()?;
+    let _reserved = reader.read_u64()?;
+    if val_size != 16 {
+        return Err(BBIReadError::InvalidFile(format!(
+            "Invalid value size for extra index: {}",
+            val_size
+        )));
+    }
+    if key.len() > key_size {
+        return Ok(vec![]);
+    }
+    let mut padded_key = key.to_vec();
+    padded_key.resize(key_size, 0);
+
+    let mut blocks = vec![];
+    let mut remaining_nodes = VecDeque::from([offset + 32]);
+    while let Some(node_offset) = remaining_nodes.pop_front() {
+        reader.seek(SeekFrom::Start(node_offset))?;
+        let is_leaf = reader.read_u8()?;
+        let _reserved = reader.read_u8()?;
+        let count = reader.read_u16()?;
+        let mut node_key = vec![0u8; key_size];
+        if is_leaf == 1 {
+            for _ in 0..count {
+                reader.read_exact(&mut node_key)?;
+                let offset = reader.read_u64()?;
+                let size = reader.read_u64()?;
+                if node_key == padded_key {
+                    blocks.push(Block { offset, size });
+                }
+            }
+        } else {
+            let mut children = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                reader.read_exact(&mut node_key)?;
+                children.push((node_key.clone(), reader.read_u64()?));
+            }
+            // A child's keys are between its first key and the next child's
+            // first key, inclusive (since keys can be repeated across children)
+            for (i, (first_key, child_offset)) in children.iter().enumerate() {
+                let next_key = children.get(i + 1).map(|c| &c.0);
+                if *first_key <= padded_key && next_key.is_none_or(|k| *k >= padded_key) {
+                    remaining_nodes.push_back(*child_offset);
+                }
+            }
         }
     }
+    Ok(blocks)
+}
 
-    *known_offset = block.offset + block.size;
-    Ok(entries.into_iter())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    /// Appends an extra index on `name` (field 3) to a bigBed, with a two
+    /// level B+ tree.
+    fn with_name_index(bytes: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut bigbed = BigBedRead::open(Cursor::new(bytes.to_vec()))?;
+        let mut items = vec![];
+        for chrom in bigbed.chroms().to_vec() {
+            let cir_tree = bigbed.full_data_cir_tree().map_err(BBIReadError::from)?;
+            let blocks = search_cir_tree(
+                &bigbed.info,
+                &mut bigbed.read,
+                cir_tree,
+                &chrom.name,
+                0,
+                chrom.length,
+            )
+            .map_err(BBIReadError::from)?;
+            for block in blocks {
+                for (_, entry) in read_block_entries(&mut bigbed, &block)? {
+                    let name = entry.rest.split('\t').next().unwrap().to_owned();
+                    items.push((name, block.offset, block.size));
+                }
+            }
+        }
+        items.sort();
+        let key_size = items.iter().map(|i| i.0.len()).max().unwrap();
+        let key = |name: &str| {
+            let mut key = name.as_bytes().to_vec();
+            key.resize(key_size, 0);
+            key
+        };
+
+        let mut out = bytes.to_vec();
+        let tree_offset = out.len() as u64;
+        let leaves: Vec<_> = items.chunks(4).collect();
+        out.extend(CHROM_TREE_MAGIC.to_le_bytes());
+        out.extend(4u32.to_le_bytes());
+        out.extend((key_size as u32).to_le_bytes());
+        out.extend(16u32.to_le_bytes());
+        out.extend((items.len() as u64).to_le_bytes());
+        out.extend(0u64.to_le_bytes());
+        let root_size = 4 + leaves.len() * (key_size + 8);
+        let leaf_offset =
+            |i: usize| tree_offset + 32 + (root_size + i * (4 + 4 * (key_size + 16))) as u64;
+        out.extend([0u8, 0]);
+        out.extend((leaves.len() as u16).to_le_bytes());
+        for (i, leaf) in leaves.iter().enumerate() {
+            out.extend(key(&leaf[0].0));
+            out.extend(leaf_offset(i).to_le_bytes());
+        }
+        for leaf in leaves.iter() {
+            out.extend([1u8, 0]);
+            out.extend((leaf.len() as u16).to_le_bytes());
+            for (name, offset, size) in leaf.iter() {
+                out.extend(key(name));
+                out.extend(offset.to_le_bytes());
+                out.extend(size.to_le_bytes());
+            }
+            // Pad each leaf to the full size, to keep offsets simple
+            out.extend(vec![0u8; (4 - leaf.len()) * (key_size + 16)]);
+        }
+
+        let index_list_offset = out.len() as u64;
+        out.extend(0u16.to_le_bytes());
+        out.extend(1u16.to_le_bytes());
+        out.extend(tree_offset.to_le_bytes());
+        out.extend(0u32.to_le_bytes());
+        out.extend(3u16.to_le_bytes());
+        out.extend(0u16.to_le_bytes());
+
+        let extension_offset = bigbed.info.header.extension_offset as usize;
+        out[extension_offset + 2..extension_offset + 4].copy_from_slice(&1u16.to_le_bytes());
+        out[extension_offset + 4..extension_offset + 12]
+            .copy_from_slice(&index_list_offset.to_le_bytes());
+        Ok(out)
+    }
+
+    #[test]
+    fn test_extra_index() -> Result<(), Box<dyn std::error::Error>> {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("resources/test/bigGenePred.bb");
+        let bytes = std::fs::read(&dir)?;
+
+        let mut bigbed = BigBedRead::open(Cursor::new(bytes.clone()))?;
+        assert!(bigbed.extra_indices()?.is_empty());
+        assert!(matches!(
+            bigbed.search_extra_index("name", "a"),
+            Err(BBIReadError::MissingExtraIndex(_))
+        ));
+
+        let mut bigbed = BigBedRead::open(Cursor::new(with_name_index(&bytes)?))?;
+        let indices = bigbed.extra_indices()?;
+        assert_eq!(indices.len(), 1);
+        assert_eq!(indices[0].field_id, 3);
+        assert_eq!(indices[0].field_name.as_deref(), Some("name"));
+
+        let chrom = bigbed.chroms()[0].clone();
+        let entries: Vec<_> = bigbed
+            .get_interval(&chrom.name, 0, chrom.length)?
+            .collect::<Result<_, _>>()?;
+        for entry in entries.iter().take(20) {
+            let name = entry.rest.split('\t').next().unwrap();
+            let mut expected = vec![];
+            for chrom in bigbed.chroms().to_vec() {
+                for e in bigbed.get_interval(&chrom.name, 0, chrom.length)? {
+                    let e = e?;
+                    if e.rest.split('\t').next() == Some(name) {
+                        expected.push((chrom.name.clone(), e));
+                    }
+                }
+            }
+            let mut found = bigbed.search_extra_index("name", name)?;
+            expected.sort_by(|a, b| (&a.0, a.1.start).cmp(&(&b.0, b.1.start)));
+            found.sort_by(|a, b| (&a.0, a.1.start).cmp(&(&b.0, b.1.start)));
+            assert!(found.contains(&(chrom.name.clone(), entry.clone())));
+            assert_eq!(found, expected);
+        }
+        assert!(bigbed.search_extra_index("name", "not a name")?.is_empty());
+        assert!(matches!(
+            bigbed.search_extra_index("score", "0"),
+            Err(BBIReadError::MissingExtraIndex(_))
+        ));
+
+        Ok(())
+    }
 }
@@ -7,13 +7,16 @@ use std::path::Path;
 use futures::sink::SinkExt;
 
 use byteorder::{NativeEndian, WriteBytesExt};
+use byteordered::{Endian, Endianness};
 use tokio::runtime::{Handle, Runtime};
 
-use crate::bbiwrite::process_internal::BBIDataProcessorCreate;
+use crate::bbiwrite::process_internal::{
+    BBIDataProcessorCreate, Interval, PendingValues, ProcessResolved,
+};
 use crate::utils::tell::Tell;
 use crate::{
-    write_info, BBIDataProcessor, BBIDataProcessoredData, BBIDataProcessoringInputSectionChannel,
-    BBIDataSource, InternalProcessData, InternalTempZoomInfo, NoZoomsInternalProcessData,
+    write_info, BBIDataProcessoredData, BBIDataProcessoringInputSectionChannel, BBIDataSource,
+    InternalProcessData, InternalTempZoomInfo, NoZoomsInternalProcessData,
     NoZoomsInternalProcessedData, ProcessDataError, ZoomsInternalProcessData,
     ZoomsInternalProcessedData,
 };
@@ -21,8 +24,8 @@ use index_list::IndexList;
 
 use crate::bbi::{BedEntry, Summary, Value, ZoomRecord, BIGBED_MAGIC};
 use crate::bbiwrite::{
-    self, encode_zoom_section, write_blank_headers, write_zooms, BBIProcessError, BBIWriteOptions,
-    SectionData,
+    self, encode_zoom_section, write_blank_headers, write_zooms, zoom_record_end, BBIProcessError,
+    BBIWriteOptions, SectionData, WriteSizeEstimate,
 };
 use crate::bed::autosql::parse::parse_autosql;
 
@@ -54,6 +57,16 @@ impl<W: Write + Seek + Send + 'static> BigBedWrite<W> {
         }
     }
 
+    /// Gives a rough estimate of the output size for `num_records` entries,
+    /// where the non-coordinate fields of each entry are `average_rest_len`
+    /// bytes on average, given the chromosome sizes and current options. This
+    /// may be useful, for example, for showing progress.
+    pub fn estimate_size(&self, num_records: u64, average_rest_len: u64) -> WriteSizeEstimate {
+        // Each bed entry is a chrom id, start, end, and nul-terminated rest
+        let item_size = 12 + average_rest_len + 1;
+        bbiwrite::estimate_size(&self.options, &self.chrom_sizes, num_records, item_size)
+    }
+
     fn write_pre(
         file: &mut BufWriter<W>,
         autosql: Option<String>,
@@ -105,7 +118,11 @@ impl<W: Write + Seek + Send + 'static> BigBedWrite<W> {
         ))
     }
 
-    /// Write the values from `V` as a bigWig. Will utilize the provided runtime for encoding values and for reading through the values (potentially parallelized by chromosome).
+    /// Write the values from `V` as a bigWig. Will utilize the provided runtime for encoding values and for reading through the values (potentially parallelized by chromosome). The number of worker threads of the runtime determines how much work is done in parallel.
+    ///
+    /// The output is deterministic: blocks and chromosomes are always written in
+    /// input order, so the same values and options give the same bytes
+    /// regardless of the runtime's threads.
     pub fn write<V: BBIDataSource<Value = BedEntry>>(
         self,
         vals: V,
@@ -157,12 +174,13 @@ impl<W: Write + Seek + Send + 'static> BigBedWrite<W> {
             summary,
             // In bigWigs, this is total sections, but total items in bigBeds
             summary.total_items,
+            self.options.endianness,
         )?;
 
         Ok(())
     }
 
-    /// Write the values from `V` as a bigBed. Will utilize the provided runtime for encoding values and for reading through the values (potentially parallelized by chromosome).
+    /// Write the values from `V` as a bigBed. Will utilize the provided runtime for encoding values and for reading through the values (potentially parallelized by chromosome). The number of worker threads of the runtime determines how much work is done in parallel.
     /// This will take two passes on the provided values: first to write the values themselves, then the zooms. This is beneficial over `write` on smaller files, where the encoding of
     /// high resolution zooms takes up a substantial portion of total processing time.
     pub fn write_multipass<V: BBIDataSource<Value = BedEntry>>(
@@ -199,6 +217,7 @@ impl<W: Write + Seek + Send + 'static> BigBedWrite<W> {
 
         let vals = make_vals()?;
 
+        let endianness = self.options.endianness;
         let output = bbiwrite::write_zoom_vals::<_, _, BigBedZoomsProcess<W>>(
             vals,
             self.options,
@@ -230,6 +249,7 @@ impl<W: Write + Seek + Send + 'static> BigBedWrite<W> {
             zoom_entries,
             summary,
             summary.total_items,
+            endianness,
         )?;
 
         Ok(())
@@ -256,8 +276,8 @@ async fn process_val(
     // TODO: test these correctly fails
     if current_val.start > current_val.end {
         return Err(ProcessDataError::InvalidInput(format!(
-            "Invalid bed: {} > {}",
-            current_val.start, current_val.end
+            "Invalid bed: the start ({}) is greater than the end ({}) on chromosome {}",
+            current_val.start, current_val.end, chrom
         )));
     }
     if current_val.start >= chrom_length {
@@ -390,7 +410,12 @@ async fn process_val(
     items.push(current_val);
     if next_val.is_none() || items.len() >= options.items_per_slot as usize {
         let items = std::mem::replace(items, Vec::with_capacity(options.items_per_slot as usize));
-        let handle = runtime.spawn(encode_section(options.compress, items, chrom_id));
+        let handle = runtime.spawn(encode_section(
+            options.effective_compression_level(),
+            options.endianness,
+            items,
+            chrom_id,
+        ));
         ftx.send(handle).await.expect("Couldn't send");
     }
 
@@ -495,8 +520,11 @@ async fn process_val_zoom(
                         }
                         if !zoom_item.records.is_empty() {
                             let items = std::mem::take(&mut zoom_item.records);
-                            let handle =
-                                runtime.spawn(encode_zoom_section(options.compress, items));
+                            let handle = runtime.spawn(encode_zoom_section(
+                                options.effective_compression_level(),
+                                options.endianness,
+                                items,
+                            ));
                             zoom_item.channel.send(handle).await.expect("Couln't send");
                         }
                     }
@@ -519,7 +547,7 @@ async fn process_val_zoom(
                     0,
                 ));
                 // The end of zoom record
-                let next_end = zoom2.start + zoom_item.size;
+                let next_end = zoom_record_end(zoom2.start, zoom_item.size)?;
                 // End of bases that we could add
                 let add_end = std::cmp::min(next_end, removed_end);
                 // If the last zoom ends before this value starts, we don't add anything
@@ -552,7 +580,11 @@ async fn process_val_zoom(
                 // Write section if full
                 if zoom_item.records.len() == options.items_per_slot as usize {
                     let items = std::mem::take(&mut zoom_item.records);
-                    let handle = runtime.spawn(encode_zoom_section(options.compress, items));
+                    let handle = runtime.spawn(encode_zoom_section(
+                        options.effective_compression_level(),
+                        options.endianness,
+                        items,
+                    ));
                     zoom_item.channel.send(handle).await.expect("Couln't send");
                 }
             }
@@ -578,6 +610,15 @@ struct EntriesSection {
     zoom_items: Vec<ZoomItem>,
 }
 
+impl Interval for BedEntry {
+    fn start(&self) -> u32 {
+        self.start
+    }
+    fn end(&self) -> u32 {
+        self.end
+    }
+}
+
 pub(crate) struct BigBedFullProcess {
     summary: Option<Summary>,
     state_val: EntriesSection,
@@ -586,6 +627,7 @@ pub(crate) struct BigBedFullProcess {
     ftx: BBIDataProcessoringInputSectionChannel,
     chrom_id: u32,
     options: BBIWriteOptions,
+    pending: PendingValues<BedEntry, ()>,
     runtime: Handle,
     chrom: String,
     length: u32,
@@ -651,18 +693,25 @@ impl BBIDataProcessorCreate for BigBedFullProcess {
             ftx,
             chrom_id,
             options,
+            pending: PendingValues::default(),
             runtime,
             chrom,
             length,
         }
     }
 }
-impl BBIDataProcessor for BigBedFullProcess {
+impl ProcessResolved for BigBedFullProcess {
     type Value = BedEntry;
-    async fn do_process(
+    type Overlaps = ();
+
+    fn pending(&mut self) -> (&BBIWriteOptions, &mut PendingValues<BedEntry, ()>) {
+        (&self.options, &mut self.pending)
+    }
+
+    async fn process_resolved(
         &mut self,
-        current_val: Self::Value,
-        next_val: Option<&Self::Value>,
+        current_val: BedEntry,
+        next_val: Option<&BedEntry>,
     ) -> Result<(), ProcessDataError> {
         let Self {
             summary,
@@ -674,6 +723,7 @@ impl BBIDataProcessor for BigBedFullProcess {
             runtime,
             chrom,
             length,
+            ..
         } = self;
         let chrom_id = *chrom_id;
         let length = *length;
@@ -723,6 +773,7 @@ struct BigBedNoZoomsProcess {
     ftx: BBIDataProcessoringInputSectionChannel,
     chrom_id: u32,
     options: BBIWriteOptions,
+    pending: PendingValues<BedEntry, ()>,
     runtime: Handle,
     chrom: String,
     length: u32,
@@ -757,6 +808,7 @@ impl BBIDataProcessorCreate for BigBedNoZoomsProcess {
             ftx,
             chrom_id,
             options,
+            pending: PendingValues::default(),
             runtime,
             chrom,
             length,
@@ -797,12 +849,18 @@ impl BBIDataProcessorCreate for BigBedNoZoomsProcess {
     }
 }
 
-impl BBIDataProcessor for BigBedNoZoomsProcess {
+impl ProcessResolved for BigBedNoZoomsProcess {
     type Value = BedEntry;
-    async fn do_process(
+    type Overlaps = ();
+
+    fn pending(&mut self) -> (&BBIWriteOptions, &mut PendingValues<BedEntry, ()>) {
+        (&self.options, &mut self.pending)
+    }
+
+    async fn process_resolved(
         &mut self,
-        current_val: Self::Value,
-        next_val: Option<&Self::Value>,
+        current_val: BedEntry,
+        next_val: Option<&BedEntry>,
     ) -> Result<(), ProcessDataError> {
         let BigBedNoZoomsProcess {
             ftx,
@@ -816,6 +874,7 @@ impl BBIDataProcessor for BigBedNoZoomsProcess {
             overlap,
             zoom_counts,
             total_items,
+            ..
         } = self;
 
         *total_items += 1;
@@ -857,6 +916,7 @@ struct BigBedZoomsProcess<W: Write + Seek + Send + 'static> {
     temp_zoom_items: Vec<InternalTempZoomInfo<W>>,
     chrom_id: u32,
     options: BBIWriteOptions,
+    pending: PendingValues<BedEntry, ()>,
     runtime: Handle,
 
     zoom_items: Vec<ZoomItem>,
@@ -884,6 +944,7 @@ impl<W: Write + Seek + Send + 'static> BBIDataProcessorCreate for BigBedZoomsPro
             temp_zoom_items,
             chrom_id,
             options,
+            pending: PendingValues::default(),
             runtime,
             zoom_items,
         }
@@ -899,12 +960,18 @@ impl<W: Write + Seek + Send + 'static> BBIDataProcessorCreate for BigBedZoomsPro
         ZoomsInternalProcessedData(self.temp_zoom_items)
     }
 }
-impl<W: Write + Seek + Send + 'static> BBIDataProcessor for BigBedZoomsProcess<W> {
+impl<W: Write + Seek + Send + 'static> ProcessResolved for BigBedZoomsProcess<W> {
     type Value = BedEntry;
-    async fn do_process(
+    type Overlaps = ();
+
+    fn pending(&mut self) -> (&BBIWriteOptions, &mut PendingValues<BedEntry, ()>) {
+        (&self.options, &mut self.pending)
+    }
+
+    async fn process_resolved(
         &mut self,
-        current_val: Self::Value,
-        next_val: Option<&Self::Value>,
+        current_val: BedEntry,
+        next_val: Option<&BedEntry>,
     ) -> Result<(), ProcessDataError> {
         let BigBedZoomsProcess {
             chrom_id,
@@ -930,7 +997,8 @@ impl<W: Write + Seek + Send + 'static> BBIDataProcessor for BigBedZoomsProcess<W
 }
 
 async fn encode_section(
-    compress: bool,
+    compression_level: u32,
+    endianness: Endianness,
     items_in_section: Vec<BedEntry>,
     chrom_id: u32,
 ) -> io::Result<(SectionData, usize)> {
@@ -945,15 +1013,16 @@ async fn encode_section(
     // We could instead use a `Cursor<&mut [u8]>`, but we would need to be a bit
     // more careful here around safety
     for item in items_in_section.iter() {
-        bytes.write_u32::<NativeEndian>(chrom_id)?;
-        bytes.write_u32::<NativeEndian>(item.start)?;
-        bytes.write_u32::<NativeEndian>(item.end)?;
+        endianness.write_u32(&mut bytes, chrom_id)?;
+        endianness.write_u32(&mut bytes, item.start)?;
+        endianness.write_u32(&mut bytes, item.end)?;
         bytes.write_all(item.rest.as_bytes())?;
         bytes.write_all(&[b'\0'])?;
     }
 
-    let (out_bytes, uncompress_buf_size) = if compress {
-        let mut compressor = Compressor::new(CompressionLvl::default());
+    let (out_bytes, uncompress_buf_size) = if compression_level > 0 {
+        let level = CompressionLvl::new(compression_level as i32).unwrap_or_default();
+        let mut compressor = Compressor::new(level);
         let max_sz = compressor.zlib_compress_bound(bytes.len());
         let mut compressed_data = vec![0; max_sz];
         let actual_sz = compressor
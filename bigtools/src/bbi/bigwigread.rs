@@ -38,20 +38,23 @@ assert_eq!(first_interval.value, 0.06792);
 # }
 ```
 */
-use std::borrow::BorrowMut;
+use std::borrow::{BorrowMut, Cow};
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
-use std::io::{self, Seek, SeekFrom};
-use std::path::Path;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::vec::Vec;
 
 use byteordered::{ByteOrdered, Endianness};
-use bytes::{Buf, BytesMut};
+use bytes::Buf;
+use itertools::Either;
 use thiserror::Error;
 
-use crate::bbi::{BBIFile, Summary, Value};
+use crate::bbi::{BBIFile, Summary, Value, ZoomRecord};
 use crate::bbiread::{
-    read_info, BBIFileInfo, BBIFileReadInfoError, BBIRead, BBIReadError, Block, ChromInfo,
-    ZoomIntervalIter,
+    next_coalesced_blocks, read_cir_tree_item_count, read_cir_tree_leaves, read_info,
+    recycle_block_data, search_cir_tree_leaves, BBIFileInfo, BBIFileReadInfoError, BBIRead,
+    BBIReadError, BBIReadOptions, Block, ChromInfo, CirTreeNodeLeaf, ZoomIntervalIter,
 };
 use crate::internal::BBIReadInternal;
 use crate::utils::reopen::{Reopen, ReopenableFile, SeekableRead};
@@ -62,10 +65,15 @@ pub struct BigWigIntervalIter<R, B> {
     bigwig: B,
     known_offset: u64,
     blocks: std::vec::IntoIter<Block>,
-    vals: Option<std::vec::IntoIter<Value>>,
+    block_data: std::vec::IntoIter<(Block, Vec<u8>)>,
+    vals: Option<BlockValues>,
     chrom: u32,
     start: u32,
     end: u32,
+    /// If `true`, `blocks` is in descending offset order and the values within
+    /// each block are emitted last-to-first, so that values are returned in
+    /// descending `start` order.
+    reverse: bool,
 }
 
 impl<R> Into<BigWigRead<R>> for BigWigIntervalIter<R, BigWigRead<R>> {
@@ -84,7 +92,11 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             match &mut self.vals {
-                Some(vals) => match vals.next() {
+                Some(vals) => match if self.reverse {
+                    vals.next_back()
+                } else {
+                    vals.next()
+                } {
                     Some(v) => {
                         return Some(Ok(v));
                     }
@@ -93,12 +105,32 @@ where
                     }
                 },
                 None => {
-                    // TODO: Could minimize this by chunking block reads
-                    let current_block = self.blocks.next()?;
-                    match get_block_values(
-                        self.bigwig.borrow_mut(),
-                        current_block,
-                        &mut self.known_offset,
+                    let (current_block, data) = match self.block_data.next() {
+                        Some(block_data) => block_data,
+                        None => {
+                            let bigwig = self.bigwig.borrow_mut();
+                            let blocks = next_coalesced_blocks(&mut self.blocks, &bigwig.options);
+                            if blocks.is_empty() {
+                                return None;
+                            }
+                            match bigwig.read.get_blocks_data(&bigwig.info, &blocks) {
+                                Ok(data) => {
+                                    self.block_data = blocks
+                                        .into_iter()
+                                        .zip(data)
+                                        .collect::<Vec<_>>()
+                                        .into_iter();
+                                }
+                                Err(e) => return Some(Err(e.into())),
+                            }
+                            continue;
+                        }
+                    };
+                    self.known_offset = current_block.offset + current_block.size;
+                    let bigwig = self.bigwig.borrow_mut();
+                    match bigwig.block_values(
+                        &current_block,
+                        data,
                         self.chrom,
                         self.start,
                         self.end,
@@ -117,6 +149,335 @@ where
     }
 }
 
+/// An `Iterator` over every `Value` in a bigWig, in file order. See
+/// [`BigWigRead::scan_all`].
+pub struct BigWigScanIter<'a, R> {
+    bigwig: &'a mut BigWigRead<R>,
+    chrom_names: HashMap<u32, String>,
+    blocks: std::vec::IntoIter<Block>,
+    block_data: std::vec::IntoIter<(Block, Vec<u8>)>,
+    vals: Option<(String, BlockValues)>,
+}
+
+impl<'a, R: BBIFileRead> Iterator for BigWigScanIter<'a, R> {
+    type Item = Result<(String, Value), BBIReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((chrom, vals)) = &mut self.vals {
+                match vals.next() {
+                    Some(v) => return Some(Ok((chrom.clone(), v))),
+                    None => self.vals = None,
+                }
+            }
+            let (block, data) = match self.block_data.next() {
+                Some(block_data) => block_data,
+                None => {
+                    let blocks = next_coalesced_blocks(&mut self.blocks, &self.bigwig.options);
+                    if blocks.is_empty() {
+                        return None;
+                    }
+                    match self.bigwig.read.get_blocks_data(&self.bigwig.info, &blocks) {
+                        Ok(data) => {
+                            self.block_data =
+                                blocks.into_iter().zip(data).collect::<Vec<_>>().into_iter()
+                        }
+                        Err(e) => return Some(Err(e.into())),
+                    }
+                    continue;
+                }
+            };
+            let chrom_id = match block_chrom_id(&self.bigwig.info, &data) {
+                Some(chrom_id) => chrom_id,
+                None => {
+                    return Some(Err(BBIReadError::InvalidFile(
+                        "Block is too short to contain a header.".to_owned(),
+                    )))
+                }
+            };
+            let chrom = match self.chrom_names.get(&chrom_id) {
+                Some(chrom) => chrom.clone(),
+                None => {
+                    return Some(Err(BBIReadError::InvalidFile(format!(
+                        "Block has an unknown chromosome id: {}",
+                        chrom_id
+                    ))))
+                }
+            };
+            match self
+                .bigwig
+                .block_values(&block, data, chrom_id, 0, u32::MAX)
+            {
+                Ok(Some(vals)) => self.vals = Some((chrom, vals)),
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// An `Iterator` over every `Value` in a bigWig, along with its chromosome,
+/// in chromosome order. See [`BigWigRead::iter_all_sorted`].
+pub struct BigWigSortedIter<'a, R> {
+    chroms: std::vec::IntoIter<String>,
+    current: Option<(String, BigWigIntervalIter<R, &'a mut BigWigRead<R>>)>,
+    prefetch: Option<std::sync::mpsc::Receiver<PrefetchedBlocks>>,
+}
+
+/// A chromosome and a batch of its (block, data) pairs read ahead by the
+/// prefetch worker of a [`BigWigSortedIter`].
+type PrefetchedBlocks = (String, Result<Vec<(Block, Vec<u8>)>, BBIReadError>);
+
+/// The maximum (compressed) size of the blocks in each batch read by the
+/// prefetch worker of a [`BigWigSortedIter`], unless a single block is larger.
+#[cfg(not(target_family = "wasm"))]
+const PREFETCH_BATCH_SIZE: u64 = 1 << 20;
+
+// Threads aren't available on wasm
+#[cfg(not(target_family = "wasm"))]
+impl<'a, R> BigWigSortedIter<'a, R>
+where
+    R: BBIFileRead + Reopen + Send + 'static,
+{
+    /// Enables or disables prefetching. When enabled, a worker thread with
+    /// its own reopened reader reads (and decompresses) the upcoming blocks
+    /// while the current ones are being iterated. Blocks are read in batches
+    /// of about 1 MiB of file data, and the worker stays at most two batches
+    /// ahead, so memory use doesn't grow with the size of a chromosome. This
+    /// is mainly useful for slow (e.g. remote) storage. The values returned
+    /// are the same either way.
+    pub fn set_prefetch(&mut self, prefetch: bool) -> Result<(), BBIReadError> {
+        // Take the rest of the current chromosome from any previous worker,
+        // then drop the receiver to stop it
+        if let Some(receiver) = self.prefetch.take() {
+            if let Some((chrom, vals)) = self.current.as_mut() {
+                let mut block_data: Vec<_> = vals.block_data.by_ref().collect();
+                while let Ok((prefetched_chrom, data)) = receiver.recv() {
+                    if prefetched_chrom != *chrom {
+                        break;
+                    }
+                    block_data.extend(data?);
+                }
+                vals.block_data = block_data.into_iter();
+            }
+        }
+        if !prefetch {
+            return Ok(());
+        }
+        let Some((_, vals)) = self.current.as_ref() else {
+            return Ok(());
+        };
+        let mut bigwig = vals.bigwig.reopen()?;
+        let chroms: Vec<String> = self.chroms.as_slice().to_vec();
+        let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+        std::thread::spawn(move || {
+            for chrom in chroms {
+                if !bigwig.send_chrom_blocks(&chrom, &sender) {
+                    return;
+                }
+            }
+        });
+        self.prefetch = Some(receiver);
+        Ok(())
+    }
+}
+
+impl<'a, R: BBIFileRead> Iterator for BigWigSortedIter<'a, R> {
+    type Item = Result<(String, Value), BBIReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (chrom, vals) = self.current.as_mut()?;
+            match vals.next() {
+                Some(Ok(v)) => return Some(Ok((chrom.clone(), v))),
+                Some(Err(e)) => return Some(Err(e)),
+                None => {}
+            }
+            // Move on to the next batch of blocks or the next chromosome,
+            // reusing the borrow of the bigWig
+            let (current_chrom, vals) = self.current.take().unwrap();
+            let prefetched = self
+                .prefetch
+                .as_ref()
+                .and_then(|receiver| receiver.recv().ok());
+            let (chrom, vals) = match prefetched {
+                Some((chrom, data)) => {
+                    if chrom != current_chrom {
+                        let next_chrom = self.chroms.next()?;
+                        debug_assert_eq!(next_chrom, chrom);
+                    }
+                    let vals =
+                        data.and_then(|data| vals.bigwig.get_interval_prefetched(&chrom, data));
+                    (chrom, vals)
+                }
+                // Without prefetching (or if the worker has stopped), read directly
+                None => {
+                    let chrom = self.chroms.next()?;
+                    let vals = vals.bigwig.get_interval_all(&chrom);
+                    (chrom, vals)
+                }
+            };
+            match vals {
+                Ok(vals) => self.current = Some((chrom, vals)),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// An `Iterator` over either the `ZoomRecord`s or the full-resolution `Value`s
+/// of a region, depending on the resolution needed. See
+/// [`BigWigRead::get_interval_for_pixels`].
+pub struct PixelIntervalIter<'a, R> {
+    inner: PixelIntervals<'a, R>,
+}
+
+enum PixelIntervals<'a, R> {
+    Zoom(ZoomIntervalIter<BigWigRead<R>, &'a mut BigWigRead<R>>),
+    Full(BigWigIntervalIter<R, &'a mut BigWigRead<R>>),
+}
+
+impl<'a, R: BBIFileRead> Iterator for PixelIntervalIter<'a, R> {
+    type Item = Result<Either<ZoomRecord, Value>, BBIReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            PixelIntervals::Zoom(records) => Some(records.next()?.map(Either::Left)),
+            PixelIntervals::Full(values) => Some(values.next()?.map(Either::Right)),
+        }
+    }
+}
+
+/// An `Iterator` adapter that fuses adjacent `Value`s that have the same
+/// value (`prev.end == next.start`) into one spanning `Value`. Two adjacent
+/// `NaN` values are considered equal and are merged, but a `NaN` is never
+/// merged with a non-`NaN` value.
+pub struct MergedValueIter<I> {
+    inner: I,
+    pending: Option<Value>,
+}
+
+impl<I> MergedValueIter<I> {
+    pub fn new(inner: I) -> Self {
+        MergedValueIter {
+            inner,
+            pending: None,
+        }
+    }
+}
+
+impl<I> Iterator for MergedValueIter<I>
+where
+    I: Iterator<Item = Result<Value, BBIReadError>>,
+{
+    type Item = Result<Value, BBIReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let next = match self.inner.next() {
+                Some(Ok(next)) => next,
+                Some(Err(e)) => return Some(Err(e)),
+                None => return self.pending.take().map(Ok),
+            };
+            let Some(prev) = self.pending.as_mut() else {
+                self.pending = Some(next);
+                continue;
+            };
+            let same_value =
+                prev.value == next.value || (prev.value.is_nan() && next.value.is_nan());
+            if prev.end == next.start && same_value {
+                prev.end = next.end;
+                continue;
+            }
+            return self.pending.replace(next).map(Ok);
+        }
+    }
+}
+
+/// An anomaly in the data of a bigWig, found by [`BigWigRead::validate`].
+/// Each is for the block at `offset`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationIssue {
+    /// The block couldn't be read (e.g. it failed to decompress)
+    UnreadableBlock { offset: u64, error: String },
+    /// The block is too short to contain a header
+    ShortBlock { offset: u64 },
+    /// The block is for a chromosome id that isn't in the chromosome tree
+    UnknownChromosome { offset: u64, chrom_id: u32 },
+    /// The block has an unknown section type
+    InvalidSection { offset: u64, section_type: u8 },
+    /// The block's data doesn't contain the number of items declared in its
+    /// header
+    ItemCountMismatch {
+        offset: u64,
+        declared: u16,
+        actual: usize,
+    },
+    /// The block's start (from its header) is past the end of its chromosome
+    BlockOutOfRange {
+        offset: u64,
+        chrom: String,
+        start: u32,
+    },
+    /// A value ends before it starts, or ends past the end of its chromosome
+    ValueOutOfRange {
+        offset: u64,
+        chrom: String,
+        start: u32,
+        end: u32,
+    },
+    /// The block's values couldn't be decoded (e.g. their positions overflow)
+    UndecodableBlock { offset: u64, error: String },
+}
+
+/// The result of [`BigWigRead::validate`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    /// The number of blocks checked
+    pub blocks: usize,
+    /// The number of values decoded
+    pub values: u64,
+    /// The anomalies found, in file order
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether no anomalies were found
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Statistics on a bigWig, gathered for diagnostics (e.g. a
+/// `bigWigInfo`-style report). See [`BigWigRead::file_stats`].
+#[derive(Clone, Debug)]
+pub struct FileStats {
+    pub version: u16,
+    pub endianness: Endianness,
+    /// Whether data and zoom blocks are compressed
+    pub compressed: bool,
+    /// The size of the buffer needed to decompress any block, or 0 if blocks
+    /// are uncompressed
+    pub uncompress_buf_size: u32,
+    pub chrom_count: usize,
+    /// The size of the full data section, in bytes
+    pub primary_data_size: u64,
+    /// The number of full data blocks
+    pub data_blocks: u64,
+    /// The zoom levels, in the order they are stored
+    pub zooms: Vec<ZoomStats>,
+    /// The total summary. See [`BigWigRead::get_summary`].
+    pub summary: Summary,
+}
+
+/// Statistics on a zoom level. See [`FileStats`].
+#[derive(Copy, Clone, Debug)]
+pub struct ZoomStats {
+    pub reduction_level: u32,
+    /// The number of blocks of zoom records
+    pub blocks: u64,
+}
+
 /// Possible errors encountered when opening a bigWig file to read
 #[derive(Debug, Error)]
 pub enum BigWigReadOpenError {
@@ -124,8 +485,16 @@ pub enum BigWigReadOpenError {
     NotABigWig,
     #[error("InvalidChroms")]
     InvalidChroms,
+    #[error("Truncated")]
+    Truncated,
     #[error("{}", .0)]
     IoError(io::Error),
+    /// An error when opening the file at `path` (from `open_file`)
+    #[error("Error when opening {}: {}", path.display(), source)]
+    FileError {
+        path: PathBuf,
+        source: Box<BigWigReadOpenError>,
+    },
 }
 
 impl From<io::Error> for BigWigReadOpenError {
@@ -144,10 +513,111 @@ impl From<BBIFileReadInfoError> for BigWigReadOpenError {
     }
 }
 
+/// The statistic to compute over a region with [`BigWigRead::stats`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StatKind {
+    /// The mean value over covered bases.
+    Mean,
+    /// The maximum value.
+    Max,
+    /// The minimum value.
+    Min,
+    /// The sum of values, weighted by the number of bases each covers.
+    Sum,
+    /// The fraction of bases in the region that have data. Bases with no data
+    /// (those that would be `NAN` in [`BigWigRead::values`]) count as
+    /// uncovered, so this is in the range `[0, 1]`.
+    Coverage,
+    /// The sample standard deviation of values over covered bases.
+    StdDev,
+}
+
+/// Accumulates summary data over a region, either from raw values or from
+/// zoom records. Zoom records that only partially overlap the region are
+/// weighted by the fraction of the record that overlaps.
+#[derive(Copy, Clone, Debug)]
+struct RegionSummary {
+    bases_covered: f64,
+    min_val: f64,
+    max_val: f64,
+    sum: f64,
+    sum_squares: f64,
+}
+
+impl RegionSummary {
+    fn new() -> Self {
+        RegionSummary {
+            bases_covered: 0.0,
+            min_val: f64::MAX,
+            max_val: f64::MIN,
+            sum: 0.0,
+            sum_squares: 0.0,
+        }
+    }
+
+    fn add_value(&mut self, value: &Value, start: u32, end: u32) {
+        let overlap = value.end.min(end).saturating_sub(value.start.max(start));
+        if overlap == 0 {
+            return;
+        }
+        let overlap = f64::from(overlap);
+        let val = f64::from(value.value);
+        self.bases_covered += overlap;
+        self.min_val = self.min_val.min(val);
+        self.max_val = self.max_val.max(val);
+        self.sum += overlap * val;
+        self.sum_squares += overlap * val * val;
+    }
+
+    fn add_zoom_record(&mut self, record: &ZoomRecord, start: u32, end: u32) {
+        let overlap = record.end.min(end).saturating_sub(record.start.max(start));
+        if overlap == 0 || record.summary.bases_covered == 0 {
+            return;
+        }
+        let weight = f64::from(overlap) / f64::from(record.end - record.start);
+        self.bases_covered += record.summary.bases_covered as f64 * weight;
+        self.min_val = self.min_val.min(record.summary.min_val);
+        self.max_val = self.max_val.max(record.summary.max_val);
+        self.sum += record.summary.sum * weight;
+        self.sum_squares += record.summary.sum_squares * weight;
+    }
+
+    /// Returns the requested statistic for a region `size` bases long. Returns
+    /// `NAN` for everything but `Sum` and `Coverage` if nothing is covered.
+    fn stat(&self, kind: StatKind, size: u32) -> f64 {
+        if self.bases_covered == 0.0 {
+            return match kind {
+                StatKind::Sum | StatKind::Coverage => 0.0,
+                _ => f64::NAN,
+            };
+        }
+        match kind {
+            StatKind::Mean => self.sum / self.bases_covered,
+            StatKind::Max => self.max_val,
+            StatKind::Min => self.min_val,
+            StatKind::Sum => self.sum,
+            StatKind::Coverage => (self.bases_covered / f64::from(size)).min(1.0),
+            StatKind::StdDev => {
+                if self.bases_covered <= 1.0 {
+                    return 0.0;
+                }
+                let var = (self.sum_squares - (self.sum * self.sum) / self.bases_covered)
+                    / (self.bases_covered - 1.0);
+                var.max(0.0).sqrt()
+            }
+        }
+    }
+}
+
 /// The struct used to read a bigWig file
 pub struct BigWigRead<R> {
     pub(super) info: BBIFileInfo,
     pub(super) read: R,
+    pub options: BBIReadOptions,
+    /// Blocks skipped because of an unknown section type
+    pub(super) skipped_blocks: Vec<Block>,
+    /// Alternative names for chromosomes, mapped to the name in the file
+    pub(super) chrom_aliases: HashMap<String, String>,
 }
 
 impl<R: Reopen> Reopen for BigWigRead<R> {
@@ -155,6 +625,9 @@ impl<R: Reopen> Reopen for BigWigRead<R> {
         Ok(BigWigRead {
             info: self.info.clone(),
             read: self.read.reopen()?,
+            options: self.options,
+            skipped_blocks: vec![],
+            chrom_aliases: self.chrom_aliases.clone(),
         })
     }
 }
@@ -192,24 +665,138 @@ impl<R> BigWigRead<R> {
         &self.info.chrom_info
     }
 
+    /// Sets alternative names for chromosomes, each mapped to the name of a
+    /// chromosome in this bigWig (for example, `"1"` to `"chr1"`). A query for
+    /// a chromosome that isn't in this bigWig then uses the chromosome it is
+    /// an alias of. Names that are neither in this bigWig nor an alias of one
+    /// of its chromosomes are still `BBIReadError::InvalidChromosome`.
+    pub fn set_chrom_aliases(&mut self, aliases: HashMap<String, String>) {
+        self.chrom_aliases = aliases;
+    }
+
+    /// The name of the chromosome in this bigWig that `chrom_name` refers to:
+    /// either `chrom_name` itself, or the chromosome it is an alias of.
+    fn resolve_chrom<'n>(&self, chrom_name: &'n str) -> Cow<'n, str> {
+        if self.info.chrom_info_named(chrom_name).is_none() {
+            if let Some(name) = self.chrom_aliases.get(chrom_name) {
+                if self.info.chrom_info_named(name).is_some() {
+                    return Cow::Owned(name.clone());
+                }
+            }
+        }
+        Cow::Borrowed(chrom_name)
+    }
+
+    /// The mapping of chromosome names to the ids used within this bigWig.
+    /// See [`BBIFileInfo::chrom_ids`].
+    pub fn chrom_ids(&self) -> HashMap<String, u32> {
+        self.info.chrom_ids()
+    }
+
     /// Consumes this `BigWigRead`, returning the underlying reader.
     pub fn into_inner(self) -> R {
         self.read
     }
+
+    /// The reduction levels of the zoom levels in this bigWig, sorted from
+    /// finest to coarsest. These are the valid `reduction_level`s for
+    /// `get_zoom_interval`.
+    pub fn zoom_levels(&self) -> Vec<u32> {
+        self.info.zoom_levels()
+    }
+
+    /// The coarsest zoom level with a reduction level at or below `desired`
+    /// (i.e. the zoom level that is closest to, but not coarser than, a
+    /// desired resolution). Returns `None` if there is no such zoom level.
+    pub fn closest_zoom_level(&self, desired: u32) -> Option<u32> {
+        self.info.closest_zoom_level(desired)
+    }
 }
 
 impl BigWigRead<ReopenableFile> {
-    /// Opens a new `BigWigRead` from a given path as a file.
+    /// Opens a new `BigWigRead` from the file at `path`. Any error is returned
+    /// as `BigWigReadOpenError::FileError`, with the path.
     pub fn open_file(path: impl AsRef<Path>) -> Result<Self, BigWigReadOpenError> {
+        let path = path.as_ref();
+        let with_path = |source| BigWigReadOpenError::FileError {
+            path: path.to_owned(),
+            source: Box::new(source),
+        };
         let reopen = ReopenableFile {
-            file: File::open(&path)?,
-            path: path.as_ref().to_owned(),
+            file: File::open(path).map_err(|e| with_path(e.into()))?,
+            path: path.to_owned(),
         };
-        let b = BigWigRead::open(reopen);
-        if b.is_err() {
-            eprintln!("Error when opening: {:?}", path.as_ref());
+        BigWigRead::open(reopen).map_err(with_path)
+    }
+}
+
+// Threads aren't available on wasm
+#[cfg(not(target_family = "wasm"))]
+impl<R> BigWigRead<R>
+where
+    R: BBIFileRead + Reopen + Send,
+{
+    /// For a given chromosome, start, and end, returns the intersecting
+    /// `Value`s, in genomic order. The overlapping blocks are read and
+    /// decompressed concurrently on up to `nthreads` threads, each with its
+    /// own reopened reader. This is mainly useful for wide queries over slow
+    /// (e.g. remote) storage; the values are collected into memory.
+    pub fn get_interval_parallel(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        nthreads: usize,
+    ) -> Result<Vec<Value>, BBIReadError> {
+        let chrom_name = &*self.resolve_chrom(chrom_name);
+        let chrom = self.info.chrom_id(chrom_name)?;
+        let cir_tree = self.full_data_cir_tree()?;
+        let mut blocks =
+            search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
+        blocks.sort_by_key(|b| b.offset);
+        if blocks.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let chunk_size = blocks.len().div_ceil(nthreads.max(1));
+        let readers = blocks
+            .chunks(chunk_size)
+            .map(|_| self.reopen())
+            .collect::<io::Result<Vec<_>>>()?;
+        let chunk_values = std::thread::scope(|scope| {
+            let handles: Vec<_> = blocks
+                .chunks(chunk_size)
+                .zip(readers)
+                .map(|(blocks, mut bigwig)| {
+                    scope.spawn(move || -> Result<(Vec<Value>, Vec<Block>), BBIReadError> {
+                        let mut values = vec![];
+                        let mut known_offset = 0;
+                        for block in blocks {
+                            let block_values = get_block_values(
+                                &mut bigwig,
+                                *block,
+                                &mut known_offset,
+                                chrom,
+                                start,
+                                end,
+                            )?;
+                            values.extend(block_values.into_iter().flatten());
+                        }
+                        Ok((values, bigwig.skipped_blocks))
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("Block reading thread panicked."))
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        let mut values = vec![];
+        for (chunk_values, skipped_blocks) in chunk_values {
+            values.extend(chunk_values);
+            self.skipped_blocks.extend(skipped_blocks);
         }
-        b
+        Ok(values)
     }
 }
 
@@ -218,16 +805,34 @@ where
     R: SeekableRead,
 {
     /// Converts this `BigWigRead`` to where the `BBIFileRead` caches index
-    /// access and block data
+    /// access and block data. Up to `DEFAULT_BLOCK_CACHE_CAPACITY` decompressed
+    /// blocks are kept, evicting the least recently used; see
+    /// `set_block_cache_capacity`.
     pub fn cached(self) -> BigWigRead<CachedBBIFileRead<R>> {
         let read = CachedBBIFileRead::new(self.read);
         BigWigRead {
             read,
             info: self.info,
+            options: self.options,
+            skipped_blocks: self.skipped_blocks,
+            chrom_aliases: self.chrom_aliases,
         }
     }
 }
 
+impl<R: SeekableRead> BigWigRead<CachedBBIFileRead<R>> {
+    /// Sets the maximum number of decompressed blocks to cache. See
+    /// [`CachedBBIFileRead::set_block_cache_capacity`].
+    pub fn set_block_cache_capacity(&mut self, capacity: usize) {
+        self.read.set_block_cache_capacity(capacity);
+    }
+
+    /// Removes all cached decompressed blocks.
+    pub fn clear_block_cache(&mut self) {
+        self.read.clear_block_cache();
+    }
+}
+
 impl<R> BigWigRead<R>
 where
     R: BBIFileRead,
@@ -240,12 +845,44 @@ where
             _ => return Err(BigWigReadOpenError::NotABigWig),
         }
 
-        Ok(BigWigRead { info, read })
+        Ok(BigWigRead {
+            info,
+            read,
+            options: BBIReadOptions::default(),
+            skipped_blocks: vec![],
+            chrom_aliases: HashMap::new(),
+        })
+    }
+
+    /// Like `open`, but also checks that the offsets in the header all point
+    /// within the file, returning `BigWigReadOpenError::Truncated` if they
+    /// don't. This catches truncated files (e.g. from an interrupted
+    /// download) up front, rather than with an unexpected EOF in a later
+    /// query. Note that this seeks to the end of the file to find its length.
+    pub fn open_checked(mut read: R) -> Result<Self, BigWigReadOpenError> {
+        let file_len = read.raw_reader().seek(SeekFrom::End(0))?;
+        read.raw_reader().seek(SeekFrom::Start(0))?;
+        let b = match BigWigRead::open(read) {
+            Err(BigWigReadOpenError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(BigWigReadOpenError::Truncated);
+            }
+            b => b?,
+        };
+        if b.info.is_truncated(file_len) {
+            return Err(BigWigReadOpenError::Truncated);
+        }
+        Ok(b)
     }
 
     /// Does *not* check if the passed `R` matches the provided info (including if the `R` is a bigWig at all!)
     pub fn with_info(info: BBIFileInfo, read: R) -> Self {
-        BigWigRead { info, read }
+        BigWigRead {
+            info,
+            read,
+            options: BBIReadOptions::default(),
+            skipped_blocks: vec![],
+            chrom_aliases: HashMap::new(),
+        }
     }
 
     /// Gets a reference to the inner `R` type, in order to access any info
@@ -253,6 +890,47 @@ where
         &self.read
     }
 
+    /// The blocks that were skipped because they have an unknown section type
+    /// (see [`BBIReadOptions::skip_unknown_sections`]), in the order they were
+    /// read. A block is added each time it is skipped, so a block read by
+    /// several queries is added several times.
+    pub fn skipped_blocks(&self) -> &[Block] {
+        &self.skipped_blocks
+    }
+
+    /// Returns and clears the blocks that were skipped. See
+    /// [`BigWigRead::skipped_blocks`].
+    pub fn take_skipped_blocks(&mut self) -> Vec<Block> {
+        std::mem::take(&mut self.skipped_blocks)
+    }
+
+    /// Decodes the data of `block` (see `block_values_from_data`), skipping
+    /// and recording it if it has an unknown section type and
+    /// `options.skip_unknown_sections` is set.
+    fn block_values(
+        &mut self,
+        block: &Block,
+        data: Vec<u8>,
+        chrom: u32,
+        start: u32,
+        end: u32,
+    ) -> Result<Option<BlockValues>, BBIReadError> {
+        let values = block_values_from_data(&self.info, data, chrom, start, end);
+        skip_unknown_section(values, &self.options, block, &mut self.skipped_blocks)
+    }
+
+    /// Returns the number of items in the bigWig, as stored at the start of
+    /// the full data section. Unlike `get_summary`, this is available for all
+    /// versions.
+    pub fn item_count(&mut self) -> Result<u64, BBIReadError> {
+        let endianness = self.info.header.endianness;
+        let data_offset = self.info.header.data_offset();
+        let reader = self.reader().raw_reader();
+        let mut reader = ByteOrdered::runtime(reader, endianness);
+        reader.seek(SeekFrom::Start(data_offset))?;
+        Ok(reader.read_u64()?)
+    }
+
     /// Returns the summary data from bigWig
     ///
     /// Note: For version 1 of bigWigs, there is no total summary. In that
@@ -261,21 +939,22 @@ where
     /// `info().header.version > 1`.
     pub fn get_summary(&mut self) -> io::Result<Summary> {
         let endianness = self.info.header.endianness;
-        let summary_offset = self.info.header.total_summary_offset;
-        let data_offset = self.info.header.full_data_offset;
+        let summary_offset = self.info.header.summary_offset();
+        let data_offset = self.info.header.data_offset();
         let reader = self.reader().raw_reader();
         let mut reader = ByteOrdered::runtime(reader, endianness);
-        let (bases_covered, min_val, max_val, sum, sum_squares) = if summary_offset != 0 {
-            reader.seek(SeekFrom::Start(summary_offset))?;
-            (
-                reader.read_u64()?,
-                reader.read_f64()?,
-                reader.read_f64()?,
-                reader.read_f64()?,
-                reader.read_f64()?,
-            )
-        } else {
-            (0, 0.0, 0.0, 0.0, 0.0)
+        let (bases_covered, min_val, max_val, sum, sum_squares) = match summary_offset {
+            Some(summary_offset) => {
+                reader.seek(SeekFrom::Start(summary_offset))?;
+                (
+                    reader.read_u64()?,
+                    reader.read_f64()?,
+                    reader.read_f64()?,
+                    reader.read_f64()?,
+                    reader.read_f64()?,
+                )
+            }
+            None => (0, 0.0, 0.0, 0.0, 0.0),
         };
         reader.seek(SeekFrom::Start(data_offset))?;
         let total_items = reader.read_u64()?;
@@ -289,15 +968,70 @@ where
         })
     }
 
+    /// Gathers statistics on this bigWig. Besides the header, this reads the
+    /// total summary and the header of each index.
+    pub fn file_stats(&mut self) -> Result<FileStats, BBIReadError> {
+        let header = &self.info.header;
+        let endianness = header.endianness;
+        let index_offsets: Vec<u64> = std::iter::once(header.full_index_offset)
+            .chain(self.info.zoom_headers.iter().map(|z| z.index_offset))
+            .collect();
+        let mut stats = FileStats {
+            version: header.version,
+            endianness,
+            compressed: header.is_compressed(),
+            uncompress_buf_size: header.uncompress_buf_size,
+            chrom_count: self.info.chrom_info.len(),
+            primary_data_size: header.primary_data_size(),
+            data_blocks: 0,
+            zooms: vec![],
+            summary: self.get_summary()?,
+        };
+        let reduction_levels: Vec<u32> = self
+            .info
+            .zoom_headers
+            .iter()
+            .map(|z| z.reduction_level)
+            .collect();
+
+        let reader = self.reader().raw_reader();
+        let mut block_counts = Vec::with_capacity(index_offsets.len());
+        for offset in index_offsets {
+            reader.seek(SeekFrom::Start(offset))?;
+            let count = read_cir_tree_item_count(endianness, reader).map_err(|e| match e {
+                Either::Left(_) => BBIReadError::UnknownMagic,
+                Either::Right(e) => BBIReadError::IoError(e),
+            })?;
+            block_counts.push(count);
+        }
+        stats.data_blocks = block_counts[0];
+        stats.zooms = reduction_levels
+            .into_iter()
+            .zip(&block_counts[1..])
+            .map(|(reduction_level, blocks)| ZoomStats {
+                reduction_level,
+                blocks: *blocks,
+            })
+            .collect();
+        Ok(stats)
+    }
+
     /// For a given chromosome, start, and end, returns an `Iterator` of the
     /// intersecting `Value`s. The resulting iterator takes a mutable reference
     /// of this `BigWigRead`.
+    ///
+    /// The query is half-open (`[start, end)`), like bed; see
+    /// `get_interval_inclusive` for an inclusive `end`. Values are clipped to
+    /// the query, so a value that only touches the query boundary isn't
+    /// returned, and an empty query returns no values. A zero-width value is
+    /// returned if its position is within the query.
     pub fn get_interval<'a>(
         &'a mut self,
         chrom_name: &str,
         start: u32,
         end: u32,
     ) -> Result<BigWigIntervalIter<R, &'a mut BigWigRead<R>>, BBIReadError> {
+        let chrom_name = &*self.resolve_chrom(chrom_name);
         let chrom = self.info.chrom_id(chrom_name)?;
         let cir_tree = self.full_data_cir_tree()?;
         let blocks = search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
@@ -306,76 +1040,690 @@ where
             bigwig: self,
             known_offset: 0,
             blocks: blocks.into_iter(),
+            block_data: vec![].into_iter(),
             vals: None,
             chrom,
             start,
             end,
+            reverse: false,
         })
     }
 
-    /// For a given chromosome, start, and end, returns an `Iterator` of the
-    /// intersecting `Value`s. The resulting iterator takes this `BigWigRead`
-    /// by value.
-    pub fn get_interval_move(
-        mut self,
+    /// Like `get_interval`, but `end` is inclusive (i.e. the query is
+    /// `[start, end]`), as with some UCSC tools.
+    pub fn get_interval_inclusive<'a>(
+        &'a mut self,
         chrom_name: &str,
         start: u32,
         end: u32,
-    ) -> Result<BigWigIntervalIter<R, BigWigRead<R>>, BBIReadError> {
+    ) -> Result<BigWigIntervalIter<R, &'a mut BigWigRead<R>>, BBIReadError> {
+        self.get_interval(chrom_name, start, end.saturating_add(1))
+    }
+
+    /// Returns an `Iterator` of all the `Value`s on a given chromosome. If the
+    /// chromosome isn't in this bigWig, `BBIReadError::InvalidChromosome` is
+    /// returned. The resulting iterator takes a mutable reference of this
+    /// `BigWigRead`.
+    pub fn get_interval_all<'a>(
+        &'a mut self,
+        chrom_name: &str,
+    ) -> Result<BigWigIntervalIter<R, &'a mut BigWigRead<R>>, BBIReadError> {
+        let chrom_name = &*self.resolve_chrom(chrom_name);
+        let length = self
+            .info
+            .chrom_info_named(chrom_name)
+            .map(|c| c.length)
+            .ok_or_else(|| BBIReadError::InvalidChromosome(chrom_name.to_owned()))?;
+        self.get_interval(chrom_name, 0, length)
+    }
+
+    /// Reads (and decompresses) the data of every block of a chromosome, as
+    /// would be read by `get_interval_all`, and sends it to `sender` in
+    /// batches of at most `PREFETCH_BATCH_SIZE` of blocks. At least one
+    /// (possibly empty) batch is sent. Returns `false` if an error was sent
+    /// or the receiver is gone.
+    #[cfg(not(target_family = "wasm"))]
+    fn send_chrom_blocks(
+        &mut self,
+        chrom_name: &str,
+        sender: &std::sync::mpsc::SyncSender<PrefetchedBlocks>,
+    ) -> bool {
+        let mut send_batches = || -> Result<bool, BBIReadError> {
+            let length = self
+                .info
+                .chrom_info_named(chrom_name)
+                .map(|c| c.length)
+                .ok_or_else(|| BBIReadError::InvalidChromosome(chrom_name.to_owned()))?;
+            let mut blocks = self.get_overlapping_blocks(chrom_name, 0, length)?;
+            loop {
+                let mut len = 0;
+                let mut size = 0;
+                while len < blocks.len()
+                    && (len == 0 || size + blocks[len].size <= PREFETCH_BATCH_SIZE)
+                {
+                    size += blocks[len].size;
+                    len += 1;
+                }
+                let rest = blocks.split_off(len);
+                let mut batch = std::mem::replace(&mut blocks, rest).into_iter();
+                let mut block_data = vec![];
+                loop {
+                    let blocks = next_coalesced_blocks(&mut batch, &self.options);
+                    if blocks.is_empty() {
+                        break;
+                    }
+                    let data = self.read.get_blocks_data(&self.info, &blocks)?;
+                    block_data.extend(blocks.into_iter().zip(data));
+                }
+                if sender
+                    .send((chrom_name.to_owned(), Ok(block_data)))
+                    .is_err()
+                {
+                    return Ok(false);
+                }
+                if blocks.is_empty() {
+                    return Ok(true);
+                }
+            }
+        };
+        match send_batches() {
+            Ok(sent) => sent,
+            Err(e) => {
+                let _ = sender.send((chrom_name.to_owned(), Err(e)));
+                false
+            }
+        }
+    }
+
+    /// Returns the same `Iterator` as `get_interval_all`, but over block data
+    /// that has already been read with `send_chrom_blocks`.
+    fn get_interval_prefetched<'a>(
+        &'a mut self,
+        chrom_name: &str,
+        block_data: Vec<(Block, Vec<u8>)>,
+    ) -> Result<BigWigIntervalIter<R, &'a mut BigWigRead<R>>, BBIReadError> {
+        let length = self
+            .info
+            .chrom_info_named(chrom_name)
+            .map(|c| c.length)
+            .ok_or_else(|| BBIReadError::InvalidChromosome(chrom_name.to_owned()))?;
         let chrom = self.info.chrom_id(chrom_name)?;
-        let cir_tree = self.full_data_cir_tree()?;
-        let blocks = search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
         Ok(BigWigIntervalIter {
             r: std::marker::PhantomData,
             bigwig: self,
             known_offset: 0,
-            blocks: blocks.into_iter(),
+            blocks: vec![].into_iter(),
+            block_data: block_data.into_iter(),
             vals: None,
             chrom,
-            start,
-            end,
+            start: 0,
+            end: length,
+            reverse: false,
         })
     }
 
     /// For a given chromosome, start, and end, returns an `Iterator` of the
-    /// intersecting `ZoomRecord`s.
-    pub fn get_zoom_interval<'a>(
+    /// intersecting `Value`s, where adjacent `Value`s with equal values are
+    /// merged into one. See [`MergedValueIter`] for details. The resulting
+    /// iterator takes a mutable reference of this `BigWigRead`.
+    pub fn get_interval_merged<'a>(
         &'a mut self,
         chrom_name: &str,
         start: u32,
         end: u32,
-        reduction_level: u32,
-    ) -> Result<ZoomIntervalIter<BigWigRead<R>, &'a mut BigWigRead<R>>, ZoomIntervalError> {
-        let cir_tree = self.zoom_cir_tree(reduction_level)?;
-
-        let chrom = self.info.chrom_id(chrom_name)?;
-
-        let blocks = search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
-
-        Ok(ZoomIntervalIter::new(
-            self,
-            blocks.into_iter(),
-            chrom,
-            start,
-            end,
+    ) -> Result<MergedValueIter<BigWigIntervalIter<R, &'a mut BigWigRead<R>>>, BBIReadError> {
+        Ok(MergedValueIter::new(
+            self.get_interval(chrom_name, start, end)?,
         ))
     }
 
     /// For a given chromosome, start, and end, returns an `Iterator` of the
-    /// intersecting `ZoomRecord`s.
-    pub fn get_zoom_interval_move<'a>(
-        mut self,
+    /// intersecting `Value`s, in descending `start` order. The resulting
+    /// iterator takes a mutable reference of this `BigWigRead`.
+    pub fn get_interval_rev<'a>(
+        &'a mut self,
         chrom_name: &str,
         start: u32,
         end: u32,
-        reduction_level: u32,
-    ) -> Result<ZoomIntervalIter<BigWigRead<R>, BigWigRead<R>>, ZoomIntervalError> {
-        let cir_tree = self.zoom_cir_tree(reduction_level)?;
-
+    ) -> Result<BigWigIntervalIter<R, &'a mut BigWigRead<R>>, BBIReadError> {
+        let chrom_name = &*self.resolve_chrom(chrom_name);
         let chrom = self.info.chrom_id(chrom_name)?;
-
-        let blocks = search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
-
+        let cir_tree = self.full_data_cir_tree()?;
+        let mut blocks =
+            search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
+        blocks.sort_by_key(|b| std::cmp::Reverse(b.offset));
+        Ok(BigWigIntervalIter {
+            r: std::marker::PhantomData,
+            bigwig: self,
+            known_offset: 0,
+            blocks: blocks.into_iter(),
+            block_data: vec![].into_iter(),
+            vals: None,
+            chrom,
+            start,
+            end,
+            reverse: true,
+        })
+    }
+
+    /// Writes the `Value`s between `start` and `end` on a chromosome to `out`
+    /// as bedGraph lines (`chrom\tstart\tend\tvalue\n`). Values are written in
+    /// their shortest form that round-trips, without trailing zeros (e.g.
+    /// `0.5`, `1`). `out` is not buffered here, so should generally be a
+    /// `BufWriter`.
+    pub fn write_bedgraph<W: Write>(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        out: &mut W,
+    ) -> Result<(), BBIReadError> {
+        for value in self.get_interval(chrom_name, start, end)? {
+            let value = value?;
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}",
+                chrom_name, value.start, value.end, value.value
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the `Value`s between `start` and `end` on a chromosome to `out`
+    /// as wig. Runs of at least three values with the same span and a
+    /// constant step are written as `fixedStep` sections, and everything else
+    /// as `variableStep` sections (with a new section whenever the span
+    /// changes). If `step_hint` is set, only runs with that step are written
+    /// as `fixedStep`. Zero-width values can't be represented in wig, so are
+    /// skipped. Wig coordinates are 1-based.
+    pub fn write_wig<W: Write>(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        out: &mut W,
+        step_hint: Option<u32>,
+    ) -> Result<(), BBIReadError> {
+        const MIN_FIXED_STEP_RUN: usize = 3;
+
+        let mut values = self
+            .get_interval(chrom_name, start, end)?
+            .collect::<Result<Vec<_>, _>>()?;
+        values.retain(|v| v.end > v.start);
+        // The span of the current `variableStep` section, if in one
+        let mut variable_span = None;
+        let mut i = 0;
+        while i < values.len() {
+            let first = values[i];
+            let span = first.end - first.start;
+            let step = values.get(i + 1).map(|v| v.start - first.start);
+            let mut run_end = i + 1;
+            // Values that start at the same position (a step of 0) can't be
+            // written as `fixedStep`
+            let step = step.filter(|s| *s > 0 && *s >= span && step_hint.is_none_or(|h| h == *s));
+            if let Some(step) = step {
+                while run_end < values.len()
+                    && values[run_end].end - values[run_end].start == span
+                    && values[run_end].start - values[run_end - 1].start == step
+                {
+                    run_end += 1;
+                }
+                if run_end - i >= MIN_FIXED_STEP_RUN {
+                    writeln!(
+                        out,
+                        "fixedStep chrom={} start={} step={} span={}",
+                        chrom_name,
+                        first.start + 1,
+                        step,
+                        span
+                    )?;
+                    for value in &values[i..run_end] {
+                        writeln!(out, "{}", value.value)?;
+                    }
+                    variable_span = None;
+                    i = run_end;
+                    continue;
+                }
+            }
+            if variable_span != Some(span) {
+                writeln!(out, "variableStep chrom={} span={}", chrom_name, span)?;
+                variable_span = Some(span);
+            }
+            writeln!(out, "{}\t{}", first.start + 1, first.value)?;
+            i += 1;
+        }
+        Ok(())
+    }
+
+    /// Writes every `Value` in this bigWig to `out` as bedGraph lines, going
+    /// through the chromosomes in the order of `chroms`. See
+    /// [`BigWigRead::write_bedgraph`].
+    pub fn write_bedgraph_all<W: Write>(&mut self, out: &mut W) -> Result<(), BBIReadError> {
+        let chroms = self.info.chrom_info.clone();
+        for chrom in chroms {
+            self.write_bedgraph(&chrom.name, 0, chrom.length, out)?;
+        }
+        Ok(())
+    }
+
+    /// Returns every `Value` in this bigWig, along with its chromosome, in a
+    /// single forward pass over the data section. The leaves of the index are
+    /// read once to find every block; blocks are then read in the order they
+    /// are stored in the file, with nearby blocks read together (up to
+    /// `options.max_coalesce_bytes` at a time, so memory use doesn't grow with
+    /// the file). This is generally much faster than querying each chromosome
+    /// when reading an entire file.
+    ///
+    /// Values are returned in file order, which is not necessarily genomic
+    /// order (though it is for files written by `bigtools` or the kent tools).
+    pub fn scan_all(&mut self) -> Result<BigWigScanIter<'_, R>, BBIReadError> {
+        let blocks: Vec<Block> = self
+            .full_data_leaves()?
+            .into_iter()
+            .map(|leaf| Block {
+                offset: leaf.data_offset,
+                size: leaf.data_size,
+            })
+            .collect();
+        let chrom_names = self
+            .info
+            .chrom_info
+            .iter()
+            .map(|c| (c.id, c.name.clone()))
+            .collect();
+        Ok(BigWigScanIter {
+            bigwig: self,
+            chrom_names,
+            blocks: blocks.into_iter(),
+            block_data: vec![].into_iter(),
+            vals: None,
+        })
+    }
+
+    /// Reads every leaf of the full data index, sorted by the offset of its
+    /// block. Unlike searching the index for each chromosome, this also finds
+    /// blocks outside of the chromosomes in the chromosome tree.
+    fn full_data_leaves(&mut self) -> Result<Vec<CirTreeNodeLeaf>, BBIReadError> {
+        let cir_tree = self.full_data_cir_tree()?;
+        let mut leaves = read_cir_tree_leaves(
+            self.info.header.endianness,
+            self.read.raw_reader(),
+            cir_tree.1,
+        )?;
+        leaves.sort_by_key(|leaf| leaf.data_offset);
+        leaves.dedup_by_key(|leaf| leaf.data_offset);
+        Ok(leaves)
+    }
+
+    /// Returns every `Value` in this bigWig, along with its chromosome. Unlike
+    /// [`BigWigRead::scan_all`], chromosomes are iterated in the order of
+    /// `chroms()`, and the values of each are those of `get_interval_all`, so
+    /// the order doesn't depend on how the file was written. This is useful
+    /// for exporting or comparing files.
+    pub fn iter_all_sorted(&mut self) -> Result<BigWigSortedIter<'_, R>, BBIReadError> {
+        let mut chroms: std::vec::IntoIter<String> = self
+            .info
+            .chrom_info
+            .iter()
+            .map(|c| c.name.clone())
+            .collect::<Vec<_>>()
+            .into_iter();
+        let current = match chroms.next() {
+            Some(chrom) => {
+                let vals = self.get_interval_all(&chrom)?;
+                Some((chrom, vals))
+            }
+            None => None,
+        };
+        Ok(BigWigSortedIter {
+            chroms,
+            current,
+            prefetch: None,
+        })
+    }
+
+    /// Reads and decodes every block of the full data, checking the block
+    /// headers and values for anomalies: unknown section types, item counts
+    /// that don't match the block size, and blocks or values outside of their
+    /// chromosome. Every block in the index is checked, including any outside
+    /// of the chromosomes in the chromosome tree. Unlike queries, this doesn't
+    /// stop at the first problem, but collects each into the returned report.
+    /// An error is only returned if the index can't be read. Together with
+    /// `open_checked`, this is useful for checking possibly malformed files.
+    pub fn validate(&mut self) -> Result<ValidationReport, BBIReadError> {
+        let leaves = self.full_data_leaves()?;
+        let chroms: HashMap<u32, ChromInfo> = self
+            .info
+            .chrom_info
+            .iter()
+            .map(|c| (c.id, c.clone()))
+            .collect();
+
+        let mut report = ValidationReport::default();
+        for leaf in leaves {
+            let block = Block {
+                offset: leaf.data_offset,
+                size: leaf.data_size,
+            };
+            report.blocks += 1;
+            match self.read.get_block_data(&self.info, &block) {
+                Ok(data) => validate_block(&self.info, &chroms, block.offset, data, &mut report),
+                Err(e) => report.issues.push(ValidationIssue::UnreadableBlock {
+                    offset: block.offset,
+                    error: e.to_string(),
+                }),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Returns the data `Block`s that overlap the given chromosome, start, and
+    /// end, in file order. Together with [`BigWigRead::decode_block`], this can
+    /// be used to implement a custom strategy for reading blocks.
+    pub fn get_overlapping_blocks(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<Block>, BBIReadError> {
+        let chrom_name = &*self.resolve_chrom(chrom_name);
+        let cir_tree = self.full_data_cir_tree()?;
+        let blocks = search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
+        Ok(blocks)
+    }
+
+    /// Counts the data blocks that overlap the given chromosome, start, and
+    /// end. Only the index is read; no block data is read or decompressed.
+    pub fn count_overlapping_blocks(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<usize, BBIReadError> {
+        Ok(self.get_overlapping_blocks(chrom_name, start, end)?.len())
+    }
+
+    /// Estimates the number of items in the blocks that overlap the given
+    /// chromosome, start, and end, as the number of overlapping blocks times
+    /// the maximum number of items per block stored in the index header. This
+    /// is an upper bound, since blocks may not be full and may only partially
+    /// overlap the region. Only the index is read.
+    pub fn estimate_items(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<u64, BBIReadError> {
+        let blocks = self.count_overlapping_blocks(chrom_name, start, end)?;
+        let items_per_slot = self.info.header.items_per_slot.unwrap_or(0);
+        Ok(blocks as u64 * u64::from(items_per_slot))
+    }
+
+    /// Reads and decodes a single `Block`, returning the `Value`s that overlap
+    /// the given chromosome, start, and end. The `Block` is generally one
+    /// returned by [`BigWigRead::get_overlapping_blocks`].
+    pub fn decode_block(
+        &mut self,
+        block: &Block,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<Value>, BBIReadError> {
+        let chrom_name = &*self.resolve_chrom(chrom_name);
+        let chrom = self.info.chrom_id(chrom_name)?;
+        let data = self.read.get_block_data(&self.info, block)?;
+        let values = self.block_values(block, data, chrom, start, end)?;
+        Ok(values.into_iter().flatten().collect())
+    }
+
+    /// Reads the raw bytes of a single `Block`, exactly as they are stored in
+    /// the file (i.e. still compressed, if the file is compressed). This is
+    /// mainly useful for debugging and for checking files against other
+    /// implementations.
+    pub fn read_block_raw(&mut self, block: &Block) -> io::Result<Vec<u8>> {
+        let reader = self.read.raw_reader();
+        reader.seek(SeekFrom::Start(block.offset))?;
+        let mut data = vec![0u8; block.size as usize];
+        reader.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    /// Reads the bytes of a single `Block`, decompressing them if the file is
+    /// compressed. Unlike [`BigWigRead::decode_block`], the data isn't parsed.
+    pub fn read_block_decompressed(&mut self, block: &Block) -> io::Result<Vec<u8>> {
+        self.read.get_block_data(&self.info, block)
+    }
+
+    /// For each of `regions` (chromosome, start, and end), returns the
+    /// intersecting `Value`s. The results are in the same order as `regions`.
+    ///
+    /// Regions are processed sorted by chromosome and start. The index is
+    /// searched once per chromosome, and the decoded values of each block are
+    /// kept until a region starts past its end, so blocks shared by nearby or
+    /// overlapping regions are only read and decompressed once.
+    pub fn get_intervals_multi<S: AsRef<str>>(
+        &mut self,
+        regions: &[(S, u32, u32)],
+    ) -> Result<Vec<Vec<Value>>, BBIReadError> {
+        let mut order: Vec<usize> = (0..regions.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a_chrom, a_start, _) = &regions[a];
+            let (b_chrom, b_start, _) = &regions[b];
+            (a_chrom.as_ref(), a_start).cmp(&(b_chrom.as_ref(), b_start))
+        });
+
+        let mut results = vec![vec![]; regions.len()];
+        let mut order = order.as_slice();
+        while let Some(&first) = order.first() {
+            let chrom_name = regions[first].0.as_ref();
+            let len = order
+                .iter()
+                .take_while(|&&index| regions[index].0.as_ref() == chrom_name)
+                .count();
+            let (chrom_order, rest) = order.split_at(len);
+            order = rest;
+
+            let chrom = self.info.chrom_id(&self.resolve_chrom(chrom_name))?;
+            for &index in chrom_order {
+                let (_, start, end) = regions[index];
+                if start > end {
+                    return Err(BBIReadError::InvalidInterval { start, end });
+                }
+            }
+            let min_start = regions[first].1;
+            let max_end = chrom_order
+                .iter()
+                .map(|&index| regions[index].2)
+                .max()
+                .unwrap_or(min_start);
+            let cir_tree = self.full_data_cir_tree()?;
+            let leaves = search_cir_tree_leaves(
+                self.info.header.endianness,
+                &mut self.read,
+                cir_tree.1,
+                chrom,
+                min_start,
+                max_end,
+            )?;
+
+            // The part of this chromosome each block covers; a block that
+            // spans chromosomes is treated as extending to this one's bounds.
+            let mut leaves = leaves.into_iter().map(|leaf| {
+                let start = if leaf.start_chrom_ix < chrom {
+                    0
+                } else {
+                    leaf.start_base
+                };
+                let end = if leaf.end_chrom_ix > chrom {
+                    u32::MAX
+                } else {
+                    leaf.end_base
+                };
+                let block = Block {
+                    offset: leaf.data_offset,
+                    size: leaf.data_size,
+                };
+                (block, start, end)
+            });
+            let mut next_leaf = leaves.next();
+            // The decoded blocks that a later region may still need, with the
+            // end of the range they cover.
+            let mut window: VecDeque<(u32, Vec<Value>)> = VecDeque::new();
+            for &index in chrom_order {
+                let (_, start, end) = regions[index];
+                window.retain(|(block_end, _)| *block_end > start);
+
+                let mut blocks = vec![];
+                let mut block_ends = vec![];
+                while let Some((block, block_start, block_end)) = next_leaf {
+                    if block_start >= end {
+                        break;
+                    }
+                    if block_end > start {
+                        blocks.push(block);
+                        block_ends.push(block_end);
+                    }
+                    next_leaf = leaves.next();
+                }
+                let mut block_ends = block_ends.into_iter();
+                let mut blocks = blocks.into_iter();
+                loop {
+                    let coalesced = next_coalesced_blocks(&mut blocks, &self.options);
+                    if coalesced.is_empty() {
+                        break;
+                    }
+                    let data = self.read.get_blocks_data(&self.info, &coalesced)?;
+                    for (block, data) in coalesced.iter().zip(data) {
+                        let values = self.block_values(block, data, chrom, 0, u32::MAX)?;
+                        let block_end = block_ends.next().unwrap();
+                        window.push_back((block_end, values.into_iter().flatten().collect()));
+                    }
+                }
+
+                results[index] = window
+                    .iter()
+                    .flat_map(|(_, values)| values)
+                    .filter(|v| v.end > start && v.start < end)
+                    .map(|v| Value {
+                        start: v.start.max(start),
+                        end: v.end.min(end),
+                        value: v.value,
+                    })
+                    .collect();
+            }
+        }
+        Ok(results)
+    }
+
+    /// Calls `f` with the chromosome and each intersecting `Value` of each of
+    /// `regions` (chromosome, start, and end), in order. Returns the first
+    /// error encountered, after which `f` isn't called again. See
+    /// `get_interval` for how each region is interpreted.
+    pub fn for_each_interval<S: AsRef<str>>(
+        &mut self,
+        regions: &[(S, u32, u32)],
+        mut f: impl FnMut(&str, &Value),
+    ) -> Result<(), BBIReadError> {
+        for (chrom, start, end) in regions {
+            let chrom = chrom.as_ref();
+            for value in self.get_interval(chrom, *start, *end)? {
+                f(chrom, &value?);
+            }
+        }
+        Ok(())
+    }
+
+    /// For a given chromosome, start, and end, returns an `Iterator` of the
+    /// intersecting `Value`s. The resulting iterator takes this `BigWigRead`
+    /// by value. See `get_interval` for how the query is interpreted.
+    pub fn get_interval_move(
+        mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<BigWigIntervalIter<R, BigWigRead<R>>, BBIReadError> {
+        let chrom_name = &*self.resolve_chrom(chrom_name);
+        let chrom = self.info.chrom_id(chrom_name)?;
+        let cir_tree = self.full_data_cir_tree()?;
+        let blocks = search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
+        Ok(BigWigIntervalIter {
+            r: std::marker::PhantomData,
+            bigwig: self,
+            known_offset: 0,
+            blocks: blocks.into_iter(),
+            block_data: vec![].into_iter(),
+            vals: None,
+            chrom,
+            start,
+            end,
+            reverse: false,
+        })
+    }
+
+    /// For a given chromosome, start, and end, returns an `Iterator` of the
+    /// intersecting `ZoomRecord`s of the finest zoom level with at most
+    /// `max_bins` records for the region. If every zoom level has more, the
+    /// coarsest is used. See [`BBIFileInfo::zoom_level_for_bins`].
+    pub fn get_zoom_interval_auto<'a>(
+        &'a mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        max_bins: u32,
+    ) -> Result<ZoomIntervalIter<BigWigRead<R>, &'a mut BigWigRead<R>>, ZoomIntervalError> {
+        let reduction_level = self
+            .info
+            .zoom_level_for_bins(end.saturating_sub(start), max_bins)
+            .ok_or(ZoomIntervalError::ReductionLevelNotFound)?;
+        self.get_zoom_interval(chrom_name, start, end, reduction_level)
+    }
+
+    /// For a given chromosome, start, and end, returns the data needed to
+    /// render the region at a width of `pixels`, as genome browsers do. If a
+    /// zoom level's records are at most one pixel wide (i.e. its reduction
+    /// level is at most `(end - start) / pixels` bases), the records of the
+    /// coarsest such zoom level are returned, as `Either::Left`. Otherwise,
+    /// every zoom record would span multiple pixels, so the full-resolution
+    /// `Value`s are returned instead, as `Either::Right`.
+    pub fn get_interval_for_pixels<'a>(
+        &'a mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        pixels: u32,
+    ) -> Result<PixelIntervalIter<'a, R>, BBIReadError> {
+        let bases_per_pixel = end.saturating_sub(start) / pixels.max(1);
+        let inner = match self.closest_zoom_level(bases_per_pixel) {
+            Some(reduction_level) => PixelIntervals::Zoom(
+                self.get_zoom_interval(chrom_name, start, end, reduction_level)
+                    .map_err(known_zoom_level_error)?,
+            ),
+            None => PixelIntervals::Full(self.get_interval(chrom_name, start, end)?),
+        };
+        Ok(PixelIntervalIter { inner })
+    }
+
+    /// For a given chromosome, start, and end, returns an `Iterator` of the
+    /// intersecting `ZoomRecord`s.
+    ///
+    /// Zoom records only exist where there is data, so if no records of the
+    /// zoom level intersect the region, the iterator is empty. An error is
+    /// only returned if the chromosome isn't in the file or `reduction_level`
+    /// isn't a zoom level.
+    pub fn get_zoom_interval<'a>(
+        &'a mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        reduction_level: u32,
+    ) -> Result<ZoomIntervalIter<BigWigRead<R>, &'a mut BigWigRead<R>>, ZoomIntervalError> {
+        let chrom_name = &*self.resolve_chrom(chrom_name);
+        let cir_tree = self.zoom_cir_tree(reduction_level)?;
+
+        let chrom = self.info.chrom_id(chrom_name)?;
+
+        let blocks = search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
+
         Ok(ZoomIntervalIter::new(
             self,
             blocks.into_iter(),
@@ -385,35 +1733,550 @@ where
         ))
     }
 
+    /// For a given chromosome, start, and end, returns an `Iterator` of the
+    /// intersecting `ZoomRecord`s.
+    pub fn get_zoom_interval_move<'a>(
+        mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        reduction_level: u32,
+    ) -> Result<ZoomIntervalIter<BigWigRead<R>, BigWigRead<R>>, ZoomIntervalError> {
+        let chrom_name = &*self.resolve_chrom(chrom_name);
+        let cir_tree = self.zoom_cir_tree(reduction_level)?;
+
+        let chrom = self.info.chrom_id(chrom_name)?;
+
+        let blocks = search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
+
+        Ok(ZoomIntervalIter::new(
+            self,
+            blocks.into_iter(),
+            chrom,
+            start,
+            end,
+        ))
+    }
+
+    /// Computes a single statistic over the values between `start` and
+    /// `end`.
+    ///
+    /// The coarsest zoom level whose `reduction_level` is at most half the
+    /// region size is used. Zoom records that straddle `start` or `end` are
+    /// weighted by their overlap with the region (`Min` and `Max` still use
+    /// the whole record). If no zoom level is fine enough, full-resolution
+    /// data is read instead. See [`StatKind`] for the meaning of each
+    /// statistic.
+    pub fn stats(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        kind: StatKind,
+    ) -> Result<f64, BBIReadError> {
+        let summaries = self.region_summaries(chrom_name, start, end, 1)?;
+        Ok(summaries[0].2.stat(kind, end - start))
+    }
+
+    /// Divides the region between `start` and `end` into `nbins` bins of
+    /// equal width and computes a statistic for each, similar to UCSC's
+    /// `bigWigSummary`. If the region size isn't evenly divisible by `nbins`,
+    /// the first bins are one base wider than the rest.
+    ///
+    /// Zoom levels are chosen as in [`BigWigRead::stats`], but based on the
+    /// bin width rather than the whole region. Bins with no covered bases are
+    /// `NAN`, for every `StatKind`.
+    pub fn get_summary_bins(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        nbins: u32,
+        kind: StatKind,
+    ) -> Result<Vec<f64>, BBIReadError> {
+        if nbins == 0 {
+            return Ok(vec![]);
+        }
+        let summaries = self.region_summaries(chrom_name, start, end, nbins)?;
+        Ok(summaries
+            .into_iter()
+            .map(|(bin_start, bin_end, summary)| {
+                if summary.bases_covered == 0.0 {
+                    f64::NAN
+                } else {
+                    summary.stat(kind, bin_end - bin_start)
+                }
+            })
+            .collect())
+    }
+
+    /// Returns the mean value of each `bin_size` bases between `start` and
+    /// `end`, where the last bin is truncated at `end` if the region size isn't
+    /// a multiple of `bin_size`. Bins with no covered bases are `NAN`. If
+    /// `bin_size` is `0`, no values are returned.
+    ///
+    /// The coarsest zoom level whose `reduction_level` evenly divides
+    /// `bin_size` is used. If there is no such zoom level, full-resolution
+    /// data is read instead.
+    pub fn values_binned(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        bin_size: u32,
+    ) -> Result<Vec<f32>, BBIReadError> {
+        let reduction_level = self
+            .zoom_levels()
+            .into_iter()
+            .rev()
+            .find(|level| bin_size.is_multiple_of(*level));
+        self.binned_means(chrom_name, start, end, bin_size, reduction_level)
+    }
+
+    /// Like [`BigWigRead::values_binned`], but always reads from the zoom level
+    /// with the given `reduction_level`, rather than choosing one based on
+    /// `bin_size`. This is useful to sample multiple files at identical
+    /// resolutions. Zoom records that straddle a bin boundary are weighted by
+    /// their overlap with each bin. If there is no zoom level with
+    /// `reduction_level`, `ZoomIntervalError::ReductionLevelNotFound` is
+    /// returned.
+    pub fn values_binned_at_level(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        bin_size: u32,
+        reduction_level: u32,
+    ) -> Result<Vec<f32>, ZoomIntervalError> {
+        if !self.zoom_levels().contains(&reduction_level) {
+            return Err(ZoomIntervalError::ReductionLevelNotFound);
+        }
+        self.binned_means(chrom_name, start, end, bin_size, Some(reduction_level))
+            .map_err(ZoomIntervalError::BBIReadError)
+    }
+
+    /// Computes the mean value of each `bin_size` bases between `start` and
+    /// `end`, from the zoom level with `reduction_level` if given, otherwise
+    /// at full resolution.
+    fn binned_means(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        bin_size: u32,
+        reduction_level: Option<u32>,
+    ) -> Result<Vec<f32>, BBIReadError> {
+        if start > end {
+            return Err(BBIReadError::InvalidInterval { start, end });
+        }
+        if bin_size == 0 {
+            return Ok(vec![]);
+        }
+        let bins = (start..end)
+            .step_by(bin_size as usize)
+            .map(|bin_start| {
+                let bin_end = bin_start.saturating_add(bin_size).min(end);
+                (bin_start, bin_end, RegionSummary::new())
+            })
+            .collect();
+        let summaries = self.summarize_bins(chrom_name, start, end, bins, reduction_level)?;
+        Ok(summaries
+            .into_iter()
+            .map(|(bin_start, bin_end, summary)| {
+                summary.stat(StatKind::Mean, bin_end - bin_start) as f32
+            })
+            .collect())
+    }
+
+    /// Splits the region into `nbins` bins (the first `(end - start) % nbins`
+    /// being one base wider) and accumulates a `RegionSummary` for each.
+    fn region_summaries(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        nbins: u32,
+    ) -> Result<Vec<(u32, u32, RegionSummary)>, BBIReadError> {
+        if start > end {
+            return Err(BBIReadError::InvalidInterval { start, end });
+        }
+        let size = end - start;
+        let base_width = size / nbins;
+        let remainder = size % nbins;
+        let mut bins = Vec::with_capacity(nbins as usize);
+        let mut bin_start = start;
+        for i in 0..nbins {
+            let bin_end = bin_start + base_width + u32::from(i < remainder);
+            bins.push((bin_start, bin_end, RegionSummary::new()));
+            bin_start = bin_end;
+        }
+        let reduction_level = self.closest_zoom_level(base_width / 2);
+        self.summarize_bins(chrom_name, start, end, bins, reduction_level)
+    }
+
+    /// Accumulates the values between `start` and `end` into `bins`, which
+    /// must be sorted and non-overlapping. Values are read from the zoom level
+    /// with `reduction_level` if given, otherwise at full resolution.
+    fn summarize_bins(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        mut bins: Vec<(u32, u32, RegionSummary)>,
+        reduction_level: Option<u32>,
+    ) -> Result<Vec<(u32, u32, RegionSummary)>, BBIReadError> {
+        // The index of the first bin that ends after `pos`
+        let bin_index = |bins: &[(u32, u32, RegionSummary)], pos: u32| -> usize {
+            bins.partition_point(|bin| bin.1 <= pos)
+        };
+
+        match reduction_level {
+            Some(reduction_level) => {
+                let records = self
+                    .get_zoom_interval(chrom_name, start, end, reduction_level)
+                    .map_err(known_zoom_level_error)?;
+                for record in records {
+                    let record = record?;
+                    let first_bin = bin_index(&bins, record.start);
+                    for bin in bins[first_bin..]
+                        .iter_mut()
+                        .take_while(|bin| bin.0 < record.end)
+                    {
+                        bin.2.add_zoom_record(&record, bin.0, bin.1);
+                    }
+                }
+            }
+            None => {
+                for value in self.get_interval(chrom_name, start, end)? {
+                    let value = value?;
+                    let first_bin = bin_index(&bins, value.start);
+                    for bin in bins[first_bin..]
+                        .iter_mut()
+                        .take_while(|bin| bin.0 < value.end)
+                    {
+                        bin.2.add_value(&value, bin.0, bin.1);
+                    }
+                }
+            }
+        }
+        Ok(bins)
+    }
+
+    /// Returns the summary data for each chromosome, keyed by chromosome name.
+    ///
+    /// If there are zoom levels, the coarsest is used. Zoom records don't
+    /// store item counts, so in that case `total_items` is the sum of the item
+    /// counts in the headers of the chromosome's data blocks (which are read,
+    /// but not decoded). Otherwise, full-resolution data is read. Chromosomes
+    /// with no data have a `min_val` and `max_val` of `0`.
+    pub fn get_summary_by_chrom(&mut self) -> Result<HashMap<String, Summary>, BBIReadError> {
+        let reduction_level = self.zoom_levels().last().copied();
+        let chroms = self.info.chrom_info.clone();
+        let mut summaries = HashMap::with_capacity(chroms.len());
+        for chrom in chroms {
+            let mut summary = Summary {
+                total_items: 0,
+                bases_covered: 0,
+                min_val: f64::MAX,
+                max_val: f64::MIN,
+                sum: 0.0,
+                sum_squares: 0.0,
+            };
+            match reduction_level {
+                Some(reduction_level) => {
+                    let records = self
+                        .get_zoom_interval(&chrom.name, 0, chrom.length, reduction_level)
+                        .map_err(known_zoom_level_error)?;
+                    for record in records {
+                        let record = record?.summary;
+                        summary.bases_covered += record.bases_covered;
+                        summary.min_val = summary.min_val.min(record.min_val);
+                        summary.max_val = summary.max_val.max(record.max_val);
+                        summary.sum += record.sum;
+                        summary.sum_squares += record.sum_squares;
+                    }
+                    summary.total_items = self.count_items(&chrom.name, chrom.length)?;
+                }
+                None => {
+                    for value in self.get_interval(&chrom.name, 0, chrom.length)? {
+                        let value = value?;
+                        let size = u64::from(value.end - value.start);
+                        let val = f64::from(value.value);
+                        summary.total_items += 1;
+                        summary.bases_covered += size;
+                        summary.min_val = summary.min_val.min(val);
+                        summary.max_val = summary.max_val.max(val);
+                        summary.sum += size as f64 * val;
+                        summary.sum_squares += size as f64 * val * val;
+                    }
+                }
+            }
+            if summary.bases_covered == 0 {
+                summary.min_val = 0.0;
+                summary.max_val = 0.0;
+            }
+            summaries.insert(chrom.name, summary);
+        }
+        Ok(summaries)
+    }
+
+    /// Counts the items of a chromosome from the headers of its data blocks,
+    /// without decoding their values.
+    fn count_items(&mut self, chrom_name: &str, length: u32) -> Result<u64, BBIReadError> {
+        let mut blocks = self
+            .get_overlapping_blocks(chrom_name, 0, length)?
+            .into_iter();
+        let mut total_items = 0;
+        loop {
+            let blocks = next_coalesced_blocks(&mut blocks, &self.options);
+            if blocks.is_empty() {
+                return Ok(total_items);
+            }
+            for data in self.read.get_blocks_data(&self.info, &blocks)? {
+                let item_count = block_item_count(&self.info, &data).ok_or_else(|| {
+                    BBIReadError::InvalidFile("Block is too short to contain a header.".to_owned())
+                })?;
+                total_items += u64::from(item_count);
+            }
+        }
+    }
+
     /// Returns the values between `start` and `end` as a `Vec<f32>`. Any
-    /// positions with no data in the bigWig will be `std::f32::NAN`.
+    /// positions with no data in the bigWig will be `std::f32::NAN`. This
+    /// includes any positions past the end of the chromosome; `end` is not
+    /// clamped, so the result always has `end - start` values.
+    /// If the chromosome isn't in the bigWig, `BBIReadError::InvalidChromosome`
+    /// is returned, rather than all `NaN`s.
     pub fn values(
         &mut self,
         chrom_name: &str,
         start: u32,
         end: u32,
     ) -> Result<Vec<f32>, BBIReadError> {
+        self.values_with_fill(chrom_name, start, end, std::f32::NAN)
+    }
+
+    /// Compares the values of this bigWig and `other` between `start` and
+    /// `end`, returning the position and both values (this bigWig's first) of
+    /// each base where they differ by more than `epsilon`. Positions without
+    /// data are `NaN`: a `NaN` in both is equal, and a `NaN` in only one is a
+    /// difference.
+    pub fn diff<S: BBIFileRead>(
+        &mut self,
+        other: &mut BigWigRead<S>,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        epsilon: f32,
+    ) -> Result<Vec<(u32, f32, f32)>, BBIReadError> {
+        let values = self.values(chrom_name, start, end)?;
+        let other_values = other.values(chrom_name, start, end)?;
+        let diffs = (start..end)
+            .zip(values.into_iter().zip(other_values))
+            .filter(|(_, (a, b))| match (a.is_nan(), b.is_nan()) {
+                (true, true) => false,
+                (false, false) => (a - b).abs() > epsilon,
+                _ => true,
+            })
+            .map(|(pos, (a, b))| (pos, a, b))
+            .collect();
+        Ok(diffs)
+    }
+
+    /// Returns the values between `start` and `end` as a `Vec<f32>`. Any
+    /// positions with no data in the bigWig will be `fill`.
+    pub fn values_with_fill(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        fill: f32,
+    ) -> Result<Vec<f32>, BBIReadError> {
+        self.fill_values(chrom_name, start, end, fill, |v| v)
+    }
+
+    /// Returns the values between `start` and `end` as a `Vec<f64>`. Any
+    /// positions with no data in the bigWig will be `f64::NAN`.
+    ///
+    /// Note: bigWigs store values as `f32`, so this doesn't increase the
+    /// precision of any individual value. It is only a convenience for
+    /// downstream accumulation (e.g. summing over many positions).
+    pub fn values_f64(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<f64>, BBIReadError> {
+        self.fill_values(chrom_name, start, end, f64::NAN, f64::from)
+    }
+
+    /// The fraction of the bases between `start` and `end` that have data.
+    /// Overlapping values are only counted once. Returns `NAN` for an empty
+    /// region.
+    pub fn coverage_fraction(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<f64, BBIReadError> {
+        let mut covered = 0u64;
+        // The end of the covered bases seen so far
+        let mut covered_end = start;
+        for value in self.get_interval(chrom_name, start, end)? {
+            let value = value?;
+            let value_start = value.start.max(covered_end);
+            if value.end > value_start {
+                covered += u64::from(value.end - value_start);
+                covered_end = value.end;
+            }
+        }
+        if end <= start {
+            return Ok(f64::NAN);
+        }
+        Ok(covered as f64 / f64::from(end - start))
+    }
+
+    /// Returns the values between `start` and `end`, smoothed with a centered
+    /// rolling mean of width `window`. Positions with no data (`NaN`) are
+    /// excluded from both the sum and the count of each window, and windows
+    /// at the edges of the region shrink rather than being padded. A position
+    /// is `NaN` only if there is no data anywhere in its window. A `window` of
+    /// `0` or `1` is the same as `values`.
+    pub fn values_smoothed(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        window: u32,
+    ) -> Result<Vec<f32>, BBIReadError> {
+        let values = self.values(chrom_name, start, end)?;
+        if window <= 1 {
+            return Ok(values);
+        }
+        // Prefix sums and counts of the non-NaN values
+        let mut sums = Vec::with_capacity(values.len() + 1);
+        let mut counts = Vec::with_capacity(values.len() + 1);
+        sums.push(0.0f64);
+        counts.push(0u32);
+        for v in values.iter() {
+            let (sum, count) = (*sums.last().unwrap(), *counts.last().unwrap());
+            if v.is_nan() {
+                sums.push(sum);
+                counts.push(count);
+            } else {
+                sums.push(sum + f64::from(*v));
+                counts.push(count + 1);
+            }
+        }
+        let before = ((window - 1) / 2) as usize;
+        let after = (window / 2) as usize;
+        let smoothed = (0..values.len())
+            .map(|i| {
+                let lo = i.saturating_sub(before);
+                let hi = (i + after + 1).min(values.len());
+                let count = counts[hi] - counts[lo];
+                if count == 0 {
+                    f32::NAN
+                } else {
+                    ((sums[hi] - sums[lo]) / f64::from(count)) as f32
+                }
+            })
+            .collect();
+        Ok(smoothed)
+    }
+
+    /// Like `values_with_fill`, but fills the caller-provided `buf` rather
+    /// than allocating a new `Vec`. This allows reusing one buffer for many
+    /// regions of the same size. `buf` must have a length of exactly
+    /// `end - start`, or `BBIReadError::InvalidBufferLength` is returned.
+    pub fn values_into(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        buf: &mut [f32],
+        fill: f32,
+    ) -> Result<(), BBIReadError> {
+        self.fill_values_into(chrom_name, start, end, buf, fill, |v| v)
+    }
+
+    fn fill_values<T: Copy>(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        fill: T,
+        convert: impl Fn(f32) -> T,
+    ) -> Result<Vec<T>, BBIReadError> {
+        let chrom_name = &*self.resolve_chrom(chrom_name);
+        self.info.chrom_id(chrom_name)?;
+        let requested = end.saturating_sub(start) as usize;
+        if let Some(cap) = self.options.max_values.filter(|cap| requested > *cap) {
+            return Err(BBIReadError::TooManyValues { requested, cap });
+        }
+        let mut values = vec![fill; requested];
+        self.fill_values_into(chrom_name, start, end, &mut values, fill, convert)?;
+        Ok(values)
+    }
+
+    fn fill_values_into<T: Copy>(
+        &mut self,
+        chrom_name: &str,
+        start: u32,
+        end: u32,
+        values: &mut [T],
+        fill: T,
+        convert: impl Fn(f32) -> T,
+    ) -> Result<(), BBIReadError> {
+        let chrom_name = &*self.resolve_chrom(chrom_name);
         let chrom = self.info.chrom_id(chrom_name)?;
+        if start > end {
+            return Err(BBIReadError::InvalidInterval { start, end });
+        }
+        if values.len() != (end - start) as usize {
+            return Err(BBIReadError::InvalidBufferLength {
+                expected: (end - start) as usize,
+                actual: values.len(),
+            });
+        }
         let cir_tree = self.full_data_cir_tree()?;
         let blocks = search_cir_tree(&self.info, &mut self.read, cir_tree, chrom_name, start, end)?;
-        let mut values = vec![std::f32::NAN; (end - start) as usize];
-        use crate::utils::tell::Tell;
-        let mut known_offset = self.reader().raw_reader().tell()?;
-        for block in blocks {
-            let block_values = get_block_values(self, block, &mut known_offset, chrom, start, end)?;
-            let block_values = match block_values {
-                Some(v) => v,
-                None => continue,
-            };
-            for block_value in block_values {
-                let block_value_start = (block_value.start - start) as usize;
-                let block_value_end = (block_value.end - start) as usize;
-                for i in &mut values[block_value_start..block_value_end] {
-                    *i = block_value.value
+        values.fill(fill);
+        let mut blocks = blocks.into_iter();
+        loop {
+            let blocks = next_coalesced_blocks(&mut blocks, &self.options);
+            if blocks.is_empty() {
+                break;
+            }
+            let blocks_data = self.read.get_blocks_data(&self.info, &blocks)?;
+            for (block, data) in blocks.iter().zip(blocks_data) {
+                let block_values = self.block_values(block, data, chrom, start, end)?;
+                let block_values = match block_values {
+                    Some(v) => v,
+                    None => continue,
+                };
+                for block_value in block_values {
+                    let block_value_start = (block_value.start - start) as usize;
+                    let block_value_end = (block_value.end - start) as usize;
+                    let value = convert(block_value.value);
+                    for i in &mut values[block_value_start..block_value_end] {
+                        *i = value
+                    }
                 }
             }
         }
-        Ok(values)
+        Ok(())
+    }
+}
+
+/// Converts the error from `get_zoom_interval` when the reduction level is
+/// known to exist (because it was taken from the zoom headers).
+fn known_zoom_level_error(e: ZoomIntervalError) -> BBIReadError {
+    match e {
+        ZoomIntervalError::BBIReadError(e) => e,
+        ZoomIntervalError::ReductionLevelNotFound => {
+            unreachable!("Reduction level is from the zoom headers.")
+        }
     }
 }
 
@@ -424,15 +2287,142 @@ fn get_block_values<R: BBIFileRead>(
     chrom: u32,
     start: u32,
     end: u32,
-) -> Result<Option<std::vec::IntoIter<Value>>, BBIReadError> {
+) -> Result<Option<BlockValues>, BBIReadError> {
     let data = bigwig.read.get_block_data(&bigwig.info, &block)?;
-    let mut bytes = BytesMut::with_capacity(data.len());
-    bytes.extend_from_slice(&data);
+    let values = bigwig.block_values(&block, data, chrom, start, end)?;
+    *known_offset = block.offset + block.size;
+    Ok(values)
+}
+
+/// Reads the chromosome id from the header of the (decompressed) data of a
+/// block.
+fn block_chrom_id(info: &BBIFileInfo, data: &[u8]) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(..4)?.try_into().ok()?;
+    Some(match info.header.endianness {
+        Endianness::Big => u32::from_be_bytes(bytes),
+        Endianness::Little => u32::from_le_bytes(bytes),
+    })
+}
+
+/// Reads the item count from the header of the (decompressed) data of a
+/// block.
+fn block_item_count(info: &BBIFileInfo, data: &[u8]) -> Option<u16> {
+    let bytes: [u8; 2] = data.get(22..24)?.try_into().ok()?;
+    Some(match info.header.endianness {
+        Endianness::Big => u16::from_be_bytes(bytes),
+        Endianness::Little => u16::from_le_bytes(bytes),
+    })
+}
+
+/// Checks the (decompressed) data of the block at `offset`, adding any
+/// anomalies to `report`. See [`BigWigRead::validate`].
+fn validate_block(
+    info: &BBIFileInfo,
+    chroms: &HashMap<u32, ChromInfo>,
+    offset: u64,
+    data: Vec<u8>,
+    report: &mut ValidationReport,
+) {
+    if data.len() < 24 {
+        report.issues.push(ValidationIssue::ShortBlock { offset });
+        return;
+    }
+    let read_u32 = |at: usize| {
+        let bytes: [u8; 4] = data[at..at + 4].try_into().unwrap();
+        match info.header.endianness {
+            Endianness::Big => u32::from_be_bytes(bytes),
+            Endianness::Little => u32::from_le_bytes(bytes),
+        }
+    };
+    let chrom_id = read_u32(0);
+    let chrom_start = read_u32(4);
+    let section_type = data[20];
+    let item_count = match info.header.endianness {
+        Endianness::Big => u16::from_be_bytes([data[22], data[23]]),
+        Endianness::Little => u16::from_le_bytes([data[22], data[23]]),
+    };
+
+    let Some(chrom) = chroms.get(&chrom_id) else {
+        report
+            .issues
+            .push(ValidationIssue::UnknownChromosome { offset, chrom_id });
+        return;
+    };
+    if chrom_start > chrom.length {
+        report.issues.push(ValidationIssue::BlockOutOfRange {
+            offset,
+            chrom: chrom.name.clone(),
+            start: chrom_start,
+        });
+    }
+    let item_size = match section_type {
+        1 => 12,
+        2 => 8,
+        3 => 4,
+        _ => {
+            report.issues.push(ValidationIssue::InvalidSection {
+                offset,
+                section_type,
+            });
+            return;
+        }
+    };
+    let data_len = data.len() - 24;
+    if data_len != item_count as usize * item_size {
+        report.issues.push(ValidationIssue::ItemCountMismatch {
+            offset,
+            declared: item_count,
+            actual: data_len / item_size,
+        });
+        if data_len < item_count as usize * item_size {
+            return;
+        }
+    }
 
-    let mut bytes_header = bytes.split_to(24);
+    let values = match block_values_from_data(info, data, chrom_id, 0, u32::MAX) {
+        Ok(Some(values)) => values,
+        Ok(None) => return,
+        Err(e) => {
+            report.issues.push(ValidationIssue::UndecodableBlock {
+                offset,
+                error: e.to_string(),
+            });
+            return;
+        }
+    };
+    for index in values.front..values.back {
+        let value = values.decode(index);
+        report.values += 1;
+        if value.end < value.start || value.end > chrom.length {
+            report.issues.push(ValidationIssue::ValueOutOfRange {
+                offset,
+                chrom: chrom.name.clone(),
+                start: value.start,
+                end: value.end,
+            });
+        }
+    }
+}
+
+/// Decodes the `Value`s from the (decompressed) data of a block, keeping only
+/// those that overlap `start` and `end` on `chrom`. Returns `None` if the
+/// block is for a different chromosome.
+pub(crate) fn block_values_from_data(
+    info: &BBIFileInfo,
+    data: Vec<u8>,
+    chrom: u32,
+    start: u32,
+    end: u32,
+) -> Result<Option<BlockValues>, BBIReadError> {
+    if data.len() < 24 {
+        return Err(BBIReadError::InvalidFile(
+            "Block is too short to contain a header.".to_owned(),
+        ));
+    }
+    let mut bytes_header = &data[..24];
 
     let (chrom_id, chrom_start, item_step, item_span, section_type, item_count) =
-        match bigwig.info.header.endianness {
+        match info.header.endianness {
             Endianness::Big => {
                 let chrom_id = bytes_header.get_u32();
                 let chrom_start = bytes_header.get_u32();
@@ -471,140 +2461,206 @@ fn get_block_values<R: BBIFileRead>(
             }
         };
 
-    let mut values: Vec<Value> = Vec::with_capacity(item_count as usize);
-
     if chrom_id != chrom {
         return Ok(None);
     }
 
-    match section_type {
-        1 => {
-            assert!(bytes.len() >= (item_count as usize) * 12);
-            for i in 0..(item_count as usize) {
-                let istart = i * 12;
-                let block_item_data: &[u8; 12] = bytes[istart..istart + 12].try_into().unwrap();
-                // bedgraph
-                let (chrom_start, chrom_end, value) = match bigwig.info.header.endianness {
-                    Endianness::Big => {
-                        let chrom_start = u32::from_be_bytes([
-                            block_item_data[0],
-                            block_item_data[1],
-                            block_item_data[2],
-                            block_item_data[3],
-                        ]);
-                        let chrom_end = u32::from_be_bytes([
-                            block_item_data[4],
-                            block_item_data[5],
-                            block_item_data[6],
-                            block_item_data[7],
-                        ]);
-                        let value = f32::from_be_bytes([
-                            block_item_data[8],
-                            block_item_data[9],
-                            block_item_data[10],
-                            block_item_data[11],
-                        ]);
-                        (chrom_start, chrom_end, value)
-                    }
-                    Endianness::Little => {
-                        let chrom_start = u32::from_le_bytes([
-                            block_item_data[0],
-                            block_item_data[1],
-                            block_item_data[2],
-                            block_item_data[3],
-                        ]);
-                        let chrom_end = u32::from_le_bytes([
-                            block_item_data[4],
-                            block_item_data[5],
-                            block_item_data[6],
-                            block_item_data[7],
-                        ]);
-                        let value = f32::from_le_bytes([
-                            block_item_data[8],
-                            block_item_data[9],
-                            block_item_data[10],
-                            block_item_data[11],
-                        ]);
-                        (chrom_start, chrom_end, value)
+    let item_size = match section_type {
+        // bedgraph
+        1 => 12,
+        // variable step
+        2 => 8,
+        // fixed step
+        3 => 4,
+        _ => return Err(BBIReadError::InvalidSection(section_type)),
+    };
+    if data.len() - 24 < item_count as usize * item_size {
+        return Err(BBIReadError::InvalidFile(format!(
+            "Block is too short to contain {} items.",
+            item_count
+        )));
+    }
+
+    // Positions are u32, so check up front that computing the start and end
+    // of every item can't overflow (decoding then doesn't need to check).
+    let max_end = match section_type {
+        2 => {
+            let max_start = data[24..24 + item_count as usize * 8]
+                .chunks_exact(8)
+                .map(|item| {
+                    let bytes: [u8; 4] = item[..4].try_into().unwrap();
+                    match info.header.endianness {
+                        Endianness::Big => u32::from_be_bytes(bytes),
+                        Endianness::Little => u32::from_le_bytes(bytes),
                     }
-                };
-                let mut value = Value {
-                    start: chrom_start,
-                    end: chrom_end,
-                    value,
-                };
-                if value.end > start && value.start < end {
-                    value.start = value.start.max(start);
-                    value.end = value.end.min(end);
-                    values.push(value)
+                })
+                .max()
+                .unwrap_or(0);
+            max_start.checked_add(item_span)
+        }
+        3 => u32::from(item_count.saturating_sub(1))
+            .checked_mul(item_step)
+            .and_then(|offset| chrom_start.checked_add(offset))
+            .and_then(|start| start.checked_add(item_span)),
+        _ => Some(0),
+    };
+    if max_end.is_none() {
+        return Err(BBIReadError::InvalidFile(format!(
+            "Block items (starting at {}, with step {} and span {}) end past the maximum position ({}).",
+            chrom_start,
+            item_step,
+            item_span,
+            u32::MAX
+        )));
+    }
+
+    Ok(Some(BlockValues {
+        data,
+        endianness: info.header.endianness,
+        section_type,
+        chrom_start,
+        item_step,
+        item_span,
+        front: 0,
+        back: item_count as usize,
+        start,
+        end,
+    }))
+}
+
+/// Handles the result of `block_values_from_data` for `block`: if the block
+/// has an unknown section type and `options.skip_unknown_sections` is set,
+/// the block is added to `skipped_blocks` and skipped, rather than returning
+/// an error.
+pub(crate) fn skip_unknown_section(
+    values: Result<Option<BlockValues>, BBIReadError>,
+    options: &BBIReadOptions,
+    block: &Block,
+    skipped_blocks: &mut Vec<Block>,
+) -> Result<Option<BlockValues>, BBIReadError> {
+    match values {
+        Err(BBIReadError::InvalidSection(_)) if options.skip_unknown_sections => {
+            skipped_blocks.push(*block);
+            Ok(None)
+        }
+        values => values,
+    }
+}
+
+/// An `Iterator` over the `Value`s of a single (decompressed) block. Values
+/// are decoded one at a time, and only those that overlap `start` and `end`
+/// are returned (clipped to `start` and `end`). Since every item in a block is
+/// the same size, this can also be iterated from the back.
+pub(crate) struct BlockValues {
+    data: Vec<u8>,
+    endianness: Endianness,
+    section_type: u8,
+    chrom_start: u32,
+    item_step: u32,
+    item_span: u32,
+    // The indices of the remaining items are `front..back`
+    front: usize,
+    back: usize,
+    start: u32,
+    end: u32,
+}
+
+impl BlockValues {
+    /// Decodes the item at `index`, without checking overlap
+    fn decode(&self, index: usize) -> Value {
+        let read_u32 = |offset: usize| {
+            let bytes: [u8; 4] = self.data[offset..offset + 4].try_into().unwrap();
+            match self.endianness {
+                Endianness::Big => u32::from_be_bytes(bytes),
+                Endianness::Little => u32::from_le_bytes(bytes),
+            }
+        };
+        let read_f32 = |offset: usize| f32::from_bits(read_u32(offset));
+        match self.section_type {
+            1 => {
+                let offset = 24 + index * 12;
+                Value {
+                    start: read_u32(offset),
+                    end: read_u32(offset + 4),
+                    value: read_f32(offset + 8),
                 }
             }
-        }
-        2 => {
-            for _ in 0..item_count {
-                // variable step
-                let (chrom_start, value) = match bigwig.info.header.endianness {
-                    Endianness::Big => {
-                        let chrom_start = bytes.get_u32();
-                        let value = bytes.get_f32();
-                        (chrom_start, value)
-                    }
-                    Endianness::Little => {
-                        let chrom_start = bytes.get_u32_le();
-                        let value = bytes.get_f32_le();
-                        (chrom_start, value)
-                    }
-                };
-                let chrom_end = chrom_start + item_span;
-                let mut value = Value {
-                    start: chrom_start,
-                    end: chrom_end,
-                    value,
-                };
-                if value.end > start && value.start < end {
-                    value.start = value.start.max(start);
-                    value.end = value.end.min(end);
-                    values.push(value)
+            2 => {
+                let offset = 24 + index * 8;
+                let start = read_u32(offset);
+                // Can't overflow, see `block_values_from_data`
+                Value {
+                    start,
+                    end: start + self.item_span,
+                    value: read_f32(offset + 4),
                 }
             }
-        }
-        3 => {
-            let mut curr_start = chrom_start;
-            for _ in 0..item_count {
-                // fixed step
-                let value = match bigwig.info.header.endianness {
-                    Endianness::Big => {
-                        let value = bytes.get_f32();
-                        value
-                    }
-                    Endianness::Little => {
-                        let value = bytes.get_f32_le();
-                        value
-                    }
-                };
-                let chrom_start = curr_start;
-                curr_start += item_step;
-                let chrom_end = chrom_start + item_span;
-                let mut value = Value {
-                    start: chrom_start,
-                    end: chrom_end,
-                    value,
-                };
-                if value.end > start && value.start < end {
-                    value.start = value.start.max(start);
-                    value.end = value.end.min(end);
-                    values.push(value)
+            _ => {
+                let offset = 24 + index * 4;
+                // Can't overflow, see `block_values_from_data`
+                let start = self.chrom_start + index as u32 * self.item_step;
+                Value {
+                    start,
+                    end: start + self.item_span,
+                    value: read_f32(offset),
                 }
             }
         }
-        _ => {
-            return Err(BBIReadError::InvalidFile(format!(
-                "Unknown bigwig section type: {}",
-                section_type
-            )))
+    }
+
+    /// Clips `value` to `start` and `end`, or returns `None` if it doesn't
+    /// overlap.
+    fn clip(&self, mut value: Value) -> Option<Value> {
+        // Inverted values can only come from a malformed file, so skip them
+        if value.end < value.start {
+            return None;
+        }
+        let overlaps = if value.start == value.end {
+            // A zero-width value overlaps if its position is in the query
+            self.start <= value.start && value.start < self.end
+        } else {
+            value.end > self.start && value.start < self.end && self.start < self.end
+        };
+        if overlaps {
+            value.start = value.start.max(self.start);
+            value.end = value.end.min(self.end);
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for BlockValues {
+    fn drop(&mut self) {
+        recycle_block_data(std::mem::take(&mut self.data));
+    }
+}
+
+impl Iterator for BlockValues {
+    type Item = Value;
+
+    fn next(&mut self) -> Option<Value> {
+        while self.front < self.back {
+            let value = self.decode(self.front);
+            self.front += 1;
+            if let Some(value) = self.clip(value) {
+                return Some(value);
+            }
         }
+        None
     }
+}
 
-    *known_offset = block.offset + block.size;
-    Ok(Some(values.into_iter()))
+impl DoubleEndedIterator for BlockValues {
+    fn next_back(&mut self) -> Option<Value> {
+        while self.front < self.back {
+            self.back -= 1;
+            let value = self.decode(self.back);
+            if let Some(value) = self.clip(value) {
+                return Some(value);
+            }
+        }
+        None
+    }
 }
@@ -42,27 +42,43 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufWriter, Seek, Write};
 use std::path::Path;
+use std::sync::Arc;
 use std::vec;
+#[cfg(feature = "read")]
+use std::{fs::OpenOptions, io::SeekFrom};
 
 use futures::sink::SinkExt;
 
 use byteorder::{NativeEndian, WriteBytesExt};
+use byteordered::{Endian, Endianness};
 use tokio::runtime::{Handle, Runtime};
 
-use crate::bbiwrite::process_internal::BBIDataProcessorCreate;
+use crate::bbiwrite::process_internal::{
+    BBIDataProcessorCreate, Interval, PendingValues, ProcessResolved, ResolveOverlaps,
+};
 use crate::utils::tell::Tell;
 use crate::{
-    write_info, BBIDataProcessor, BBIDataProcessoredData, BBIDataProcessoringInputSectionChannel,
-    BBIDataSource, InternalProcessData, InternalTempZoomInfo, NoZoomsInternalProcessData,
+    write_info, BBIDataProcessoredData, BBIDataProcessoringInputSectionChannel, BBIDataSource,
+    InternalProcessData, InternalTempZoomInfo, NoZoomsInternalProcessData,
     NoZoomsInternalProcessedData, ProcessDataError, ZoomsInternalProcessData,
     ZoomsInternalProcessedData,
 };
 
+#[cfg(feature = "read")]
+use crate::bbi::ZoomHeader;
 use crate::bbi::{Summary, Value, ZoomRecord, BIGWIG_MAGIC};
+#[cfg(feature = "read")]
+use crate::bbiread::read_cir_tree_leaves;
 use crate::bbiwrite::{
-    self, encode_zoom_section, write_blank_headers, write_zooms, BBIProcessError, BBIWriteOptions,
-    SectionData,
+    self, encode_zoom_section, write_blank_headers, write_zooms, zoom_record_end, BBIProcessError,
+    BBIWriteOptions, OnOverlap, SectionData, WriteSizeEstimate,
 };
+#[cfg(feature = "read")]
+use crate::bbiwrite::{get_rtreeindex, write_chrom_tree, write_rtreeindex, Section};
+use crate::bed::bedparser::BedValueError;
+use crate::beddata::BedParserStreamingIterator;
+#[cfg(feature = "read")]
+use crate::{BBIFileRead, BBIReadError, BigWigRead, BigWigReadOpenError};
 
 struct ZoomItem {
     // How many bases this zoom item covers
@@ -89,6 +105,218 @@ impl BigWigWrite<File> {
         let out = File::create(path)?;
         Ok(BigWigWrite::new(out, chrom_sizes))
     }
+
+    /// Writes in-memory `(chrom, start, end, value)` data as a bigWig at
+    /// `path`. The data is sorted by chromosome and start first, so it may be
+    /// given in any order, but is otherwise validated like with `write`: for
+    /// example, overlapping values or unknown chromosomes are errors. Values
+    /// are processed on a single-threaded runtime.
+    pub fn write_vec(
+        path: impl AsRef<Path>,
+        chrom_sizes: HashMap<String, u32>,
+        mut data: Vec<(String, u32, u32, f32)>,
+        options: BBIWriteOptions,
+    ) -> Result<(), BBIProcessError<BedValueError>> {
+        data.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
+        let vals = data
+            .into_iter()
+            .map(|(chrom, start, end, value)| (chrom, Value { start, end, value }));
+        let vals = BedParserStreamingIterator::wrap_infallible_iter(vals, false);
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let mut out = BigWigWrite::create_file(path, chrom_sizes)?;
+        out.options = options;
+        out.write(vals, runtime)
+    }
+
+    /// Appends the `values` of a new chromosome `chrom`, with `length`, to the
+    /// existing bigWig at `path`. Rather than rewriting the file, the data and
+    /// zoom blocks of the new chromosome are appended to the end of the file,
+    /// followed by new chromosome and index trees (covering both the existing
+    /// and the new blocks), and the header and total summary are updated in
+    /// place. The existing blocks are neither read nor re-encoded, so this is
+    /// much cheaper than writing the whole file again. The old trees are left
+    /// in the file, unused.
+    ///
+    /// Because the chromosome ids of the existing blocks can't be changed,
+    /// `chrom` must sort after every chromosome already in the file; otherwise
+    /// `ProcessDataError::InvalidChromosome` is returned. The zoom records of
+    /// the new chromosome are computed for the existing zoom levels. `values`
+    /// must be sorted and non-overlapping. Of `options`, only `items_per_slot`,
+    /// `block_size`, and the compression level are used; the endianness and
+    /// whether blocks are compressed are those of the existing file.
+    #[cfg(feature = "read")]
+    pub fn extend_with_chrom(
+        path: impl AsRef<Path>,
+        chrom: &str,
+        length: u32,
+        values: Vec<Value>,
+        options: &BBIWriteOptions,
+    ) -> Result<(), ProcessDataError> {
+        let path = path.as_ref();
+        let mut bigwig = BigWigRead::open_file(path).map_err(|e| match e {
+            BigWigReadOpenError::FileError { source, .. } => match *source {
+                BigWigReadOpenError::IoError(e) => ProcessDataError::IoError(e),
+                e => ProcessDataError::InvalidInput(format!(
+                    "Can't extend {}: {}",
+                    path.display(),
+                    e
+                )),
+            },
+            e => ProcessDataError::InvalidInput(format!("Can't extend {}: {}", path.display(), e)),
+        })?;
+        let mut summary = bigwig.get_summary()?;
+        let info = bigwig.info().clone();
+        drop(bigwig);
+        let header = &info.header;
+        let endianness = header.endianness;
+        let Some(total_summary_offset) = header.summary_offset() else {
+            return Err(ProcessDataError::InvalidInput(format!(
+                "Can't extend {}: version 1 bigWigs have no total summary.",
+                path.display()
+            )));
+        };
+        if let Some(last) = info.chrom_info.iter().map(|c| &c.name).max() {
+            if chrom <= last.as_str() {
+                return Err(ProcessDataError::InvalidChromosome(format!(
+                    "Chromosome `{}` would need to be interleaved with the existing chromosomes: only chromosomes that sort after `{}` can be appended.",
+                    chrom, last
+                )));
+            }
+        }
+        if values.is_empty() {
+            return Err(ProcessDataError::InvalidInput(format!(
+                "No values to append for chromosome {}.",
+                chrom
+            )));
+        }
+        for value in values.iter() {
+            if value.start > value.end || value.end > length {
+                return Err(ProcessDataError::InvalidInput(format!(
+                    "Invalid value on chromosome {} (length {}): {}-{}",
+                    chrom, length, value.start, value.end
+                )));
+            }
+        }
+        for pair in values.windows(2) {
+            if pair[0].end > pair[1].start {
+                return Err(ProcessDataError::InvalidInput(format!(
+                    "Values on chromosome {} must be sorted and non-overlapping, but {}-{} is followed by {}-{}.",
+                    chrom, pair[0].start, pair[0].end, pair[1].start, pair[1].end
+                )));
+            }
+        }
+
+        let chrom_id = info.chrom_info.iter().map(|c| c.id + 1).max().unwrap_or(0);
+        let mut options = options.clone();
+        options.endianness = endianness;
+        let compression_level = match (
+            header.is_compressed(),
+            options.effective_compression_level(),
+        ) {
+            (false, _) => 0,
+            (true, 0) => bbiwrite::DEFAULT_COMPRESSION_LEVEL,
+            (true, level) => level,
+        };
+
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mut full_sections = index_sections(&mut file, endianness, header.full_index_offset)?;
+        let mut zooms = Vec::with_capacity(info.zoom_headers.len());
+        for zoom in info.zoom_headers.iter() {
+            let sections = index_sections(&mut file, endianness, zoom.index_offset)?;
+            zooms.push((zoom.reduction_level, zoom.data_offset, sections));
+        }
+
+        let mut file = BufWriter::new(file);
+        file.seek(SeekFrom::End(0))?;
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let mut uncompress_buf_size = header.uncompress_buf_size as usize;
+        for items in values.chunks(options.items_per_slot as usize) {
+            for item in items {
+                let len = item.end - item.start;
+                let val = f64::from(item.value);
+                summary.bases_covered += u64::from(len);
+                summary.min_val = summary.min_val.min(val);
+                summary.max_val = summary.max_val.max(val);
+                summary.sum += f64::from(len) * val;
+                summary.sum_squares += f64::from(len) * val * val;
+            }
+            let (section, buf_size) = runtime.block_on(encode_section(
+                compression_level,
+                endianness,
+                items.to_vec(),
+                chrom_id,
+                options.step_sections,
+            ))?;
+            uncompress_buf_size = uncompress_buf_size.max(buf_size);
+            full_sections.push(append_section(&mut file, section)?);
+        }
+        for (reduction_level, _, sections) in zooms.iter_mut() {
+            let records = zoom_records(&values, *reduction_level, chrom_id)?;
+            for items in records.chunks(options.items_per_slot as usize) {
+                let (section, buf_size) = runtime.block_on(encode_zoom_section(
+                    compression_level,
+                    endianness,
+                    items.to_vec(),
+                ))?;
+                uncompress_buf_size = uncompress_buf_size.max(buf_size);
+                sections.push(append_section(&mut file, section)?);
+            }
+        }
+
+        let mut chrom_sizes: HashMap<String, u32> = info
+            .chrom_info
+            .iter()
+            .map(|c| (c.name.clone(), c.length))
+            .collect();
+        let mut chrom_ids: HashMap<String, u32> = info
+            .chrom_info
+            .iter()
+            .map(|c| (c.name.clone(), c.id))
+            .collect();
+        chrom_sizes.insert(chrom.to_string(), length);
+        chrom_ids.insert(chrom.to_string(), chrom_id);
+        let chrom_index_start = file.tell()?;
+        write_chrom_tree(&mut file, chrom_sizes, &chrom_ids, endianness)?;
+
+        let index_start = file.tell()?;
+        let (nodes, levels, total_sections) = get_rtreeindex(full_sections.into_iter(), &options);
+        write_rtreeindex(&mut file, nodes, levels, total_sections, &options)?;
+
+        let mut zoom_entries = Vec::with_capacity(zooms.len());
+        for (reduction_level, data_offset, sections) in zooms {
+            let index_offset = file.tell()?;
+            let (nodes, levels, zoom_sections) = get_rtreeindex(sections.into_iter(), &options);
+            write_rtreeindex(&mut file, nodes, levels, zoom_sections, &options)?;
+            zoom_entries.push(ZoomHeader {
+                reduction_level,
+                data_offset,
+                index_offset,
+                index_tree_offset: None,
+            });
+        }
+
+        write_info(
+            &mut file,
+            BIGWIG_MAGIC,
+            zoom_entries.len() as u16,
+            chrom_index_start,
+            header.full_data_offset,
+            index_start,
+            0,
+            0,
+            0,
+            total_summary_offset,
+            uncompress_buf_size,
+            zoom_entries,
+            summary,
+            total_sections,
+            endianness,
+        )?;
+        file.flush()?;
+
+        Ok(())
+    }
 }
 
 impl<W: Write + Seek + Send + 'static> BigWigWrite<W> {
@@ -100,6 +328,14 @@ impl<W: Write + Seek + Send + 'static> BigWigWrite<W> {
         }
     }
 
+    /// Gives a rough estimate of the output size for `num_records`
+    /// non-overlapping values, given the chromosome sizes and current options.
+    /// This may be useful, for example, for showing progress.
+    pub fn estimate_size(&self, num_records: u64) -> WriteSizeEstimate {
+        // Each bedGraph item is a start, end, and value
+        bbiwrite::estimate_size(&self.options, &self.chrom_sizes, num_records, 12)
+    }
+
     fn write_pre(file: &mut BufWriter<W>) -> Result<(u64, u64, u64), ProcessDataError> {
         write_blank_headers(file)?;
 
@@ -120,7 +356,11 @@ impl<W: Write + Seek + Send + 'static> BigWigWrite<W> {
         Ok((total_summary_offset, full_data_offset, pre_data))
     }
 
-    /// Write the values from `V` as a bigWig. Will utilize the provided runtime for encoding values and for reading through the values (potentially parallelized by chromosome).
+    /// Write the values from `V` as a bigWig. Will utilize the provided runtime for encoding values and for reading through the values (potentially parallelized by chromosome). The number of worker threads of the runtime determines how much work is done in parallel.
+    ///
+    /// The output is deterministic: blocks and chromosomes are always written in
+    /// input order, so the same values and options give the same bytes
+    /// regardless of the runtime's threads.
     pub fn write<V: BBIDataSource<Value = Value>>(
         self,
         vals: V,
@@ -175,12 +415,103 @@ impl<W: Write + Seek + Send + 'static> BigWigWrite<W> {
             zoom_entries,
             summary,
             total_sections,
+            self.options.endianness,
         )?;
 
         Ok(())
     }
 
-    /// Write the values from `V` as a bigWig. Will utilize the provided runtime for encoding values and for reading through the values (potentially parallelized by chromosome).
+    /// Writes the values of an existing bigWig, with its existing zoom levels
+    /// plus the additional `zoom_sizes`. This is useful for files with too few
+    /// zoom levels for fast browsing. The values are read in a single pass
+    /// (see `BigWigRead::scan_all`), and all zooms are recomputed from them,
+    /// so the original bedGraph isn't needed. Note that this overrides
+    /// `options.manual_zoom_sizes`, and that a file can have at most 10 zoom
+    /// levels.
+    #[cfg(feature = "read")]
+    pub fn write_rezoomed<R: BBIFileRead>(
+        mut self,
+        bigwig: &mut BigWigRead<R>,
+        zoom_sizes: &[u32],
+        runtime: Runtime,
+    ) -> Result<(), BBIProcessError<BedValueError>> {
+        let mut all_zoom_sizes = bigwig.zoom_levels();
+        all_zoom_sizes.extend_from_slice(zoom_sizes);
+        all_zoom_sizes.sort();
+        all_zoom_sizes.dedup();
+        self.options.manual_zoom_sizes = Some(all_zoom_sizes);
+
+        let vals = bigwig
+            .scan_all()
+            .map_err(|e| BBIProcessError::SourceError(BedValueError::IoError(e.into())))?
+            .map(|v| v.map_err(|e| BedValueError::IoError(e.into())));
+        // Values are in file order, where chromosomes may not be sorted
+        let vals = BedParserStreamingIterator::wrap_iter(vals, true);
+        self.write(vals, runtime)
+    }
+
+    /// Writes the per-base combination of several bigWigs, such as the sum of
+    /// replicates or `log2(treatment / control)`. For each base, `combine` is
+    /// called with the value of each input (in order), where bases without
+    /// data are `NaN`. Bases where the result is `NaN` are left without data,
+    /// and adjacent bases with equal results are written as one value.
+    ///
+    /// All inputs must have the same chromosomes (with the same lengths), and
+    /// these must be in the chromosome sizes of this `BigWigWrite`.
+    #[cfg(feature = "read")]
+    pub fn write_combined<R: BBIFileRead, F: Fn(&[f32]) -> f32>(
+        self,
+        inputs: &mut [BigWigRead<R>],
+        combine: F,
+        runtime: Runtime,
+    ) -> Result<(), BBIProcessError<BedValueError>> {
+        let Some(first) = inputs.first() else {
+            return Err(BBIProcessError::InvalidInput(
+                "At least one bigWig is needed to combine.".to_owned(),
+            ));
+        };
+        let mut chroms: Vec<(String, u32)> = first
+            .chroms()
+            .iter()
+            .map(|c| (c.name.clone(), c.length))
+            .collect();
+        chroms.sort();
+        for input in inputs.iter().skip(1) {
+            let mut input_chroms: Vec<(String, u32)> = input
+                .chroms()
+                .iter()
+                .map(|c| (c.name.clone(), c.length))
+                .collect();
+            input_chroms.sort();
+            if input_chroms != chroms {
+                return Err(BBIProcessError::InvalidChromosome(
+                    "The bigWigs to combine have different chromosomes.".to_owned(),
+                ));
+            }
+        }
+        for (chrom, length) in chroms.iter() {
+            if self.chrom_sizes.get(chrom) != Some(length) {
+                return Err(BBIProcessError::InvalidChromosome(format!(
+                    "Chromosome {} of the bigWigs to combine doesn't match the chrom sizes.",
+                    chrom
+                )));
+            }
+        }
+
+        let vals = CombinedValues {
+            inputs,
+            combine,
+            chroms: chroms.into_iter(),
+            chrom: None,
+            window_start: 0,
+            pending: vec![].into_iter(),
+            run: None,
+        };
+        let vals = BedParserStreamingIterator::wrap_iter(vals, false);
+        self.write(vals, runtime)
+    }
+
+    /// Write the values from `V` as a bigWig. Will utilize the provided runtime for encoding values and for reading through the values (potentially parallelized by chromosome). The number of worker threads of the runtime determines how much work is done in parallel.
     /// This will take two passes on the provided values: first to write the values themselves, then the zooms. This is beneficial over `write` on smaller files, where the encoding of
     /// high resolution zooms takes up a substantial portion of total processing time.
     pub fn write_multipass<V: BBIDataSource<Value = Value>>(
@@ -216,6 +547,7 @@ impl<W: Write + Seek + Send + 'static> BigWigWrite<W> {
 
         let vals = make_vals()?;
 
+        let endianness = self.options.endianness;
         let output = bbiwrite::write_zoom_vals::<_, _, BigWigZoomsProcess<W>>(
             vals,
             self.options,
@@ -245,12 +577,106 @@ impl<W: Write + Seek + Send + 'static> BigWigWrite<W> {
             zoom_entries,
             summary,
             total_sections,
+            endianness,
         )?;
 
         Ok(())
     }
 }
 
+/// Splits overlapping values at their starts and ends and combines the values
+/// covering each piece, for the aggregating [`OnOverlap`] modes. Values must
+/// be sorted by start.
+#[derive(Default)]
+pub(crate) struct OverlapResolver {
+    /// Values (clipped to the parts not yet resolved) that may still overlap
+    /// a later value, in input order.
+    active: Vec<Value>,
+    /// The last resolved value, held back until the value after it is known.
+    held: Option<Value>,
+}
+
+impl ResolveOverlaps<Value> for OverlapResolver {
+    /// Invalid or unsorted values aren't resolved, so that processing reports
+    /// the error.
+    fn resolves(
+        &self,
+        on_overlap: OnOverlap,
+        current_val: &Value,
+        next_val: Option<&Value>,
+    ) -> bool {
+        on_overlap != OnOverlap::Error
+            && current_val.start <= current_val.end
+            && next_val.is_none_or(|next_val| current_val.start <= next_val.start)
+    }
+
+    /// Zero-width values that fall within other values are dropped.
+    fn push(
+        &mut self,
+        on_overlap: OnOverlap,
+        current_val: Value,
+        next_val: Option<&Value>,
+    ) -> Vec<(Value, Option<Value>)> {
+        let mut resolved = vec![];
+        if current_val.start == current_val.end && self.active.is_empty() {
+            resolved.push(current_val);
+        } else if current_val.start < current_val.end {
+            self.active.push(current_val);
+        }
+
+        // No later value starts before `bound`, so everything before it is final
+        let bound = next_val.map_or(u32::MAX, |v| v.start);
+        let mut points: Vec<u32> = self
+            .active
+            .iter()
+            .flat_map(|v| [v.start, v.end.min(bound)])
+            .collect();
+        points.sort_unstable();
+        points.dedup();
+        for w in points.windows(2) {
+            let (start, end) = (w[0], w[1]);
+            let mut covering = self
+                .active
+                .iter()
+                .filter(|v| v.start <= start && v.end >= end)
+                .map(|v| v.value);
+            let Some(first) = covering.next() else {
+                continue;
+            };
+            let (value, count) = covering.fold((first, 1), |(acc, count), v| {
+                let acc = match on_overlap {
+                    OnOverlap::Error | OnOverlap::Last => v,
+                    OnOverlap::Max => acc.max(v),
+                    OnOverlap::Sum | OnOverlap::Mean => acc + v,
+                };
+                (acc, count + 1)
+            });
+            let value = match on_overlap {
+                OnOverlap::Mean => value / count as f32,
+                _ => value,
+            };
+            resolved.push(Value { start, end, value });
+        }
+        self.active.retain(|v| v.end > bound);
+        for v in self.active.iter_mut() {
+            v.start = v.start.max(bound);
+        }
+
+        let mut ready = vec![];
+        for val in resolved {
+            if let Some(prev) = self.held.replace(val) {
+                ready.push((prev, Some(val)));
+            }
+        }
+        if next_val.is_none() {
+            if let Some(prev) = self.held.take() {
+                ready.push((prev, None));
+            }
+        }
+        ready
+    }
+}
+
 async fn process_val(
     current_val: Value,
     next_val: Option<&Value>,
@@ -266,12 +692,12 @@ async fn process_val(
     // Check a few preconditions:
     // - The current end is greater than or equal to the start
     // - The current end is at most the chromosome length
+    // - If there is a next value, then it does not start before value
     // - If there is a next value, then it does not overlap value
-    // TODO: test these correctly fails
     if current_val.start > current_val.end {
         return Err(BigWigInvalidInput(format!(
-            "Invalid bed graph: {} > {}",
-            current_val.start, current_val.end
+            "Invalid bed graph: the start ({}) is greater than the end ({}) on chromosome {}",
+            current_val.start, current_val.end, chrom
         )));
     }
     if current_val.end > chrom_length {
@@ -283,12 +709,28 @@ async fn process_val(
     match next_val {
         None => {}
         Some(next_val) => {
-            if current_val.end > next_val.start {
+            if current_val.start > next_val.start {
                 return Err(BigWigInvalidInput(format!(
-                    "Invalid bed graph: overlapping values on chromosome {} at {}-{} and {}-{}",
-                    chrom, current_val.start, current_val.end, next_val.start, next_val.end,
+                    "Invalid bed graph: not sorted on chromosome {} at {}-{} (first) and {}-{} (second). Use sort -k1,1 -k2,2n to sort the bedGraph before input.",
+                    chrom,
+                    current_val.start,
+                    current_val.end,
+                    next_val.start,
+                    next_val.end,
                 )));
             }
+            if current_val.end > next_val.start {
+                match options.on_overlap {
+                    OnOverlap::Error => {
+                        return Err(BigWigInvalidInput(format!(
+                            "Invalid bed graph: overlapping values on chromosome {} at {}-{} and {}-{}",
+                            chrom, current_val.start, current_val.end, next_val.start, next_val.end,
+                        )));
+                    }
+                    // Overlaps are resolved (by `OverlapResolver`) before values get here
+                    OnOverlap::Max | OnOverlap::Sum | OnOverlap::Mean | OnOverlap::Last => {}
+                }
+            }
         }
     }
 
@@ -309,7 +751,13 @@ async fn process_val(
     if next_val.is_none() || items.len() >= options.items_per_slot as usize {
         let items = std::mem::replace(items, Vec::with_capacity(options.items_per_slot as usize));
         let handle: tokio::task::JoinHandle<io::Result<(SectionData, usize)>> =
-            runtime.spawn(encode_section(options.compress, items, chrom_id));
+            runtime.spawn(encode_section(
+                options.effective_compression_level(),
+                options.endianness,
+                items,
+                chrom_id,
+                options.step_sections,
+            ));
         ftx.send(handle).await.expect("Couldn't send");
     }
 
@@ -323,7 +771,7 @@ async fn process_val_zoom(
     next_val: Option<&Value>,
     runtime: &Handle,
     chrom_id: u32,
-) {
+) -> Result<(), ProcessDataError> {
     // Then, add the item to the zoom item queues. This is a bit complicated.
     for zoom_item in zoom_items.iter_mut() {
         debug_assert_ne!(zoom_item.records.len(), options.items_per_slot as usize);
@@ -343,7 +791,11 @@ async fn process_val_zoom(
                 || zoom_item.records.len() == options.items_per_slot as usize
             {
                 let items = std::mem::take(&mut zoom_item.records);
-                let handle = runtime.spawn(encode_zoom_section(options.compress, items));
+                let handle = runtime.spawn(encode_zoom_section(
+                    options.effective_compression_level(),
+                    options.endianness,
+                    items,
+                ));
                 zoom_item.channel.send(handle).await.expect("Couln't send");
             }
             if add_start >= current_val.end {
@@ -370,7 +822,7 @@ async fn process_val_zoom(
                 },
             });
             // The end of zoom record
-            let next_end = zoom2.start + zoom_item.size;
+            let next_end = zoom_record_end(zoom2.start, zoom_item.size)?;
             // End of bases that we could add
             let add_end = std::cmp::min(next_end, current_val.end);
             // If the last zoom ends before this value starts, we don't add anything
@@ -390,10 +842,172 @@ async fn process_val_zoom(
                 zoom_item.records.push(zoom_item.live_info.take().unwrap());
             }
             // Set where we would start for next time
-            add_start = add_end;
+            add_start = std::cmp::max(add_end, current_val.start);
         }
         debug_assert_ne!(zoom_item.records.len(), options.items_per_slot as usize);
     }
+    Ok(())
+}
+
+/// The number of values batched per zoom task when zoom levels are computed
+/// in parallel (see [`BBIWriteOptions::parallel_zooms`]).
+const PARALLEL_ZOOM_BATCH_SIZE: usize = 1 << 14;
+
+/// Adds a value to the zoom levels, either directly or (if
+/// `options.parallel_zooms` is set) by batching values in `zoom_batch` and
+/// adding each batch to every level in a separate task. Either way, each
+/// level sees the same values in the same order, so the output is the same.
+/// A panic in a zoom task is returned as an `IoError`.
+async fn add_val_zoom(
+    zoom_items: &mut Vec<ZoomItem>,
+    zoom_batch: &mut Vec<Value>,
+    options: &BBIWriteOptions,
+    current_val: Value,
+    next_val: Option<&Value>,
+    runtime: &Handle,
+    chrom_id: u32,
+) -> Result<(), ProcessDataError> {
+    if !options.parallel_zooms || zoom_items.len() < 2 {
+        return process_val_zoom(
+            zoom_items,
+            options,
+            current_val,
+            next_val,
+            runtime,
+            chrom_id,
+        )
+        .await;
+    }
+    zoom_batch.push(current_val);
+    if next_val.is_some() && zoom_batch.len() < PARALLEL_ZOOM_BATCH_SIZE {
+        return Ok(());
+    }
+    let batch = Arc::new(std::mem::take(zoom_batch));
+    let next_val = next_val.copied();
+    let handles: Vec<_> = zoom_items
+        .drain(..)
+        .map(|zoom_item| {
+            let batch = batch.clone();
+            let options = options.clone();
+            let task_runtime = runtime.clone();
+            runtime.spawn(async move {
+                let mut zoom_items = vec![zoom_item];
+                for (i, val) in batch.iter().enumerate() {
+                    let next = batch.get(i + 1).or(next_val.as_ref());
+                    process_val_zoom(
+                        &mut zoom_items,
+                        &options,
+                        *val,
+                        next,
+                        &task_runtime,
+                        chrom_id,
+                    )
+                    .await?;
+                }
+                Ok::<_, ProcessDataError>(zoom_items.pop().unwrap())
+            })
+        })
+        .collect();
+    // Keep the levels in order
+    for handle in handles {
+        zoom_items.push(handle.await.map_err(io::Error::other)??);
+    }
+    Ok(())
+}
+
+/// The number of bases of each input read at a time by `CombinedValues`
+#[cfg(feature = "read")]
+const COMBINE_WINDOW_SIZE: u32 = 1 << 16;
+
+/// The `Value`s of combining several bigWigs per base, a window at a time.
+/// See [`BigWigWrite::write_combined`].
+#[cfg(feature = "read")]
+struct CombinedValues<'a, R, F> {
+    inputs: &'a mut [BigWigRead<R>],
+    combine: F,
+    chroms: vec::IntoIter<(String, u32)>,
+    chrom: Option<(String, u32)>,
+    window_start: u32,
+    pending: vec::IntoIter<Value>,
+    /// The current run of equal values, which may continue into the next window
+    run: Option<Value>,
+}
+
+#[cfg(feature = "read")]
+impl<'a, R: BBIFileRead, F: Fn(&[f32]) -> f32> CombinedValues<'a, R, F> {
+    /// Combines the values of the next window of the current chromosome,
+    /// returning the completed runs.
+    fn combine_window(
+        &mut self,
+        chrom: &str,
+        start: u32,
+        end: u32,
+    ) -> Result<Vec<Value>, BBIReadError> {
+        let values = self
+            .inputs
+            .iter_mut()
+            .map(|input| input.values(chrom, start, end))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut completed = vec![];
+        let mut column = vec![0.0; values.len()];
+        for (i, pos) in (start..end).enumerate() {
+            for (c, v) in column.iter_mut().zip(values.iter()) {
+                *c = v[i];
+            }
+            let value = (self.combine)(&column);
+            match &mut self.run {
+                Some(run) if run.end == pos && run.value == value => run.end += 1,
+                _ => {
+                    completed.extend(self.run.take());
+                    if !value.is_nan() {
+                        self.run = Some(Value {
+                            start: pos,
+                            end: pos + 1,
+                            value,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(completed)
+    }
+}
+
+#[cfg(feature = "read")]
+impl<'a, R: BBIFileRead, F: Fn(&[f32]) -> f32> Iterator for CombinedValues<'a, R, F> {
+    type Item = Result<(String, Value), BedValueError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(value) = self.pending.next() {
+                let chrom = self.chrom.as_ref().unwrap().0.clone();
+                return Some(Ok((chrom, value)));
+            }
+            let (chrom, length) = match &self.chrom {
+                Some(chrom) => chrom.clone(),
+                None => {
+                    self.chrom = Some(self.chroms.next()?);
+                    self.window_start = 0;
+                    continue;
+                }
+            };
+            if self.window_start >= length {
+                // The chromosome is done, so any run is complete
+                self.chrom = None;
+                if let Some(run) = self.run.take() {
+                    return Some(Ok((chrom, run)));
+                }
+                continue;
+            }
+            let start = self.window_start;
+            let end = start.saturating_add(COMBINE_WINDOW_SIZE).min(length);
+            self.window_start = end;
+            match self.combine_window(&chrom, start, end) {
+                Ok(values) => self.pending = values.into_iter(),
+                Err(e) => return Some(Err(BedValueError::IoError(e.into()))),
+            }
+        }
+    }
 }
 
 struct BigWigInvalidInput(String);
@@ -404,14 +1018,25 @@ impl From<BigWigInvalidInput> for ProcessDataError {
     }
 }
 
+impl Interval for Value {
+    fn start(&self) -> u32 {
+        self.start
+    }
+    fn end(&self) -> u32 {
+        self.end
+    }
+}
+
 pub(crate) struct BigWigFullProcess {
     summary: Summary,
     items: Vec<Value>,
     zoom_items: Vec<ZoomItem>,
+    zoom_batch: Vec<Value>,
 
     ftx: BBIDataProcessoringInputSectionChannel,
     chrom_id: u32,
     options: BBIWriteOptions,
+    pending: PendingValues<Value, OverlapResolver>,
     runtime: Handle,
     chrom: String,
     length: u32,
@@ -448,9 +1073,11 @@ impl BBIDataProcessorCreate for BigWigFullProcess {
             summary,
             items,
             zoom_items,
+            zoom_batch: vec![],
             ftx,
             chrom_id,
             options,
+            pending: PendingValues::default(),
             runtime,
             chrom,
             length,
@@ -478,9 +1105,15 @@ impl BBIDataProcessorCreate for BigWigFullProcess {
     }
 }
 
-impl BBIDataProcessor for BigWigFullProcess {
+impl ProcessResolved for BigWigFullProcess {
     type Value = Value;
-    async fn do_process(
+    type Overlaps = OverlapResolver;
+
+    fn pending(&mut self) -> (&BBIWriteOptions, &mut PendingValues<Value, OverlapResolver>) {
+        (&self.options, &mut self.pending)
+    }
+
+    async fn process_resolved(
         &mut self,
         current_val: Value,
         next_val: Option<&Value>,
@@ -489,12 +1122,14 @@ impl BBIDataProcessor for BigWigFullProcess {
             summary,
             items,
             zoom_items,
+            zoom_batch,
             ftx,
             chrom_id,
             options,
             runtime,
             chrom,
             length,
+            ..
         } = self;
         let chrom_id = *chrom_id;
         let length = *length;
@@ -513,17 +1148,16 @@ impl BBIDataProcessor for BigWigFullProcess {
         )
         .await?;
 
-        process_val_zoom(
+        add_val_zoom(
             zoom_items,
+            zoom_batch,
             options,
             current_val,
             next_val,
             &runtime,
             chrom_id,
         )
-        .await;
-
-        Ok(())
+        .await
     }
 }
 
@@ -537,6 +1171,7 @@ struct BigWigNoZoomsProcess {
     ftx: BBIDataProcessoringInputSectionChannel,
     chrom_id: u32,
     options: BBIWriteOptions,
+    pending: PendingValues<Value, OverlapResolver>,
     runtime: Handle,
     chrom: String,
     length: u32,
@@ -576,6 +1211,7 @@ impl BBIDataProcessorCreate for BigWigNoZoomsProcess {
             ftx,
             chrom_id,
             options,
+            pending: PendingValues::default(),
             runtime,
             chrom,
             length,
@@ -608,12 +1244,18 @@ impl BBIDataProcessorCreate for BigWigNoZoomsProcess {
     }
 }
 
-impl BBIDataProcessor for BigWigNoZoomsProcess {
+impl ProcessResolved for BigWigNoZoomsProcess {
     type Value = Value;
-    async fn do_process(
+    type Overlaps = OverlapResolver;
+
+    fn pending(&mut self) -> (&BBIWriteOptions, &mut PendingValues<Value, OverlapResolver>) {
+        (&self.options, &mut self.pending)
+    }
+
+    async fn process_resolved(
         &mut self,
-        current_val: Self::Value,
-        next_val: Option<&Self::Value>,
+        current_val: Value,
+        next_val: Option<&Value>,
     ) -> Result<(), ProcessDataError> {
         let BigWigNoZoomsProcess {
             ftx,
@@ -625,6 +1267,7 @@ impl BBIDataProcessor for BigWigNoZoomsProcess {
             summary,
             items,
             zoom_counts,
+            ..
         } = self;
 
         process_val(
@@ -660,9 +1303,11 @@ struct BigWigZoomsProcess<W: Write + Seek + Send + 'static> {
     temp_zoom_items: Vec<InternalTempZoomInfo<W>>,
     chrom_id: u32,
     options: BBIWriteOptions,
+    pending: PendingValues<Value, OverlapResolver>,
     runtime: Handle,
 
     zoom_items: Vec<ZoomItem>,
+    zoom_batch: Vec<Value>,
 }
 
 impl<W: Write + Seek + Send + 'static> BBIDataProcessorCreate for BigWigZoomsProcess<W> {
@@ -686,8 +1331,10 @@ impl<W: Write + Seek + Send + 'static> BBIDataProcessorCreate for BigWigZoomsPro
             temp_zoom_items,
             chrom_id,
             options,
+            pending: PendingValues::default(),
             runtime,
             zoom_items,
+            zoom_batch: vec![],
         }
     }
     fn destroy(self) -> Self::Out {
@@ -701,39 +1348,47 @@ impl<W: Write + Seek + Send + 'static> BBIDataProcessorCreate for BigWigZoomsPro
         ZoomsInternalProcessedData(self.temp_zoom_items)
     }
 }
-impl<W: Write + Seek + Send + 'static> BBIDataProcessor for BigWigZoomsProcess<W> {
+impl<W: Write + Seek + Send + 'static> ProcessResolved for BigWigZoomsProcess<W> {
     type Value = Value;
-    async fn do_process(
+    type Overlaps = OverlapResolver;
+
+    fn pending(&mut self) -> (&BBIWriteOptions, &mut PendingValues<Value, OverlapResolver>) {
+        (&self.options, &mut self.pending)
+    }
+
+    async fn process_resolved(
         &mut self,
-        current_val: Self::Value,
-        next_val: Option<&Self::Value>,
+        current_val: Value,
+        next_val: Option<&Value>,
     ) -> Result<(), ProcessDataError> {
         let BigWigZoomsProcess {
             chrom_id,
             options,
             runtime,
             zoom_items,
+            zoom_batch,
             ..
         } = self;
 
-        process_val_zoom(
+        add_val_zoom(
             zoom_items,
+            zoom_batch,
             options,
             current_val,
             next_val,
             &runtime,
             *chrom_id,
         )
-        .await;
-
-        Ok(())
+        .await
     }
 }
 
 async fn encode_section(
-    compress: bool,
+    compression_level: u32,
+    endianness: Endianness,
     items_in_section: Vec<Value>,
     chrom_id: u32,
+    step_sections: bool,
 ) -> io::Result<(SectionData, usize)> {
     use libdeflater::{CompressionLvl, Compressor};
 
@@ -741,23 +1396,35 @@ async fn encode_section(
 
     let start = items_in_section[0].start;
     let end = items_in_section[items_in_section.len() - 1].end;
-    bytes.write_u32::<NativeEndian>(chrom_id)?;
-    bytes.write_u32::<NativeEndian>(start)?;
-    bytes.write_u32::<NativeEndian>(end)?;
-    bytes.write_u32::<NativeEndian>(0)?;
-    bytes.write_u32::<NativeEndian>(0)?;
-    bytes.write_u8(1)?;
+    let (section_type, item_step, item_span) = if step_sections {
+        section_layout(&items_in_section)
+    } else {
+        (1, 0, 0)
+    };
+    endianness.write_u32(&mut bytes, chrom_id)?;
+    endianness.write_u32(&mut bytes, start)?;
+    endianness.write_u32(&mut bytes, end)?;
+    endianness.write_u32(&mut bytes, item_step)?;
+    endianness.write_u32(&mut bytes, item_span)?;
+    bytes.write_u8(section_type)?;
     bytes.write_u8(0)?;
-    bytes.write_u16::<NativeEndian>(items_in_section.len() as u16)?;
+    endianness.write_u16(&mut bytes, items_in_section.len() as u16)?;
 
     for item in items_in_section.iter() {
-        bytes.write_u32::<NativeEndian>(item.start)?;
-        bytes.write_u32::<NativeEndian>(item.end)?;
-        bytes.write_f32::<NativeEndian>(item.value)?;
+        match section_type {
+            1 => {
+                endianness.write_u32(&mut bytes, item.start)?;
+                endianness.write_u32(&mut bytes, item.end)?;
+            }
+            2 => endianness.write_u32(&mut bytes, item.start)?,
+            _ => {}
+        }
+        endianness.write_f32(&mut bytes, item.value)?;
     }
 
-    let (out_bytes, uncompress_buf_size) = if compress {
-        let mut compressor = Compressor::new(CompressionLvl::default());
+    let (out_bytes, uncompress_buf_size) = if compression_level > 0 {
+        let level = CompressionLvl::new(compression_level as i32).unwrap_or_default();
+        let mut compressor = Compressor::new(level);
         let max_sz = compressor.zlib_compress_bound(bytes.len());
         let mut compressed_data = vec![0; max_sz];
         let actual_sz = compressor
@@ -779,3 +1446,116 @@ async fn encode_section(
         uncompress_buf_size,
     ))
 }
+
+/// Picks the section type, step and span to encode `items` with: `fixedStep`
+/// (type 3) if the items have the same span and are evenly spaced,
+/// `variableStep` (type 2) if they only have the same span, and `bedGraph`
+/// (type 1) otherwise.
+fn section_layout(items: &[Value]) -> (u8, u32, u32) {
+    let span = items[0].end - items[0].start;
+    if items.iter().any(|item| item.end - item.start != span) {
+        return (1, 0, 0);
+    }
+    let step = match items {
+        [first, second, ..] => second.start - first.start,
+        _ => span,
+    };
+    let evenly_spaced = items
+        .windows(2)
+        .all(|pair| pair[1].start - pair[0].start == step);
+    if step > 0 && evenly_spaced {
+        (3, step, span)
+    } else {
+        (2, 0, span)
+    }
+}
+
+/// Reads the sections indexed by the cir tree at `index_offset`.
+#[cfg(feature = "read")]
+fn index_sections(
+    file: &mut File,
+    endianness: Endianness,
+    index_offset: u64,
+) -> Result<Vec<Section>, ProcessDataError> {
+    // The root node directly follows the 48 byte header
+    read_cir_tree_leaves(endianness, file, index_offset + 48)?
+        .into_iter()
+        .map(|leaf| {
+            if leaf.start_chrom_ix != leaf.end_chrom_ix {
+                return Err(ProcessDataError::InvalidInput(
+                    "Can't extend a file with blocks that span multiple chromosomes.".to_string(),
+                ));
+            }
+            Ok(Section {
+                chrom: leaf.start_chrom_ix,
+                start: leaf.start_base,
+                end: leaf.end_base,
+                offset: leaf.data_offset,
+                size: leaf.data_size,
+            })
+        })
+        .collect()
+}
+
+/// Writes the data of `section` at the current position of `file`.
+#[cfg(feature = "read")]
+fn append_section(file: &mut BufWriter<File>, section: SectionData) -> io::Result<Section> {
+    let offset = file.tell()?;
+    file.write_all(&section.data)?;
+    Ok(Section {
+        chrom: section.chrom,
+        start: section.start,
+        end: section.end,
+        offset,
+        size: section.data.len() as u64,
+    })
+}
+
+/// Computes the zoom records, each spanning at most `size` bases, for the
+/// sorted and non-overlapping `values` of a single chromosome. Each record
+/// starts at the first base with data after the previous record.
+#[cfg(feature = "read")]
+fn zoom_records(
+    values: &[Value],
+    size: u32,
+    chrom_id: u32,
+) -> Result<Vec<ZoomRecord>, ProcessDataError> {
+    let mut records: Vec<ZoomRecord> = vec![];
+    for value in values {
+        let val = f64::from(value.value);
+        let mut add_start = value.start;
+        while add_start < value.end {
+            let in_last = match records.last() {
+                Some(r) => add_start < zoom_record_end(r.start, size)?,
+                None => false,
+            };
+            if !in_last {
+                records.push(ZoomRecord {
+                    chrom: chrom_id,
+                    start: add_start,
+                    end: add_start,
+                    summary: Summary {
+                        total_items: 0,
+                        bases_covered: 0,
+                        min_val: val,
+                        max_val: val,
+                        sum: 0.0,
+                        sum_squares: 0.0,
+                    },
+                });
+            }
+            let record = records.last_mut().unwrap();
+            let add_end = zoom_record_end(record.start, size)?.min(value.end);
+            let added_bases = add_end - add_start;
+            record.end = add_end;
+            record.summary.total_items += 1;
+            record.summary.bases_covered += u64::from(added_bases);
+            record.summary.min_val = record.summary.min_val.min(val);
+            record.summary.max_val = record.summary.max_val.max(val);
+            record.summary.sum += f64::from(added_bases) * val;
+            record.summary.sum_squares += f64::from(added_bases) * val * val;
+            add_start = add_end;
+        }
+    }
+    Ok(records)
+}
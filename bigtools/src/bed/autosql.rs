@@ -56,6 +56,8 @@ table bed
 
 // Defined by https://github.com/ucscGenomeBrowser/kent/blob/c26640b68ba8ad219e7d79c3f8251ea20f9f57e0/src/hg/autoSql/autoSql.doc
 pub mod parse {
+    use thiserror::Error;
+
     mod parser {
         pub(super) struct Parser<'a> {
             pub(super) data: &'a str,
@@ -324,14 +326,21 @@ pub mod parse {
         }
     }
 
-    #[derive(Debug)]
+    #[derive(Error, Debug)]
     pub enum ParseError {
+        #[error("Invalid declaration type: {}", .0)]
         InvalidDeclareType(String),
+        #[error("Invalid declaration name: {}", .0)]
         InvalidDeclareName(String),
+        #[error("Expected bracket in declaration, found: {}", .0)]
         InvalidDeclareBrackets(String),
+        #[error("Expected `]` after field size, found: {}", .0)]
         InvalidFieldSizeClose(String),
+        #[error("Expected `;` after field, found: {}", .0)]
         InvalidFieldCommentSeparater(String),
+        #[error("Expected `(` for field values, found: {}", .0)]
         InvalidFieldValuesBrackets(String),
+        #[error("Expected `]` after index size, found: {}", .0)]
         InvalidIndexSizeBrackets(String),
     }
 
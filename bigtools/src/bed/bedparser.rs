@@ -9,6 +9,7 @@
 //! The second layer of abstraction (`BedParser`) manages the state information for when values switch
 //! from one chromosome to another. The is important because bigwig/bigbed writing is "chunked" by chromosome.
 
+use std::collections::VecDeque;
 use std::io::{self, BufRead, BufReader, Read};
 
 use thiserror::Error;
@@ -44,8 +45,11 @@ pub fn parse_bed<'a>(s: &'a str) -> Option<Result<(&'a str, BedEntry), BedValueE
     }
 }
 
+/// Parses a single bedGraph line (`chrom start end value`) into a `Value`.
+/// Returns an `InvalidInput` error if a column is missing or is not numeric.
 pub fn parse_bedgraph<'a>(s: &'a str) -> Option<Result<(&'a str, Value), BedValueError>> {
-    let mut split = s.trim_end().splitn(5, '\t');
+    let line = s.trim_end();
+    let mut split = line.splitn(5, '\t');
     let chrom = match split.next() {
         Some(chrom) => chrom,
         None => return None,
@@ -53,19 +57,19 @@ pub fn parse_bedgraph<'a>(s: &'a str) -> Option<Result<(&'a str, Value), BedValu
     let res = (|| {
         let s = split
             .next()
-            .ok_or_else(|| BedValueError::InvalidInput(format!("Missing start: {:}", s)))?;
+            .ok_or_else(|| BedValueError::InvalidInput(format!("Missing start: {:}", line)))?;
         let start = s
             .parse::<u32>()
             .map_err(|_| BedValueError::InvalidInput(format!("Invalid start: {:}", s)))?;
         let s = split
             .next()
-            .ok_or_else(|| BedValueError::InvalidInput(format!("Missing end: {:}", s)))?;
+            .ok_or_else(|| BedValueError::InvalidInput(format!("Missing end: {:}", line)))?;
         let end = s
             .parse::<u32>()
             .map_err(|_| BedValueError::InvalidInput(format!("Invalid end: {:}", s)))?;
         let s = split
             .next()
-            .ok_or_else(|| BedValueError::InvalidInput(format!("Missing value: {:}", s)))?;
+            .ok_or_else(|| BedValueError::InvalidInput(format!("Missing value: {:}", line)))?;
         let value = s
             .parse::<f32>()
             .map_err(|_| BedValueError::InvalidInput(format!("Invalid value: {:}", s)))?;
@@ -77,6 +81,18 @@ pub fn parse_bedgraph<'a>(s: &'a str) -> Option<Result<(&'a str, Value), BedValu
     }
 }
 
+/// Whether a line of a bed-like file contains no data and should be skipped:
+/// blank lines, `#` comments, and UCSC `track` and `browser` lines.
+pub(crate) fn is_skipped_line(line: &str) -> bool {
+    if line.starts_with('#') {
+        return true;
+    }
+    matches!(
+        line.split_whitespace().next(),
+        None | Some("track") | Some("browser")
+    )
+}
+
 // FIXME: can replace with this with just a simple `LendingIterator`
 /// Essentially a combined lending iterator over the chrom (&str) and remaining
 /// values of bed-like data
@@ -96,27 +112,205 @@ pub enum BedValueError {
 
 pub type Parser<V> = for<'a> fn(&'a str) -> Option<Result<(&'a str, V), BedValueError>>;
 
+/// The coordinate system of the starts in a bed-like file.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CoordBase {
+    /// 0-based, half-open intervals, as in bed files
+    #[default]
+    Zero,
+    /// 1-based, closed intervals (e.g. from GFF). Starts are converted to
+    /// 0-based on input, and must be at least `1`.
+    One,
+}
+
+/// The layout of the columns in a bed-like file. The default is the standard
+/// layout: tab-delimited, with the chrom, start, and end as the first three
+/// columns. Any other columns are passed on, in order, as the rest of the
+/// line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BedStreamConfig {
+    pub delimiter: u8,
+    pub chrom_column: usize,
+    pub start_column: usize,
+    pub end_column: usize,
+    pub coordinate_base: CoordBase,
+}
+
+impl Default for BedStreamConfig {
+    fn default() -> Self {
+        BedStreamConfig {
+            delimiter: b'\t',
+            chrom_column: 0,
+            start_column: 1,
+            end_column: 2,
+            coordinate_base: CoordBase::Zero,
+        }
+    }
+}
+
+impl BedStreamConfig {
+    /// Rewrites `line` into the standard layout (with 0-based starts),
+    /// stopping early if the chrom, start, or end column is missing (which the
+    /// parser then reports).
+    fn remap_line(&self, line: &str, out: &mut String) -> Result<(), BedValueError> {
+        out.clear();
+        let fields: Vec<&str> = line.trim_end().split(self.delimiter as char).collect();
+        let columns = [self.chrom_column, self.start_column, self.end_column];
+        for (i, column) in columns.iter().enumerate() {
+            let Some(field) = fields.get(*column) else {
+                return Ok(());
+            };
+            if i > 0 {
+                out.push('\t');
+            }
+            match (i, self.coordinate_base, field.parse::<u32>()) {
+                (1, CoordBase::One, Ok(0)) => {
+                    return Err(BedValueError::InvalidInput(format!(
+                        "Start must be at least 1 for 1-based coordinates: {}",
+                        field
+                    )));
+                }
+                (1, CoordBase::One, Ok(start)) => out.push_str(&(start - 1).to_string()),
+                _ => out.push_str(field),
+            }
+        }
+        for (i, field) in fields.iter().enumerate() {
+            if !columns.contains(&i) {
+                out.push('\t');
+                out.push_str(field);
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Parses a bed-like file
 pub struct BedFileStream<V, B> {
     pub bed: StreamingLineReader<B>,
     pub parse: Parser<V>,
+    pub(crate) config: BedStreamConfig,
+    /// The current line, rewritten into the standard layout. Only used with a
+    /// non-default `config`.
+    pub(crate) remapped: String,
+    /// The chromosome of the last value parsed.
+    chrom: String,
+    /// If set, lines that can't be parsed are passed to this and skipped,
+    /// rather than returned as errors. See `new_lenient`.
+    pub(crate) on_error: Option<Box<dyn FnMut(BedValueError) + Send>>,
 }
 
-impl<R: Read> BedFileStream<BedEntry, BufReader<R>> {
-    pub fn from_bed_file(file: R) -> BedFileStream<BedEntry, BufReader<R>> {
+impl<V, B> BedFileStream<V, B> {
+    pub fn new(bed: StreamingLineReader<B>, parse: Parser<V>) -> Self {
+        BedFileStream::with_config(bed, parse, BedStreamConfig::default())
+    }
+
+    /// Like `new`, but for files with the columns laid out as in `config`.
+    pub fn with_config(
+        bed: StreamingLineReader<B>,
+        parse: Parser<V>,
+        config: BedStreamConfig,
+    ) -> Self {
         BedFileStream {
-            bed: StreamingLineReader::new(BufReader::new(file)),
-            parse: parse_bed,
+            bed,
+            parse,
+            config,
+            remapped: String::new(),
+            chrom: String::new(),
+            on_error: None,
+        }
+    }
+
+    /// Like `new`, but lines that can't be parsed don't end the stream: the
+    /// error (with the line number and line) is passed to `on_error`, and
+    /// parsing continues with the next line. This is useful to log and skip
+    /// malformed lines. I/O errors are still returned.
+    pub fn new_lenient(
+        bed: StreamingLineReader<B>,
+        parse: Parser<V>,
+        on_error: impl FnMut(BedValueError) + Send + 'static,
+    ) -> Self {
+        let mut stream = BedFileStream::new(bed, parse);
+        stream.on_error = Some(Box::new(on_error));
+        stream
+    }
+}
+
+impl<V, B: BufRead> BedFileStream<V, B> {
+    /// The 1-based line number of the last line read. See
+    /// [`StreamingLineReader::line_number`].
+    pub fn line_number(&self) -> usize {
+        self.bed.line_number()
+    }
+
+    /// Parses the current line, adding the line number and line to any
+    /// parse error. The chromosome is copied into `chrom`, so that the line
+    /// is no longer borrowed and malformed lines can be skipped.
+    fn parse_current(&mut self) -> Option<Result<V, BedValueError>> {
+        let line_number = self.bed.line_number();
+        let line = self.bed.current_line();
+        let parsed = if self.config == BedStreamConfig::default() {
+            (self.parse)(line)
+        } else {
+            match self.config.remap_line(line, &mut self.remapped) {
+                Ok(()) => (self.parse)(&self.remapped),
+                Err(e) => Some(Err(e)),
+            }
+        };
+        match parsed {
+            None => None,
+            Some(Ok((chrom, v))) => {
+                self.chrom.clear();
+                self.chrom.push_str(chrom);
+                Some(Ok(v))
+            }
+            Some(Err(BedValueError::InvalidInput(e))) => Some(Err(BedValueError::InvalidInput(
+                format!("{} (line {}: `{}`)", e, line_number, line),
+            ))),
+            Some(Err(e)) => Some(Err(e)),
         }
     }
 }
 
+impl<B: BufRead> BedFileStream<BedEntry, B> {
+    /// Creates a stream of `BedEntry`s from any buffered reader (e.g. stdin or
+    /// a decompressor).
+    pub fn from_bed_bufread(bufread: B) -> BedFileStream<BedEntry, B> {
+        BedFileStream::new(StreamingLineReader::new(bufread), parse_bed)
+    }
+}
+
+impl<B: BufRead> BedFileStream<Value, B> {
+    /// Creates a stream of `Value`s from any buffered reader (e.g. stdin or a
+    /// decompressor) of bedGraph data.
+    pub fn from_bedgraph_bufread(bufread: B) -> BedFileStream<Value, B> {
+        BedFileStream::new(StreamingLineReader::new(bufread), parse_bedgraph)
+    }
+}
+
+impl<R: Read> BedFileStream<BedEntry, BufReader<R>> {
+    pub fn from_bed_file(file: R) -> BedFileStream<BedEntry, BufReader<R>> {
+        BedFileStream::from_bed_bufread(BufReader::new(file))
+    }
+
+    /// Creates a stream of `BedEntry`s from a bed file with the columns laid
+    /// out as in `config`.
+    pub fn from_bed_file_with_config(
+        file: R,
+        config: BedStreamConfig,
+    ) -> BedFileStream<BedEntry, BufReader<R>> {
+        BedFileStream::with_config(
+            StreamingLineReader::new(BufReader::new(file)),
+            parse_bed,
+            config,
+        )
+    }
+}
+
 impl<R: Read> BedFileStream<Value, BufReader<R>> {
+    /// Creates a stream of `Value`s from a bedGraph file
+    /// (`chrom start end value`), suitable for writing a bigWig.
     pub fn from_bedgraph_file(file: R) -> BedFileStream<Value, BufReader<R>> {
-        BedFileStream {
-            bed: StreamingLineReader::new(BufReader::new(file)),
-            parse: parse_bedgraph,
-        }
+        BedFileStream::from_bedgraph_bufread(BufReader::new(file))
     }
 }
 
@@ -124,15 +318,205 @@ impl<V, B: BufRead> StreamingBedValues for BedFileStream<V, B> {
     type Value = V;
 
     fn next(&mut self) -> Option<Result<(&str, Self::Value), BedValueError>> {
-        let line = match self.bed.read()? {
-            Ok(line) => line.trim_end(),
-            Err(e) => return Some(Err(e.into())),
+        loop {
+            match self.bed.read()? {
+                Ok(line) if is_skipped_line(line) => continue,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e.into())),
+            }
+            match self.parse_current()? {
+                Ok(v) => return Some(Ok((&self.chrom, v))),
+                Err(e @ BedValueError::InvalidInput(_)) if self.on_error.is_some() => {
+                    (self.on_error.as_mut().unwrap())(e);
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Wraps a `StreamingBedValues`, allowing upcoming values to be peeked
+/// without consuming them (e.g. to prepare for the next chromosome before the
+/// current one is finished).
+pub struct PeekableBedStream<S: StreamingBedValues> {
+    inner: S,
+    chrom: String,
+    /// Values read from `inner` but not yet returned, with their chromosomes.
+    peeked: VecDeque<S::Value>,
+    peeked_chroms: VecDeque<String>,
+    /// Set once `inner` has ended (`Some(None)`) or errored (`Some(Some(_))`)
+    /// after the buffered values.
+    peeked_end: Option<Option<BedValueError>>,
+}
+
+impl<S: StreamingBedValues> PeekableBedStream<S> {
+    pub fn new(inner: S) -> Self {
+        PeekableBedStream {
+            inner,
+            chrom: String::new(),
+            peeked: VecDeque::new(),
+            peeked_chroms: VecDeque::new(),
+            peeked_end: None,
+        }
+    }
+
+    /// Reads from `inner` until at least `n` values are buffered, or `inner`
+    /// ends or errors.
+    fn fill(&mut self, n: usize) {
+        while self.peeked.len() < n && self.peeked_end.is_none() {
+            match self.inner.next() {
+                None => self.peeked_end = Some(None),
+                Some(Err(e)) => self.peeked_end = Some(Some(e)),
+                Some(Ok((chrom, val))) => {
+                    self.peeked_chroms.push_back(chrom.to_string());
+                    self.peeked.push_back(val);
+                }
+            }
+        }
+    }
+
+    /// The chromosome of the next value, without consuming it. Returns `None`
+    /// if there are no more values, or if the next value is an error (which
+    /// is returned by the following call to `next`).
+    pub fn peek_chrom(&mut self) -> Option<&str> {
+        self.fill(1);
+        self.peeked_chroms.front().map(String::as_str)
+    }
+
+    /// Up to `n` upcoming values on the current chromosome, without consuming
+    /// them. The current chromosome is that of the last value returned by
+    /// `next` (or of the next value, if none has been returned yet), so this
+    /// is empty once the stream has moved past it. Fewer than `n` values are
+    /// returned if the chromosome ends, the stream ends, or an error is
+    /// reached (the error is returned by `next` after the buffered values).
+    ///
+    /// This buffers up to `n` values in memory.
+    pub fn peek_n(&mut self, n: usize) -> &[S::Value] {
+        self.fill(n);
+        let chrom = if self.chrom.is_empty() {
+            match self.peeked_chroms.front() {
+                Some(chrom) => chrom,
+                None => return &[],
+            }
+        } else {
+            &self.chrom
         };
-        match (self.parse)(line) {
+        let len = self
+            .peeked_chroms
+            .iter()
+            .take(n)
+            .take_while(|c| *c == chrom)
+            .count();
+        &self.peeked.make_contiguous()[..len]
+    }
+}
+
+impl<S: StreamingBedValues> StreamingBedValues for PeekableBedStream<S> {
+    type Value = S::Value;
+
+    fn next(&mut self) -> Option<Result<(&str, Self::Value), BedValueError>> {
+        if let Some(val) = self.peeked.pop_front() {
+            // Chroms and values are always pushed together
+            self.chrom = self.peeked_chroms.pop_front().unwrap();
+            return Some(Ok((&self.chrom, val)));
+        }
+        match self.peeked_end.take() {
+            Some(None) => return None,
+            Some(Some(e)) => return Some(Err(e)),
+            None => {}
+        }
+        match self.inner.next()? {
+            Ok((chrom, val)) => {
+                if self.chrom != chrom {
+                    self.chrom.clear();
+                    self.chrom.push_str(chrom);
+                }
+                Some(Ok((&self.chrom, val)))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// K-way merges several bedGraph-like sources, each sorted by chromosome
+/// (lexicographically, as with `sort -k1,1 -k2,2n`) and start, into a single
+/// stream sorted by `(chrom, start)`. For example, to write per-chromosome
+/// bedGraph shards to one bigWig without concatenating them first.
+///
+/// It is an error for a source to not be sorted. Values that overlap (within
+/// or across sources) are passed through as is, so that `BigWigWrite` handles
+/// them according to [`BBIWriteOptions::on_overlap`] (by default, an error).
+/// Each value takes `O(k)` time for `k` sources.
+///
+/// [`BBIWriteOptions::on_overlap`]: crate::BBIWriteOptions::on_overlap
+pub struct MergedBedStream<S: StreamingBedValues<Value = Value>> {
+    sources: Vec<S>,
+    /// The next value of each source, or `None` if it has ended.
+    heads: Vec<Option<(String, Value)>>,
+    started: bool,
+    chrom: String,
+}
+
+impl<S: StreamingBedValues<Value = Value>> MergedBedStream<S> {
+    pub fn new(sources: Vec<S>) -> Self {
+        let heads = sources.iter().map(|_| None).collect();
+        MergedBedStream {
+            sources,
+            heads,
+            started: false,
+            chrom: String::new(),
+        }
+    }
+
+    /// Reads the next value of source `i`, checking that it doesn't come
+    /// before `prev` (the source's previous value).
+    fn advance(&mut self, i: usize, prev: Option<(&str, &Value)>) -> Result<(), BedValueError> {
+        self.heads[i] = match self.sources[i].next() {
             None => None,
-            Some(Ok(v)) => Some(Ok(v)),
-            Some(Err(e)) => Some(Err(e.into())),
+            Some(Err(e)) => return Err(e),
+            Some(Ok((chrom, val))) => {
+                if let Some((prev_chrom, prev)) = prev {
+                    if (chrom, val.start) < (prev_chrom, prev.start) {
+                        return Err(BedValueError::InvalidInput(format!(
+                            "Source {} is not sorted: {}:{}-{} comes after {}:{}-{}. Use sort -k1,1 -k2,2n to sort each source before merging.",
+                            i, chrom, val.start, val.end, prev_chrom, prev.start, prev.end,
+                        )));
+                    }
+                }
+                Some((chrom.to_string(), val))
+            }
+        };
+        Ok(())
+    }
+}
+
+impl<S: StreamingBedValues<Value = Value>> StreamingBedValues for MergedBedStream<S> {
+    type Value = Value;
+
+    fn next(&mut self) -> Option<Result<(&str, Value), BedValueError>> {
+        if !self.started {
+            self.started = true;
+            for i in 0..self.sources.len() {
+                if let Err(e) = self.advance(i, None) {
+                    return Some(Err(e));
+                }
+            }
+        }
+        // Ties go to the first source
+        let (i, _) = self
+            .heads
+            .iter()
+            .enumerate()
+            .filter_map(|(i, head)| head.as_ref().map(|(chrom, val)| (i, (chrom, val.start))))
+            .min_by(|a, b| a.1.cmp(&b.1))?;
+        let (chrom, val) = self.heads[i].take().unwrap();
+        if let Err(e) = self.advance(i, Some((&chrom, &val))) {
+            return Some(Err(e));
+        }
+        if self.chrom != chrom {
+            self.chrom = chrom;
         }
+        Some(Ok((&self.chrom, val)))
     }
 }
 
@@ -194,3 +578,269 @@ impl<V: Clone, C: Into<String> + for<'a> PartialEq<&'a str>, I: Iterator<Item =
         self.curr.as_ref().map(|v| Ok((v.0.deref(), v.1.clone())))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_malformed_line() {
+        let data = "chr1\t1\t100\t0.5\n\nchr1\t101\tabc\t0.5\nchr1\t201\t300\t0.5\n";
+        let mut stream = BedFileStream::from_bedgraph_file(data.as_bytes());
+        let (chrom, value) = stream.next().unwrap().unwrap();
+        assert_eq!(chrom, "chr1");
+        assert_eq!(value.end, 100);
+        match stream.next() {
+            Some(Err(BedValueError::InvalidInput(e))) => {
+                assert!(e.contains("line 3"), "{}", e);
+                assert!(e.contains("chr1\t101\tabc\t0.5"), "{}", e);
+            }
+            _ => panic!("Expected an error for a malformed line."),
+        }
+        assert_eq!(stream.line_number(), 3);
+    }
+
+    #[test]
+    fn test_lenient() {
+        use std::sync::{Arc, Mutex};
+
+        let data = "chr1\t1\t100\t0.5\nchr1\t101\tabc\t0.5\nchr1\t201\t300\t1.5\nchr1\t301\t400\txyz\nchr2\t1\t10\t2.5\n";
+        let errors = Arc::new(Mutex::new(vec![]));
+        let errors_clone = errors.clone();
+        let mut stream = BedFileStream::new_lenient(
+            StreamingLineReader::new(data.as_bytes()),
+            parse_bedgraph,
+            move |e| errors_clone.lock().unwrap().push(e.to_string()),
+        );
+        let mut values = vec![];
+        while let Some(value) = stream.next() {
+            let (chrom, value) = value.unwrap();
+            values.push((chrom.to_string(), value.start, value.value));
+        }
+        assert_eq!(
+            values,
+            [
+                ("chr1".to_string(), 1, 0.5),
+                ("chr1".to_string(), 201, 1.5),
+                ("chr2".to_string(), 1, 2.5),
+            ]
+        );
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("line 2"), "{}", errors[0]);
+        assert!(errors[1].contains("line 4"), "{}", errors[1]);
+    }
+
+    #[test]
+    fn test_bedgraph_invalid_value() {
+        let data = "chr1\t1\t100\tabc\nchr1\t101\t200\n";
+        let mut stream = BedFileStream::from_bedgraph_file(data.as_bytes());
+        match stream.next() {
+            Some(Err(BedValueError::InvalidInput(e))) => {
+                assert!(e.starts_with("Invalid value: abc"), "{}", e);
+            }
+            _ => panic!("Expected an error for a non-numeric value."),
+        }
+        match stream.next() {
+            Some(Err(BedValueError::InvalidInput(e))) => {
+                assert!(e.starts_with("Missing value: chr1\t101\t200"), "{}", e);
+            }
+            _ => panic!("Expected an error for a missing value."),
+        }
+    }
+
+    #[test]
+    fn test_column_config() {
+        let data = "name1,chr1,+,1,100\nname2,chr1,-,101,200\nname3,chr2\n";
+        let config = BedStreamConfig {
+            delimiter: b',',
+            chrom_column: 1,
+            start_column: 3,
+            end_column: 4,
+            ..Default::default()
+        };
+        let mut stream = BedFileStream::from_bed_file_with_config(data.as_bytes(), config);
+        let (chrom, entry) = stream.next().unwrap().unwrap();
+        assert_eq!(chrom, "chr1");
+        assert_eq!((entry.start, entry.end), (1, 100));
+        assert_eq!(entry.rest, "name1\t+");
+        let (chrom, entry) = stream.next().unwrap().unwrap();
+        assert_eq!(chrom, "chr1");
+        assert_eq!((entry.start, entry.end), (101, 200));
+        assert_eq!(entry.rest, "name2\t-");
+        match stream.next() {
+            Some(Err(BedValueError::InvalidInput(e))) => {
+                assert!(e.contains("name3,chr2"), "{}", e);
+            }
+            _ => panic!("Expected an error for a line with missing columns."),
+        }
+    }
+
+    #[test]
+    fn test_one_based() {
+        let zero_based = "chr1\t0\t100\tname1\nchr1\t100\t200\tname2\n";
+        let one_based = "chr1\t1\t100\tname1\nchr1\t101\t200\tname2\nchr1\t0\t10\tname3\n";
+        let config = BedStreamConfig {
+            coordinate_base: CoordBase::One,
+            ..Default::default()
+        };
+        let mut expected = BedFileStream::from_bed_file(zero_based.as_bytes());
+        let mut stream = BedFileStream::from_bed_file_with_config(one_based.as_bytes(), config);
+        for _ in 0..2 {
+            let (chrom, entry) = stream.next().unwrap().unwrap();
+            let (chrom, entry) = (chrom.to_string(), entry);
+            let (expected_chrom, expected_entry) = expected.next().unwrap().unwrap();
+            assert_eq!(chrom, expected_chrom);
+            assert_eq!(entry, expected_entry);
+        }
+        assert!(expected.next().is_none());
+        match stream.next() {
+            Some(Err(BedValueError::InvalidInput(e))) => {
+                assert!(e.contains("at least 1"), "{}", e);
+                assert!(e.contains("line 3"), "{}", e);
+            }
+            _ => panic!("Expected an error for a start of 0."),
+        }
+    }
+
+    #[test]
+    fn test_crlf() {
+        let data = "chr1\t1\t100\tname1\r\nchr1\t101\t200\tname2 \t\r\nchr1\t201\t300\r\n";
+        let mut stream = BedFileStream::from_bed_file(data.as_bytes());
+        assert_eq!(stream.next().unwrap().unwrap().1.rest, "name1");
+        assert_eq!(stream.next().unwrap().unwrap().1.rest, "name2");
+        let (_, entry) = stream.next().unwrap().unwrap();
+        assert_eq!((entry.end, entry.rest.as_str()), (300, ""));
+        assert!(stream.next().is_none());
+
+        let data = "chr1\t1\t100\t0.5\r\nchr1\t101\t200\t1.5\r\n";
+        let mut stream = BedFileStream::from_bedgraph_file(data.as_bytes());
+        assert_eq!(stream.next().unwrap().unwrap().1.value, 0.5);
+        assert_eq!(stream.next().unwrap().unwrap().1.value, 1.5);
+    }
+
+    #[test]
+    fn test_peek_chrom() {
+        let data = "chr1\t1\t100\t0.5\nchr1\t101\t200\t0.5\nchr2\t1\t100\t0.5\n";
+        let mut stream = PeekableBedStream::new(BedFileStream::from_bedgraph_file(data.as_bytes()));
+        assert_eq!(stream.peek_chrom(), Some("chr1"));
+        assert_eq!(stream.peek_chrom(), Some("chr1"));
+        assert_eq!(stream.next().unwrap().unwrap().1.start, 1);
+        assert_eq!(stream.next().unwrap().unwrap().1.start, 101);
+        assert_eq!(stream.peek_chrom(), Some("chr2"));
+        let (chrom, value) = stream.next().unwrap().unwrap();
+        assert_eq!((chrom, value.start), ("chr2", 1));
+        assert_eq!(stream.peek_chrom(), None);
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_peek_n() {
+        let data =
+            "chr1\t1\t100\t0.5\nchr1\t101\t200\t0.5\nchr1\t201\t300\t0.5\nchr2\t1\t100\t0.5\n";
+        let mut stream = PeekableBedStream::new(BedFileStream::from_bedgraph_file(data.as_bytes()));
+        let starts = |vals: &[Value]| vals.iter().map(|v| v.start).collect::<Vec<_>>();
+        assert_eq!(starts(stream.peek_n(2)), [1, 101]);
+        assert_eq!(starts(stream.peek_n(10)), [1, 101, 201]);
+        assert_eq!(stream.peek_chrom(), Some("chr1"));
+        assert_eq!(stream.next().unwrap().unwrap().1.start, 1);
+        assert_eq!(starts(stream.peek_n(10)), [101, 201]);
+        assert_eq!(stream.next().unwrap().unwrap().1.start, 101);
+        assert_eq!(stream.next().unwrap().unwrap().1.start, 201);
+        assert!(stream.peek_n(10).is_empty());
+        assert_eq!(stream.peek_chrom(), Some("chr2"));
+        let (chrom, value) = stream.next().unwrap().unwrap();
+        assert_eq!((chrom, value.start), ("chr2", 1));
+        assert!(stream.peek_n(10).is_empty());
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_merged_stream() {
+        let stream = |data: &'static str| BedFileStream::from_bedgraph_file(data.as_bytes());
+        let mut merged = MergedBedStream::new(vec![
+            stream("chr1\t1\t100\t0.5\nchr1\t300\t400\t0.5\nchr3\t1\t100\t0.5\n"),
+            stream("chr1\t100\t200\t1.5\nchr2\t1\t100\t1.5\n"),
+            stream(""),
+        ]);
+        let mut vals = vec![];
+        while let Some(val) = merged.next() {
+            let (chrom, val) = val.unwrap();
+            vals.push((chrom.to_string(), val.start, val.value));
+        }
+        let expected = [
+            ("chr1", 1, 0.5),
+            ("chr1", 100, 1.5),
+            ("chr1", 300, 0.5),
+            ("chr2", 1, 1.5),
+            ("chr3", 1, 0.5),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(chrom, start, value)| (chrom.to_string(), *start, *value))
+            .collect();
+        assert_eq!(vals, expected);
+
+        // Values from different sources overlap, and are left for the writer
+        let mut merged = MergedBedStream::new(vec![
+            stream("chr1\t1\t100\t0.5\n"),
+            stream("chr1\t50\t150\t1.5\n"),
+        ]);
+        assert_eq!(merged.next().unwrap().unwrap().1.start, 1);
+        assert_eq!(merged.next().unwrap().unwrap().1.start, 50);
+        assert!(merged.next().is_none());
+
+        // A source isn't sorted
+        let mut merged = MergedBedStream::new(vec![
+            stream("chr2\t1\t100\t0.5\nchr1\t1\t100\t0.5\n"),
+            stream("chr3\t1\t100\t1.5\n"),
+        ]);
+        match merged.next() {
+            Some(Err(BedValueError::InvalidInput(e))) => {
+                assert!(e.contains("Source 0 is not sorted"), "{}", e)
+            }
+            _ => panic!("Expected an error for an unsorted source."),
+        }
+    }
+
+    #[test]
+    fn test_bufread() {
+        let data = "chr1\t1\t100\t0.5\nchr1\t101\t200\t1.5\n";
+        let mut stream = BedFileStream::from_bedgraph_bufread(io::Cursor::new(data));
+        assert_eq!(stream.next().unwrap().unwrap().1.value, 0.5);
+        assert_eq!(stream.next().unwrap().unwrap().1.value, 1.5);
+        assert!(stream.next().is_none());
+
+        let data = "chr1\t1\t100\tname\n";
+        let mut stream = BedFileStream::from_bed_bufread(data.as_bytes());
+        assert_eq!(stream.next().unwrap().unwrap().1.rest, "name");
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_header_lines() {
+        let data = "\
+browser position chr1:1-300
+track type=bedGraph name=test
+# A comment
+chr1\t1\t100\t0.5
+#chr1\t50\t100\t0.5
+chr1\t101\t200\t0.5
+";
+        let mut stream = BedFileStream::from_bedgraph_file(data.as_bytes());
+        let (chrom, value) = stream.next().unwrap().unwrap();
+        assert_eq!(chrom, "chr1");
+        assert_eq!(value.start, 1);
+        assert_eq!(stream.next().unwrap().unwrap().1.start, 101);
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_blank_lines() {
+        let data = "chr1\t1\t100\t0.5\n   \n\nchr1\t101\t200\t0.5\n\n";
+        let mut stream = BedFileStream::from_bedgraph_file(data.as_bytes());
+        assert_eq!(stream.next().unwrap().unwrap().1.start, 1);
+        assert_eq!(stream.next().unwrap().unwrap().1.start, 101);
+        assert!(stream.next().is_none());
+    }
+}
@@ -6,6 +6,7 @@ use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
 
 use index_list::{IndexList, ListIndex};
 
+use crate::bed::bedparser::is_skipped_line;
 use crate::utils::tell::Tell;
 
 /// Returns a Vec of offsets into a bed file, and the chromosome starting at each offset.
@@ -14,7 +15,14 @@ pub fn index_chroms(file: File) -> io::Result<Option<Vec<(u64, String)>>> {
 
     let mut line = String::new();
 
-    file.read_line(&mut line)?;
+    // Skip any leading header lines; they are skipped when parsing too
+    loop {
+        line.clear();
+        file.read_line(&mut line)?;
+        if line.is_empty() || !is_skipped_line(line.trim_end()) {
+            break;
+        }
+    }
 
     if line.is_empty() {
         return Err(io::Error::new(
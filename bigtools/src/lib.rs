@@ -17,6 +17,33 @@ in `info` fields. However, to access the main data, the most common method to ca
 is [`BigWigRead::get_interval`] or [`BigBedRead::get_interval`], which returns an
 `Iterator` of [`Value`]s or [`BedEntry`]s overlapping the provided region, respectively.
 
+For many queries over the same regions (for example, from a genome browser),
+[`BigWigRead::cached`] and [`BigBedRead::cached`] wrap the reader in a
+[`CachedBBIFileRead`], which caches the index and keeps an LRU cache of up to
+[`DEFAULT_BLOCK_CACHE_CAPACITY`] decompressed blocks. A cached block is neither
+read nor decompressed again. The capacity can be changed with
+[`BigWigRead::set_block_cache_capacity`] (where `0` disables block caching),
+and the cache emptied with [`BigWigRead::clear_block_cache`].
+
+With the `async` feature, `AsyncBigWigRead` provides the same for bigWigs over
+tokio's `AsyncRead` and `AsyncSeek`, returning a `Stream` of [`Value`]s.
+
+## WebAssembly
+
+The read side can be built for `wasm32-unknown-unknown`. Disable the default
+features and enable only `read` (i.e. `default-features = false, features =
+["read"]`): the `write`, `cli`, `remote`, `mmap`, and `async` features rely on
+files, threads, or a multithreaded tokio runtime, and don't build for wasm.
+Files can't be opened there, so use an in-memory reader like
+[`BytesReader`][crate::utils::file::bytes_reader::BytesReader] with
+[`BigWigRead::open`] rather than [`BigWigRead::open_file`].
+`BigWigRead::get_interval_parallel` is not available on wasm. Note that
+libdeflate is compiled from C, so this needs a C compiler (e.g. clang) that can
+target wasm. This build is checked in CI.
+
+Writing isn't supported on wasm: the writers spill sections to temporary
+files and need a tokio runtime with `rt-multi-thread`.
+
 ## Writing
 
 Writing new bigWigs and bigBeds is a tad more difficult. To begin, a
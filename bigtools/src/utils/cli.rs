@@ -17,11 +17,18 @@ pub mod bigwigmerge;
 pub mod bigwigtobedgraph;
 pub mod bigwigvaluesoverbed;
 
+/// The number of logical CPUs, or `6` if that can't be determined.
+fn default_nthreads() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(6)
+}
+
 #[derive(Clone, Debug, PartialEq, Args)]
 pub struct BBIWriteArgs {
-    /// Set the number of threads to use. This tool will typically use ~225% CPU on a HDD. SDDs may be higher. (IO bound)
+    /// Set the number of threads to use. Defaults to the number of logical CPUs. This tool will typically use ~225% CPU on a HDD. SDDs may be higher. (IO bound)
     #[arg(short = 't', long)]
-    #[arg(default_value_t = 6)]
+    #[arg(default_value_t = default_nthreads())]
     pub nthreads: usize,
 
     /// Set the maximum of zooms to create.
@@ -73,12 +73,20 @@ pub fn bedgraphtobigwig(args: BedGraphToBigWigArgs) -> Result<(), Box<dyn Error>
         .map(|l| {
             let words = l.expect("Split error");
             let mut split = words.split_whitespace();
-            (
-                split.next().expect("Missing chrom").to_owned(),
-                split.next().expect("Missing size").parse::<u32>().unwrap(),
-            )
+            let chrom = split.next().expect("Missing chrom").to_owned();
+            let size = split.next().expect("Missing size");
+            // Positions in bbi files are u32, so larger chromosomes can't be written
+            match size.parse::<u32>() {
+                Ok(size) => Ok((chrom, size)),
+                Err(_) => Err(format!(
+                    "Invalid size for chromosome {}: `{}`. Sizes must be integers of at most {}.",
+                    chrom,
+                    size,
+                    u32::MAX
+                )),
+            }
         })
-        .collect();
+        .collect::<Result<_, String>>()?;
 
     let mut outb = BigWigWrite::create_file(bigwigpath, chrom_map)?;
     outb.options.max_zooms = args.write_args.nzooms;
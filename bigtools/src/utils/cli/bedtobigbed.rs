@@ -83,12 +83,21 @@ pub fn bedtobigbed(args: BedToBigBedArgs) -> anyhow::Result<()> {
         .map(|l| {
             let words = l.expect("Split error");
             let mut split = words.split_whitespace();
-            (
-                split.next().expect("Missing chrom").to_owned(),
-                split.next().expect("Missing size").parse::<u32>().unwrap(),
-            )
+            let chrom = split.next().expect("Missing chrom").to_owned();
+            let size = split.next().expect("Missing size");
+            // Positions in bbi files are u32, so larger chromosomes can't be written
+            match size.parse::<u32>() {
+                Ok(size) => Ok((chrom, size)),
+                Err(_) => Err(format!(
+                    "Invalid size for chromosome {}: `{}`. Sizes must be integers of at most {}.",
+                    chrom,
+                    size,
+                    u32::MAX
+                )),
+            }
         })
-        .collect();
+        .collect::<Result<_, String>>()
+        .map_err(anyhow::Error::msg)?;
 
     let mut outb = BigBedWrite::create_file(bigwigpath, chrom_map)
         .with_context(|| format!("Failed to create bigBed file."))?;
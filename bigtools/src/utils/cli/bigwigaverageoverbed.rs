@@ -115,10 +115,10 @@ pub fn bigwigaverageoverbed(
 
             let chrom_bed_file = File::open(bedinpath)?;
             let chrom_bed_file = FileView::new(chrom_bed_file, start, end)?;
-            let mut bed_stream = BedFileStream {
-                bed: StreamingLineReader::new(BufReader::new(chrom_bed_file)),
-                parse: parse_bed,
-            };
+            let mut bed_stream = BedFileStream::new(
+                StreamingLineReader::new(BufReader::new(chrom_bed_file)),
+                parse_bed,
+            );
 
             loop {
                 let (chrom, entry) = match bed_stream.next() {
@@ -85,14 +85,10 @@ pub fn bigwiginfo(args: BigWigInfoArgs) -> Result<(), Box<dyn Error>> {
             }
         }
         println!("basesCovered: {}", num_with_commas(summary.bases_covered));
-        println!("mean: {:.6}", summary.sum / summary.bases_covered as f64);
+        println!("mean: {:.6}", summary.mean());
         println!("min: {:.6}", summary.min_val);
         println!("max: {:.6}", summary.max_val);
-        let var = (summary.sum_squares
-            - (summary.sum * summary.sum) / summary.bases_covered as f64)
-            / (summary.bases_covered as f64 - 1.0);
-        let std = var.sqrt();
-        println!("std: {:.6}", std);
+        println!("std: {:.6}", summary.std_dev());
 
         Ok(())
     }
@@ -63,7 +63,8 @@ pub fn bigwigvaluesoverbed(args: BigWigValuesOverBedArgs) -> Result<(), Box<dyn
         if bigwigpath.starts_with("http") {
             use crate::utils::remote_file::RemoteFile;
             let f = RemoteFile::new(&bigwigpath);
-            let inbigwig = BigWigRead::open(f)?;
+            // Remote reads are slow, so keep blocks shared by nearby regions
+            let inbigwig = BigWigRead::open(f)?.cached();
             write(bedin, inbigwig, out, options)?;
         } else {
             let inbigwig = BigWigRead::open_file(&bigwigpath)?.cached();
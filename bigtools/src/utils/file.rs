@@ -1,3 +1,4 @@
+pub mod bytes_reader;
 pub mod file_view;
 pub mod reopen;
 pub mod streaming_linereader;
@@ -8,6 +9,9 @@ pub mod tempfilebuffer;
 #[cfg(feature = "remote")]
 pub mod remote_file;
 
+#[cfg(feature = "mmap")]
+pub mod mmap_file;
+
 use std::fs::File;
 use std::io::{self, BufRead, Seek};
 
@@ -0,0 +1,97 @@
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::Arc;
+
+use crate::utils::file::reopen::Reopen;
+
+/// A reader over bytes that are already in memory (for example, fetched by
+/// another layer, or for tests). Reopening is cheap, since the bytes are
+/// shared, and only resets the position.
+#[derive(Clone)]
+pub struct BytesReader {
+    bytes: Arc<Vec<u8>>,
+    position: u64,
+}
+
+impl BytesReader {
+    pub fn new(bytes: impl Into<Arc<Vec<u8>>>) -> BytesReader {
+        BytesReader {
+            bytes: bytes.into(),
+            position: 0,
+        }
+    }
+
+    /// The underlying bytes.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl Read for BytesReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let start = (self.position as usize).min(self.bytes.len());
+        let remaining = &self.bytes[start..];
+        let read = buf.len().min(remaining.len());
+        buf[..read].copy_from_slice(&remaining[..read]);
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for BytesReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(s) => (s, 0),
+            SeekFrom::End(s) => (self.bytes.len() as u64, s),
+            SeekFrom::Current(s) => (self.position, s),
+        };
+        self.position = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+impl Reopen for BytesReader {
+    fn reopen(&self) -> io::Result<BytesReader> {
+        Ok(BytesReader {
+            bytes: self.bytes.clone(),
+            position: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BigWigRead;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_bytes_matches_file() -> Result<(), Box<dyn std::error::Error>> {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("resources/test");
+        dir.push("valid.bigWig");
+
+        let mut file = BigWigRead::open_file(&dir)?;
+        let mut bytes = BigWigRead::open(BytesReader::new(std::fs::read(&dir)?))?;
+
+        let file_intervals = file
+            .get_interval("chr17", 0, 83257441)?
+            .collect::<Result<Vec<_>, _>>()?;
+        let bytes_intervals = bytes
+            .get_interval("chr17", 0, 83257441)?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert!(!file_intervals.is_empty());
+        assert_eq!(file_intervals, bytes_intervals);
+
+        let mut reopened = bytes.reopen()?;
+        let reopened_intervals = reopened
+            .get_interval("chr17", 0, 83257441)?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(file_intervals, reopened_intervals);
+        Ok(())
+    }
+}
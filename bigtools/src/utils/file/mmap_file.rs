@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use memmap2::Mmap;
+
+use crate::utils::file::reopen::Reopen;
+
+/// A file that is memory-mapped, rather than read through system calls. This
+/// can be faster for repeated random access of a large local file. Reopening
+/// is cheap, since the mapping is shared.
+///
+/// Safety: the file *must not* be modified (by this or any other process)
+/// while it is mapped. Doing so is undefined behavior.
+pub struct MmapFile {
+    pub path: PathBuf,
+    map: Arc<Mmap>,
+    position: u64,
+}
+
+impl MmapFile {
+    /// Opens and memory-maps the file at `path`. See the type-level docs for
+    /// the assumption that the file is not modified while mapped.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<MmapFile> {
+        let file = File::open(&path)?;
+        // SAFETY: We assume the file is not modified while mapped. This is
+        // documented on the type.
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(MmapFile {
+            path: path.as_ref().to_owned(),
+            map: Arc::new(map),
+            position: 0,
+        })
+    }
+}
+
+impl Read for MmapFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let start = (self.position as usize).min(self.map.len());
+        let remaining = &self.map[start..];
+        let read = buf.len().min(remaining.len());
+        buf[..read].copy_from_slice(&remaining[..read]);
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for MmapFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match pos {
+            SeekFrom::Start(s) => (s, 0),
+            SeekFrom::End(s) => (self.map.len() as u64, s),
+            SeekFrom::Current(s) => (self.position, s),
+        };
+        self.position = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+impl Reopen for MmapFile {
+    fn reopen(&self) -> io::Result<MmapFile> {
+        Ok(MmapFile {
+            path: self.path.clone(),
+            map: self.map.clone(),
+            position: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BigWigRead;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_mmap_matches_file() -> Result<(), Box<dyn std::error::Error>> {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("resources/test");
+        dir.push("valid.bigWig");
+
+        let mut file = BigWigRead::open_file(&dir)?;
+        let mut mmap = BigWigRead::open(MmapFile::open(&dir)?)?;
+
+        let file_intervals = file
+            .get_interval("chr17", 0, 83257441)?
+            .collect::<Result<Vec<_>, _>>()?;
+        let mmap_intervals = mmap
+            .get_interval("chr17", 0, 83257441)?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert!(!file_intervals.is_empty());
+        assert_eq!(file_intervals, mmap_intervals);
+
+        let mut reopened = mmap.reopen()?;
+        let reopened_intervals = reopened
+            .get_interval("chr17", 0, 83257441)?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(file_intervals, reopened_intervals);
+        Ok(())
+    }
+}
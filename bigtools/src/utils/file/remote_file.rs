@@ -8,7 +8,7 @@ use crate::utils::file::reopen::Reopen;
 const READ_SIZE: usize = 10 * 1024; // 10 KB chunks
 
 // Remote file reads are cached to a temporary file. The size of each block
-// (with the exception of the last block) is `READ_SIZE + 1` bytes. The first
+// (with the exception of the last block) is `read_size + 1` bytes. The first
 // byte of a block is `0` if the data hasn't been written yet, or `1` if it
 // has (and has enough data to fill the block). A value of `2` signifies that
 // there wasn't enough data to fill the block, which only should happen for the
@@ -19,24 +19,62 @@ pub struct RemoteFile {
     current_position: u64,
     current: Option<(u64, Cursor<Vec<u8>>)>,
     cache: Option<File>,
+    len: Option<u64>,
+    read_size: usize,
 }
 
 impl RemoteFile {
     pub fn new(url: &str) -> RemoteFile {
+        RemoteFile::with_read_size(url, READ_SIZE)
+    }
+
+    /// Like `new`, but reads the file in blocks of `read_size` bytes, rather
+    /// than the default of 10 KB. Every read from the server is at least one
+    /// block, so larger blocks mean fewer requests (which are often the
+    /// bottleneck for remote files), at the cost of downloading and caching
+    /// more unneeded data around each read. A `read_size` of `0` is treated
+    /// as `1`.
+    pub fn with_read_size(url: &str, read_size: usize) -> RemoteFile {
         RemoteFile {
             url: url.to_string(),
             current_position: 0,
             current: None,
             cache: None,
+            len: None,
+            read_size: read_size.max(1),
         }
     }
+
+    /// Gets the length of the remote file, from the `Content-Length` of a
+    /// `HEAD` request. This is only requested once.
+    fn len(&mut self) -> io::Result<u64> {
+        if let Some(len) = self.len {
+            return Ok(len);
+        }
+        let resp = attohttpc::head(&self.url).send()?;
+        if !resp.is_success() {
+            return Err(io::Error::other(
+                "Unable to connect to server to receive file.".to_string(),
+            ));
+        }
+        let len = resp
+            .headers()
+            .get(attohttpc::header::CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.parse::<u64>().ok())
+            .ok_or_else(|| {
+                io::Error::other("Server did not return the length of the file.".to_string())
+            })?;
+        self.len = Some(len);
+        Ok(len)
+    }
 }
 
 impl RemoteFile {
     fn read_current_block(&mut self, read_size: u64) -> io::Result<u64> {
-        let block = self.current_position / READ_SIZE as u64;
-        let block_start = block * READ_SIZE as u64;
-        let cache_block_start = block * (READ_SIZE as u64 + 1);
+        let block = self.current_position / self.read_size as u64;
+        let block_start = block * self.read_size as u64;
+        let cache_block_start = block * (self.read_size as u64 + 1);
         let cache = match self.cache.as_mut() {
             None => {
                 self.cache = Some(tempfile::tempfile()?);
@@ -49,10 +87,10 @@ impl RemoteFile {
         cache.seek(SeekFrom::Start(cache_block_start))?;
         let status = cache.read_u8().unwrap_or(0);
         if status == 1 {
-            let mut bytes = vec![0u8; READ_SIZE];
+            let mut bytes = vec![0u8; self.read_size];
             cache.read_exact(&mut bytes)?;
             self.current = Some((block_start, Cursor::new(bytes.to_vec())));
-            return Ok(READ_SIZE as u64);
+            return Ok(self.read_size as u64);
         } else if status == 2 {
             let bytes_available = cache.read_u64::<byteorder::BigEndian>()?;
             let mut bytes = vec![0u8; bytes_available as usize];
@@ -63,10 +101,11 @@ impl RemoteFile {
 
         let read_len = {
             let cur_pos = self.current_position;
-            let block = cur_pos / (READ_SIZE as u64);
-            let block_start = block * (READ_SIZE as u64);
-            let blocks_to_read = (cur_pos - block_start + read_size - 1) / (READ_SIZE as u64) + 1;
-            blocks_to_read * (READ_SIZE as u64)
+            let block = cur_pos / (self.read_size as u64);
+            let block_start = block * (self.read_size as u64);
+            let blocks_to_read =
+                (cur_pos - block_start + read_size - 1) / (self.read_size as u64) + 1;
+            blocks_to_read * (self.read_size as u64)
         };
 
         let resp = attohttpc::get(&self.url)
@@ -89,15 +128,15 @@ impl RemoteFile {
         };
         cache.seek(SeekFrom::Start(cache_block_start))?;
         let blocks_to_write = if bytes.len() == read_len as usize {
-            bytes.len() / READ_SIZE
+            bytes.len() / self.read_size
         } else {
-            (bytes.len() + READ_SIZE - 1) / READ_SIZE
+            bytes.len().div_ceil(self.read_size)
         };
         for start in 0..blocks_to_write {
-            let begin = start * READ_SIZE;
-            let end = ((start + 1) * READ_SIZE).min(bytes.len());
+            let begin = start * self.read_size;
+            let end = ((start + 1) * self.read_size).min(bytes.len());
             let block_data = &bytes[begin..end];
-            if block_data.len() == READ_SIZE {
+            if block_data.len() == self.read_size {
                 cache.write_u8(1)?;
             } else {
                 cache.write_u8(2)?;
@@ -125,7 +164,8 @@ impl Read for RemoteFile {
             // 4) Whatever is left in the current memory is leftover from a
             //    a previous read, and it's not enough.
             let reset_cursor = |this: &mut Self| -> io::Result<u64> {
-                let cursor_start = (this.current_position / READ_SIZE as u64) * READ_SIZE as u64;
+                let cursor_start =
+                    (this.current_position / this.read_size as u64) * this.read_size as u64;
                 let in_block = this.current_position - cursor_start;
                 // If we not at the start of the block, then the length that we need
                 // is longer than the length of the buf itself, since we have to
@@ -163,9 +203,10 @@ impl Read for RemoteFile {
             if read == 0 || read == remaining_buf.len() || read == bytes_available as usize {
                 break;
             }
-            let cursor_start = (self.current_position / READ_SIZE as u64) * READ_SIZE as u64;
+            let cursor_start =
+                (self.current_position / self.read_size as u64) * self.read_size as u64;
             let in_block = self.current_position - cursor_start;
-            let remaining_in_block = READ_SIZE - in_block as usize;
+            let remaining_in_block = self.read_size - in_block as usize;
             // If we didn't read everything, we *must* have at least read until
             // the end of the block
             assert!(read >= remaining_in_block);
@@ -177,22 +218,19 @@ impl Read for RemoteFile {
 
 impl Seek for RemoteFile {
     fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
-        self.current_position = match pos {
-            SeekFrom::Start(s) => s,
-            SeekFrom::End(_) => unimplemented!(),
-            SeekFrom::Current(s) => {
-                if s >= 0 {
-                    self.current_position + (s as u64)
-                } else {
-                    if self.current_position < s.checked_neg().unwrap() as u64 {
-                        panic!("Seeked to <0");
-                    }
-                    self.current_position - s.checked_neg().unwrap() as u64
-                }
-            }
+        let (base, offset) = match pos {
+            SeekFrom::Start(s) => (s, 0),
+            SeekFrom::End(s) => (self.len()?, s),
+            SeekFrom::Current(s) => (self.current_position, s),
         };
+        self.current_position = base.checked_add_signed(offset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )
+        })?;
         if let Some((cursor_start, cursor)) = self.current.as_mut() {
-            let cursor_end = *cursor_start + READ_SIZE as u64;
+            let cursor_end = *cursor_start + self.read_size as u64;
             if *cursor_start <= self.current_position && self.current_position < cursor_end {
                 let new_position = self.current_position - *cursor_start;
                 cursor.set_position(new_position);
@@ -211,6 +249,8 @@ impl Clone for RemoteFile {
             current_position: 0,
             current: None,
             cache: None,
+            len: self.len,
+            read_size: self.read_size,
         }
     }
 }
@@ -222,6 +262,8 @@ impl Reopen for RemoteFile {
             current_position: 0,
             current: None,
             cache: None,
+            len: self.len,
+            read_size: self.read_size,
         })
     }
 }
@@ -231,6 +273,100 @@ mod tests {
     use super::*;
     use crate::bbi::{BigBedRead, BigWigRead};
 
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    /// Serves `data` over HTTP on a local port, answering `HEAD` and (ranged)
+    /// `GET` requests, and returns the url. If `content_length` is false, the
+    /// `HEAD` response has no `Content-Length`. Each response closes its
+    /// connection.
+    fn serve(data: Vec<u8>, content_length: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/file", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(bytes) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        let (start, end) = bytes.split_once('-').unwrap();
+                        range = Some((
+                            start.parse::<usize>().unwrap(),
+                            end.parse::<usize>().unwrap(),
+                        ));
+                    }
+                }
+                let response = if request.starts_with("HEAD") {
+                    let mut head = "HTTP/1.1 200 OK\r\nConnection: close\r\n".to_string();
+                    if content_length {
+                        head += &format!("Content-Length: {}\r\n", data.len());
+                    }
+                    head += "\r\n";
+                    head.into_bytes()
+                } else {
+                    let (start, end) = range.unwrap_or((0, data.len().saturating_sub(1)));
+                    let body = &data[start.min(data.len())..(end + 1).min(data.len())];
+                    let mut response = format!(
+                        "HTTP/1.1 206 Partial Content\r\nConnection: close\r\nContent-Length: {}\r\n\r\n",
+                        body.len()
+                    )
+                    .into_bytes();
+                    response.extend_from_slice(body);
+                    response
+                };
+                let _ = stream.write_all(&response);
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_seek_from_end() -> io::Result<()> {
+        let data: Vec<u8> = (0..100u8).collect();
+        let mut f = RemoteFile::with_read_size(&serve(data, true), 16);
+        assert_eq!(f.seek(SeekFrom::End(-10))?, 90);
+        let mut buf = [0u8; 10];
+        f.read_exact(&mut buf)?;
+        assert_eq!(buf.to_vec(), (90..100u8).collect::<Vec<_>>());
+        assert_eq!(f.seek(SeekFrom::End(0))?, 100);
+        assert_eq!(f.seek(SeekFrom::End(5))?, 105);
+        Ok(())
+    }
+
+    #[test]
+    fn test_seek_negative() -> io::Result<()> {
+        let mut f = RemoteFile::with_read_size(&serve((0..100u8).collect(), true), 16);
+        let err = f.seek(SeekFrom::End(-101)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // Relative seeks don't need the length of the file
+        let mut f = RemoteFile::new("http://127.0.0.1:1/unused");
+        assert_eq!(f.seek(SeekFrom::Start(10))?, 10);
+        let err = f.seek(SeekFrom::Current(-11)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        // A failed seek doesn't move the position
+        assert_eq!(f.seek(SeekFrom::Current(-10))?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_seek_from_end_unknown_length() {
+        let mut f = RemoteFile::new(&serve((0..100u8).collect(), false));
+        let err = f.seek(SeekFrom::End(0)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    }
+
     #[ignore]
     #[test]
     fn test_remote() {
@@ -265,6 +401,42 @@ mod tests {
         let _: Vec<_> = interval.unwrap().collect();
     }
 
+    #[ignore]
+    #[test]
+    fn test_remote_read_size() {
+        let url = "https://encode-public.s3.amazonaws.com/2020/01/17/7d2573b1-86f4-4592-a68a-ac3d5d0372d6/ENCFF592UJG.bigBed";
+        let mut intervals = vec![];
+        for read_size in [READ_SIZE, 1024 * 1024] {
+            let f = RemoteFile::with_read_size(url, read_size);
+            let mut remote = BigBedRead::open(f).unwrap();
+            let remote_intervals: Vec<_> = remote
+                .get_interval("chr10", 100000000, 100010000)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            intervals.push(remote_intervals);
+        }
+        assert_eq!(intervals[0].len(), 5);
+        assert_eq!(intervals[0], intervals[1]);
+    }
+
+    #[test]
+    fn test_read_size() -> io::Result<()> {
+        let data: Vec<u8> = (0..=255u8).cycle().take(5000).collect();
+        let url = serve(data.clone(), true);
+        for read_size in [0, 1, 7, 1024, READ_SIZE] {
+            let mut f = RemoteFile::with_read_size(&url, read_size);
+            // Reads spanning several blocks, and re-reads of cached blocks
+            for (start, len) in [(0, 100), (1000, 2500), (4990, 10), (50, 2000)] {
+                f.seek(SeekFrom::Start(start))?;
+                let mut buf = vec![0u8; len];
+                f.read_exact(&mut buf)?;
+                assert_eq!(buf, data[start as usize..start as usize + len]);
+            }
+        }
+        Ok(())
+    }
+
     #[ignore]
     #[test]
     fn test_remote4() {
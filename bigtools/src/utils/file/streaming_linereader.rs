@@ -3,6 +3,7 @@ use std::io::{self, BufRead};
 #[derive(Debug)]
 pub struct StreamingLineReader<B> {
     current_line: String,
+    line_number: usize,
     buf_read: B,
 }
 
@@ -10,15 +11,33 @@ impl<B: BufRead> StreamingLineReader<B> {
     pub fn new(bf: B) -> StreamingLineReader<B> {
         StreamingLineReader {
             current_line: String::new(),
+            line_number: 0,
             buf_read: bf,
         }
     }
 
+    /// The 1-based line number of the last line read, or `0` if no lines
+    /// have been read. This can be used to point to the line that an error
+    /// occurred on.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// The last line read, with trailing whitespace removed.
+    pub(crate) fn current_line(&self) -> &str {
+        self.current_line.trim_end()
+    }
+
+    /// Reads the next line, with trailing whitespace removed. This includes
+    /// the `\r` of CRLF line endings.
     pub fn read(&mut self) -> Option<io::Result<&'_ str>> {
         self.current_line.clear();
         match self.buf_read.read_line(&mut self.current_line) {
             Ok(size) if size == 0 => None,
-            Ok(_) => Some(Ok(self.current_line.trim_end())),
+            Ok(_) => {
+                self.line_number += 1;
+                Some(Ok(self.current_line.trim_end()))
+            }
             Err(e) => Some(Err(e)),
         }
     }
@@ -39,13 +58,34 @@ mod tests {
         let f = File::open(dir)?;
         let bf = BufReader::new(f);
         let mut slr = StreamingLineReader::new(bf);
+        assert_eq!(slr.line_number(), 0);
         assert_eq!("chr17\t1\t100\t0.5", slr.read().unwrap().unwrap());
+        assert_eq!(slr.line_number(), 1);
         assert_eq!("chr17\t101\t200\t0.5", slr.read().unwrap().unwrap());
+        assert_eq!(slr.line_number(), 2);
         assert_eq!("chr17\t201\t300\t0.5", slr.read().unwrap().unwrap());
         assert_eq!("chr18\t1\t100\t0.5", slr.read().unwrap().unwrap());
         assert_eq!("chr18\t101\t200\t0.5", slr.read().unwrap().unwrap());
         assert_eq!("chr19\t1\t100\t0.5", slr.read().unwrap().unwrap());
         assert!(slr.read().is_none());
+        assert_eq!(slr.line_number(), 6);
         Ok(())
     }
+    #[test]
+    fn test_no_trailing_newline() {
+        let data = "chr1\t1\t100\t0.5\nchr1\t101\t200\t1.5";
+        let mut slr = StreamingLineReader::new(data.as_bytes());
+        assert_eq!("chr1\t1\t100\t0.5", slr.read().unwrap().unwrap());
+        assert_eq!("chr1\t101\t200\t1.5", slr.read().unwrap().unwrap());
+        assert_eq!(slr.line_number(), 2);
+        assert!(slr.read().is_none());
+        assert_eq!(slr.line_number(), 2);
+
+        // The same for CRLF line endings
+        let data = "chr1\t1\t100\t0.5\r\nchr1\t101\t200\t1.5";
+        let mut slr = StreamingLineReader::new(data.as_bytes());
+        assert_eq!("chr1\t1\t100\t0.5", slr.read().unwrap().unwrap());
+        assert_eq!("chr1\t101\t200\t1.5", slr.read().unwrap().unwrap());
+        assert!(slr.read().is_none());
+    }
 }
@@ -4,9 +4,21 @@ use std::collections::HashMap;
 pub struct IdMap {
     map: HashMap<String, u32>,
     next_id: u32,
+    // Whether the ids were pre-assigned, in which case no new ids are made
+    fixed: bool,
 }
 
 impl IdMap {
+    /// Creates an `IdMap` with pre-assigned ids. No new ids are assigned, so
+    /// `try_get_id` returns `None` for any key not in `map`.
+    pub fn with_ids(map: HashMap<String, u32>) -> Self {
+        IdMap {
+            map,
+            next_id: 0,
+            fixed: true,
+        }
+    }
+
     pub fn get_map(self) -> HashMap<String, u32> {
         self.map
     }
@@ -14,14 +26,27 @@ impl IdMap {
     /// If the key already exists in the map, this will simply return the id for it.
     /// Otherwise, it locks a mutex and returns a new id.
     /// This means that in the case of missing keys, there are two map hits.
+    ///
+    /// Panics if the ids were pre-assigned and the key doesn't have one. Use
+    /// `try_get_id` to handle that case.
     pub fn get_id(&mut self, key: &str) -> u32 {
+        self.try_get_id(key)
+            .unwrap_or_else(|| panic!("No pre-assigned id for key: {}", key))
+    }
+
+    /// Like `get_id`, but returns `None` if the ids were pre-assigned (see
+    /// `with_ids`) and the key doesn't have one.
+    pub fn try_get_id(&mut self, key: &str) -> Option<u32> {
         if let Some(id) = self.map.get(key) {
-            return *id;
+            return Some(*id);
+        }
+        if self.fixed {
+            return None;
         }
         let next_id = self.next_id;
         self.next_id += 1;
         let chrom_id: u32 = *self.map.entry(key.to_string()).or_insert(next_id);
-        chrom_id
+        Some(chrom_id)
     }
 }
 
@@ -42,4 +67,14 @@ mod tests {
         let map = idmap.get_map();
         assert!(map.len() == 2);
     }
+
+    #[test]
+    fn test_with_ids() {
+        let ids = HashMap::from([("chr1".to_string(), 5), ("chr2".to_string(), 2)]);
+        let mut idmap = IdMap::with_ids(ids.clone());
+        assert_eq!(idmap.get_id("chr1"), 5);
+        assert_eq!(idmap.try_get_id("chr2"), Some(2));
+        assert_eq!(idmap.try_get_id("chr3"), None);
+        assert_eq!(idmap.get_map(), ids);
+    }
 }
@@ -0,0 +1,35 @@
+use std::error::Error;
+
+#[test]
+fn bigbedread_typed_test() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigBedRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut bigbed = dir.clone();
+    bigbed.push("bigGenePred.bb");
+
+    let mut bbread = BigBedRead::open_file(bigbed)?;
+    let num_fields = bbread.autosql_fields()?.len() - 3;
+    let entries: Vec<_> = bbread
+        .get_interval("chr1", 0, 30000)?
+        .collect::<Result<_, _>>()?;
+    let typed: Vec<_> = bbread
+        .get_interval_typed("chr1", 0, 30000)?
+        .collect::<Result<_, _>>()?;
+    assert!(!typed.is_empty());
+    assert_eq!(entries.len(), typed.len());
+    for (entry, typed) in entries.iter().zip(typed.iter()) {
+        assert_eq!(entry.start, typed.start);
+        assert_eq!(entry.end, typed.end);
+        assert_eq!(typed.fields.len(), num_fields);
+        assert_eq!(entry.rest, typed.rest());
+    }
+    assert_eq!(typed[0].fields[0], "ENST00000456328.2");
+    assert_eq!(typed[0].fields[9], "DDX11L1");
+
+    Ok(())
+}
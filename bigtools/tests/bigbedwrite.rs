@@ -64,6 +64,10 @@ fn bigbedwrite_test() -> Result<(), Box<dyn Error>> {
         "table bed\n\"Browser Extensible Data\"\n(\n    string chrom;       \"Reference sequence chromosome or scaffold\"\n    uint   chromStart;  \"Start position in chromosome\"\n    uint   chromEnd;    \"End position in chromosome\"\n   string name;        \"Name of item.\"\n   uint score;          \"Score (0-1000)\"\n)",
     );
     assert_eq!(bwread.info().header.field_count, 5);
+    let fields = bwread.autosql_fields()?;
+    let names: Vec<_> = fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["chrom", "chromStart", "chromEnd", "name", "score"]);
+    assert_eq!(fields[4].field_type.to_string(), "uint");
 
     let mut intervals = bwread.get_interval("chr17", 0, 83257441)?;
     let first_interval = intervals.next().unwrap().unwrap();
@@ -73,3 +77,269 @@ fn bigbedwrite_test() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn bigbedwrite_gzip_test() -> Result<(), Box<dyn Error>> {
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::path::PathBuf;
+
+    use tempfile;
+
+    use bigtools::{BigBedRead, BigBedWrite};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut bed = dir.clone();
+    bed.push("small.bed");
+    let mut gzbed = dir.clone();
+    gzbed.push("small.bed.gz");
+
+    let runtime = runtime::Builder::new_multi_thread()
+        .worker_threads(6)
+        .build()
+        .expect("Unable to create runtime.");
+
+    let tempfile = tempfile::NamedTempFile::new()?;
+
+    let mut chrom_map = HashMap::new();
+    chrom_map.insert("chr17".to_string(), 83257441);
+    chrom_map.insert("chr18".to_string(), 80373285);
+    chrom_map.insert("chr19".to_string(), 58617616);
+
+    let outb = BigBedWrite::create_file(tempfile.path(), chrom_map).unwrap();
+    let infile = File::open(gzbed)?;
+    let data = BedParserStreamingIterator::from_bed_gzip_file(infile, false);
+    outb.write(data, runtime).unwrap();
+
+    let mut bbread = BigBedRead::open_file(tempfile.path()).unwrap();
+    let mut expected = BedFileStream::from_bed_file(File::open(bed)?);
+    for chrom in ["chr17", "chr18", "chr19"] {
+        for entry in bbread.get_interval(chrom, 0, 1000)? {
+            let entry = entry?;
+            let (expected_chrom, expected_entry) = expected.next().unwrap()?;
+            assert_eq!(chrom, expected_chrom);
+            assert_eq!(entry, expected_entry);
+        }
+    }
+    assert!(expected.next().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn bigbedwrite_unsorted_starts() {
+    use std::collections::HashMap;
+
+    use bigtools::{BBIProcessError, BedEntry, BigBedWrite};
+
+    let vals = vec![("chr1", 100, 200), ("chr1", 300, 400), ("chr1", 250, 260)];
+    let iter = vals.into_iter().map(|(chrom, start, end)| {
+        (
+            chrom,
+            BedEntry {
+                start,
+                end,
+                rest: String::new(),
+            },
+        )
+    });
+    let vals_iter = BedParserStreamingIterator::wrap_infallible_iter(iter, false);
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 10_000)]);
+
+    let runtime = runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to create runtime.");
+
+    let tempfile = tempfile::NamedTempFile::new().unwrap();
+    let outb = BigBedWrite::create_file(tempfile.path(), chrom_map).unwrap();
+    match outb.write(vals_iter, runtime) {
+        Err(BBIProcessError::InvalidInput(e)) => {
+            assert!(e.contains("not sorted on chromosome chr1"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for unsorted input."),
+    }
+}
+
+#[test]
+fn bigbedwrite_position_overflow() {
+    use std::collections::HashMap;
+
+    use bigtools::{BBIProcessError, BedEntry, BigBedWrite};
+
+    let vals = vec![("chr1", 10, 20), ("chr1", u32::MAX - 10, u32::MAX)];
+    let iter = vals.into_iter().map(|(chrom, start, end)| {
+        (
+            chrom,
+            BedEntry {
+                start,
+                end,
+                rest: String::new(),
+            },
+        )
+    });
+    let vals_iter = BedParserStreamingIterator::wrap_infallible_iter(iter, false);
+
+    let chrom_map = HashMap::from([("chr1".to_string(), u32::MAX)]);
+
+    let runtime = runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to create runtime.");
+
+    // A zoom record of the last entry would end past `u32::MAX`
+    let tempfile = tempfile::NamedTempFile::new().unwrap();
+    let outb = BigBedWrite::create_file(tempfile.path(), chrom_map).unwrap();
+    match outb.write(vals_iter, runtime) {
+        Err(BBIProcessError::InvalidInput(e)) => {
+            assert!(e.contains("maximum position"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for an overflowing zoom."),
+    }
+}
+
+#[test]
+fn bigbedwrite_chrom_ids() -> Result<(), Box<dyn Error>> {
+    use std::collections::HashMap;
+
+    use bigtools::{BBIProcessError, BedEntry, BigBedRead, BigBedWrite};
+
+    // Small blocks and index nodes, so the index has several levels
+    let entries_per_chrom = 1_000;
+    let vals = || {
+        let iter = ["chr1", "chr2"].into_iter().flat_map(move |chrom| {
+            (0..entries_per_chrom).map(move |i| {
+                (
+                    chrom,
+                    BedEntry {
+                        start: i * 10,
+                        end: i * 10 + 5,
+                        rest: String::new(),
+                    },
+                )
+            })
+        });
+        BedParserStreamingIterator::wrap_infallible_iter(iter, false)
+    };
+    let chrom_map = HashMap::from([
+        ("chr1".to_string(), 100_000),
+        ("chr2".to_string(), 100_000),
+        ("chr3".to_string(), 100_000),
+    ]);
+    let runtime = || {
+        runtime::Builder::new_current_thread()
+            .build()
+            .expect("Unable to create runtime.")
+    };
+    let write = |chrom_ids: Vec<(&str, u32)>| {
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+        let mut outb = BigBedWrite::create_file(tempfile.path(), chrom_map.clone()).unwrap();
+        outb.options.items_per_slot = 4;
+        outb.options.block_size = 4;
+        outb.options.chrom_ids = Some(
+            chrom_ids
+                .into_iter()
+                .map(|(chrom, id)| (chrom.to_string(), id))
+                .collect(),
+        );
+        outb.write(vals(), runtime()).map(|_| tempfile)
+    };
+
+    // Chromosomes are stored in id order. Ids don't need to be contiguous,
+    // and a chromosome without data can have any id.
+    let tempfile = write(vec![("chr3", 0), ("chr1", 5), ("chr2", 9)])?;
+    let mut bbread = BigBedRead::open_file(tempfile.path())?;
+    let names: Vec<_> = bbread.chroms().iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["chr3", "chr1", "chr2"]);
+    for chrom in ["chr1", "chr2"] {
+        let starts: Vec<_> = bbread
+            .get_interval(chrom, 0, 100_000)?
+            .map(|entry| entry.map(|entry| entry.start))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(
+            starts,
+            (0..entries_per_chrom).map(|i| i * 10).collect::<Vec<_>>()
+        );
+    }
+
+    // Ids that decrease in the order chromosomes are written would make the
+    // index bounds invalid
+    match write(vec![("chr3", 0), ("chr2", 1), ("chr1", 2)]) {
+        Err(BBIProcessError::InvalidInput(e)) => {
+            assert!(e.contains("increasing order"), "{}", e);
+            assert!(e.contains("chr2"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for decreasing ids."),
+    }
+
+    // A chromosome in the data without a pre-assigned id
+    match write(vec![("chr1", 0)]) {
+        Err(BBIProcessError::InvalidChromosome(e)) => assert!(e.contains("chr2"), "{}", e),
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for a chromosome without an id."),
+    }
+
+    // Duplicate ids
+    assert!(matches!(
+        write(vec![("chr1", 0), ("chr2", 0)]),
+        Err(BBIProcessError::InvalidInput(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn bigbedwrite_zero_width_and_inverted() -> Result<(), Box<dyn Error>> {
+    use std::collections::HashMap;
+
+    use bigtools::{BBIProcessError, BedEntry, BigBedRead, BigBedWrite};
+
+    let write = |vals: Vec<(u32, u32)>, drop_zero_width: bool| {
+        let iter = vals.into_iter().map(|(start, end)| {
+            (
+                "chr1",
+                BedEntry {
+                    start,
+                    end,
+                    rest: String::new(),
+                },
+            )
+        });
+        let vals_iter = BedParserStreamingIterator::wrap_infallible_iter(iter, false);
+        let chrom_map = HashMap::from([("chr1".to_string(), 10_000)]);
+        let runtime = runtime::Builder::new_current_thread()
+            .build()
+            .expect("Unable to create runtime.");
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+        let mut outb = BigBedWrite::create_file(tempfile.path(), chrom_map).unwrap();
+        outb.options.drop_zero_width = drop_zero_width;
+        outb.write(vals_iter, runtime).map(|_| tempfile)
+    };
+    let read_all = |path: &std::path::Path| -> Result<Vec<_>, Box<dyn Error>> {
+        let mut bbread = BigBedRead::open_file(path)?;
+        Ok(bbread
+            .get_interval("chr1", 0, 10_000)?
+            .map(|e| e.map(|e| (e.start, e.end)))
+            .collect::<Result<_, _>>()?)
+    };
+
+    let vals = vec![(100, 200), (250, 250), (300, 400), (450, 450)];
+    let tempfile = write(vals.clone(), false)?;
+    assert_eq!(read_all(tempfile.path())?, vals);
+    let tempfile = write(vals, true)?;
+    assert_eq!(read_all(tempfile.path())?, [(100, 200), (300, 400)]);
+
+    match write(vec![(100, 200), (300, 250)], true) {
+        Err(BBIProcessError::InvalidInput(e)) => {
+            assert!(e.contains("(300)") && e.contains("(250)"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for an inverted entry."),
+    }
+
+    Ok(())
+}
@@ -1,5 +1,12 @@
 use std::error::Error;
 
+/// The path of `resources/test/valid.bigWig`.
+fn valid_bigwig() -> std::path::PathBuf {
+    let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources/test/valid.bigWig");
+    path
+}
+
 #[test]
 fn test_valid_read() -> Result<(), Box<dyn Error>> {
     use std::path::PathBuf;
@@ -55,6 +62,56 @@ fn test_values() -> Result<(), Box<dyn Error>> {
     let vals = bwread.values("chr17", 0, 59899)?;
     assert_eq!(vals.len(), 59899);
     assert_eq!(vals[59898], 0.06792);
+
+    let vals_f64 = bwread.values_f64("chr17", 0, 59899)?;
+    assert_eq!(vals_f64.len(), 59899);
+    assert!(vals_f64[0].is_nan());
+    assert_eq!(vals_f64[59898], 0.06792f32 as f64);
+    Ok(())
+}
+
+#[test]
+fn test_values_smoothed() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let (start, end) = (59890, 59990);
+    let vals = bwread.values("chr17", start, end)?;
+    let smoothed = bwread.values_smoothed("chr17", start, end, 1)?;
+    assert_eq!(
+        vals.iter().map(|v| v.to_bits()).collect::<Vec<_>>(),
+        smoothed.iter().map(|v| v.to_bits()).collect::<Vec<_>>()
+    );
+
+    let smoothed = bwread.values_smoothed("chr17", start, end, 5)?;
+    assert_eq!(smoothed.len(), vals.len());
+    for (i, smoothed) in smoothed.iter().enumerate() {
+        let window = &vals[i.saturating_sub(2)..(i + 3).min(vals.len())];
+        let covered: Vec<f64> = window
+            .iter()
+            .filter(|v| !v.is_nan())
+            .map(|v| *v as f64)
+            .collect();
+        if covered.is_empty() {
+            assert!(smoothed.is_nan());
+        } else {
+            let mean = covered.iter().sum::<f64>() / covered.len() as f64;
+            assert!((*smoothed as f64 - mean).abs() < 1e-4);
+        }
+    }
+    // The first data is at 59898, so only windows reaching it have a value
+    assert!(smoothed[5].is_nan());
+    assert!(!smoothed[6].is_nan());
+
     Ok(())
 }
 
@@ -71,9 +128,1616 @@ fn test_reduction_values() -> Result<(), Box<dyn Error>> {
     valid_bigwig.push("valid.bigWig");
 
     let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+    let zoom_levels = bwread.zoom_levels();
+    assert!(zoom_levels.contains(&10240));
+    assert!(zoom_levels.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(bwread.closest_zoom_level(10240), Some(10240));
+    assert_eq!(bwread.closest_zoom_level(10241), Some(10240));
+    assert_eq!(bwread.closest_zoom_level(0), None);
+
     let interval = bwread.get_zoom_interval("chr17", 0, 36996442, 10240);
     let x: Vec<_> = interval.unwrap().collect();
 
     assert_eq!(x.len(), 16);
     Ok(())
 }
+
+#[test]
+fn test_stats() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::{BigWigRead, StatKind};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    // Small enough that no zoom level is used
+    let vals = bwread.values("chr17", 59890, 59910)?;
+    let covered: Vec<f64> = vals
+        .iter()
+        .filter(|v| !v.is_nan())
+        .map(|v| *v as f64)
+        .collect();
+    let mean = covered.iter().sum::<f64>() / covered.len() as f64;
+    let max = covered.iter().cloned().fold(f64::MIN, f64::max);
+    let coverage = covered.len() as f64 / vals.len() as f64;
+    let stat_mean = bwread.stats("chr17", 59890, 59910, StatKind::Mean)?;
+    assert!((stat_mean - mean).abs() < 1e-6);
+    assert_eq!(bwread.stats("chr17", 59890, 59910, StatKind::Max)?, max);
+    assert_eq!(
+        bwread.stats("chr17", 59890, 59910, StatKind::Coverage)?,
+        coverage
+    );
+
+    // Over the whole chromosome, zoom levels are used
+    let summary = bwread.get_summary()?;
+    let length = bwread.chroms()[0].length;
+    let coverage = bwread.stats("chr17", 0, length, StatKind::Coverage)?;
+    let expected = summary.bases_covered as f64 / length as f64;
+    assert!((coverage - expected).abs() / expected < 0.01);
+    let max = bwread.stats("chr17", 0, length, StatKind::Max)?;
+    assert_eq!(max, summary.max_val);
+
+    // Empty regions
+    assert!(bwread.stats("chr17", 0, 100, StatKind::Mean)?.is_nan());
+    assert_eq!(bwread.stats("chr17", 0, 100, StatKind::Coverage)?, 0.0);
+
+    Ok(())
+}
+
+#[test]
+fn test_summary_bins() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::{BigWigRead, StatKind};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    // 2000 bases into 7 bins: the first 5 bins are 286 bases, the last 2 are 285
+    let bins = bwread.get_summary_bins("chr17", 59000, 61000, 7, StatKind::Mean)?;
+    assert_eq!(bins.len(), 7);
+    // The first data is at 59898
+    assert!(bins[0].is_nan());
+    assert!(bins[1].is_nan());
+    assert!(bins[2].is_nan());
+    let mut bin_start = 59000;
+    for (i, bin) in bins.iter().enumerate() {
+        let bin_end = bin_start + if i < 5 { 286 } else { 285 };
+        let expected = bwread.stats("chr17", bin_start, bin_end, StatKind::Mean)?;
+        if expected.is_nan() {
+            assert!(bin.is_nan());
+        } else {
+            assert!((bin - expected).abs() < 1e-6);
+        }
+        bin_start = bin_end;
+    }
+    assert_eq!(bin_start, 61000);
+
+    let bins = bwread.get_summary_bins("chr17", 59000, 61000, 7, StatKind::Coverage)?;
+    assert!(bins[0].is_nan());
+    assert!(bins[6] > 0.0 && bins[6] <= 1.0);
+
+    Ok(())
+}
+
+#[test]
+fn test_values_with_fill() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let vals = bwread.values_with_fill("chr17", 59890, 59910, 0.0)?;
+    let nan_vals = bwread.values("chr17", 59890, 59910)?;
+    assert_eq!(vals[0], 0.0);
+    assert_eq!(vals[8], 0.06792);
+    for (val, nan_val) in vals.iter().zip(nan_vals.iter()) {
+        if nan_val.is_nan() {
+            assert_eq!(*val, 0.0);
+        } else {
+            assert_eq!(val, nan_val);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_interval_rev() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let mut forward = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<Vec<_>, _>>()?;
+    let reverse = bwread
+        .get_interval_rev("chr17", 0, 83257441)?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert!(reverse.len() > 1);
+    assert!(reverse.windows(2).all(|w| w[0].start > w[1].start));
+    forward.reverse();
+    assert_eq!(forward, reverse);
+    Ok(())
+}
+
+#[test]
+fn test_interval_parallel() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let serial = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<Vec<_>, _>>()?;
+    for nthreads in [1, 3, 8] {
+        let parallel = bwread.get_interval_parallel("chr17", 0, 83257441, nthreads)?;
+        assert_eq!(serial, parallel);
+    }
+    assert!(bwread.get_interval_parallel("chr17", 0, 100, 4)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_coalesced_reads() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let coalesced = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<Vec<_>, _>>()?;
+    let coalesced_values = bwread.values("chr17", 59000, 1000000)?;
+    bwread.options.max_coalesce_gap = 0;
+    let uncoalesced = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<Vec<_>, _>>()?;
+    let uncoalesced_values = bwread.values("chr17", 59000, 1000000)?;
+    assert_eq!(coalesced, uncoalesced);
+    assert!(coalesced_values
+        .iter()
+        .zip(uncoalesced_values.iter())
+        .all(|(a, b)| a == b || (a.is_nan() && b.is_nan())));
+
+    // Every run is a single block
+    bwread.options.max_coalesce_gap = u64::MAX;
+    bwread.options.max_coalesce_bytes = 1;
+    let single = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(coalesced, single);
+    Ok(())
+}
+
+#[test]
+fn test_summary_by_chrom() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let summary = bwread.get_summary()?;
+    let by_chrom = bwread.get_summary_by_chrom()?;
+    assert_eq!(by_chrom.len(), 1);
+    let chr17 = &by_chrom["chr17"];
+    // The other fields come from zoom records, but `total_items` is exact
+    assert!(!bwread.info().zoom_headers.is_empty());
+    assert_eq!(
+        chr17.total_items,
+        bwread.get_interval_all("chr17")?.count() as u64
+    );
+    assert!(chr17.total_items > 0);
+    assert_eq!(chr17.bases_covered, summary.bases_covered);
+    assert_eq!(chr17.min_val, summary.min_val);
+    assert_eq!(chr17.max_val, summary.max_val);
+    // Zoom records store sums as f32
+    assert!((chr17.sum - summary.sum).abs() / summary.sum < 1e-4);
+    Ok(())
+}
+
+#[test]
+fn test_interval_all() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::{BBIReadError, BigWigRead};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let all = bwread
+        .get_interval_all("chr17")?
+        .collect::<Result<Vec<_>, _>>()?;
+    let interval = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(all, interval);
+
+    match bwread.get_interval_all("chr1") {
+        Err(BBIReadError::InvalidChromosome(chrom)) => assert_eq!(chrom, "chr1"),
+        _ => panic!("Expected an invalid chromosome error."),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_invalid_interval() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::{BBIReadError, BigWigRead, StatKind, ZoomIntervalError};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    assert!(matches!(
+        bwread.values("chr17", 100, 50),
+        Err(BBIReadError::InvalidInterval {
+            start: 100,
+            end: 50
+        })
+    ));
+    assert!(matches!(
+        bwread.get_interval("chr17", 100, 50),
+        Err(BBIReadError::InvalidInterval { .. })
+    ));
+    assert!(matches!(
+        bwread.get_zoom_interval("chr17", 100, 50, 10240),
+        Err(ZoomIntervalError::BBIReadError(
+            BBIReadError::InvalidInterval { .. }
+        ))
+    ));
+    assert!(matches!(
+        bwread.stats("chr17", 100, 50, StatKind::Mean),
+        Err(BBIReadError::InvalidInterval { .. })
+    ));
+    assert!(matches!(
+        bwread.values("chr1", 0, 50),
+        Err(BBIReadError::InvalidChromosome(_))
+    ));
+
+    // An empty interval is valid
+    assert!(bwread.values("chr17", 100, 100)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_interval_merged() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::{BigWigRead, MergedValueIter, Value};
+
+    let v = |start, end, value| Ok(Value { start, end, value });
+    let merged: Vec<_> = MergedValueIter::new(
+        vec![
+            v(0, 10, 1.0),
+            v(10, 20, 1.0),
+            v(25, 30, 1.0),
+            v(30, 40, f32::NAN),
+            v(40, 50, f32::NAN),
+            v(50, 60, 2.0),
+        ]
+        .into_iter(),
+    )
+    .collect::<Result<_, _>>()?;
+    assert_eq!(merged.len(), 4);
+    assert_eq!((merged[0].start, merged[0].end), (0, 20));
+    assert_eq!((merged[1].start, merged[1].end), (25, 30));
+    assert_eq!((merged[2].start, merged[2].end), (30, 50));
+    assert!(merged[2].value.is_nan());
+    assert_eq!(
+        (merged[3].start, merged[3].end, merged[3].value),
+        (50, 60, 2.0)
+    );
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let intervals: Vec<_> = bwread
+        .get_interval("chr17", 0, 59899)?
+        .collect::<Result<_, _>>()?;
+    let merged: Vec<_> = bwread
+        .get_interval_merged("chr17", 0, 59899)?
+        .collect::<Result<_, _>>()?;
+    assert!(merged.len() <= intervals.len());
+    let covered = |vals: &[Value]| vals.iter().map(|v| v.end - v.start).sum::<u32>();
+    assert_eq!(covered(&intervals), covered(&merged));
+    for pair in merged.windows(2) {
+        assert!(!(pair[0].end == pair[1].start && pair[0].value == pair[1].value));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_values_binned() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::{BigWigRead, ZoomIntervalError};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    // No zoom level divides an odd bin size, so full resolution data is used
+    let start = 59_000;
+    let end = 70_005;
+    let bin_size = 1001;
+    let binned = bwread.values_binned("chr17", start, end, bin_size)?;
+    assert_eq!(binned.len(), 11);
+    let values = bwread.values("chr17", start, end)?;
+    for (i, bin) in values.chunks(bin_size as usize).enumerate() {
+        let covered: Vec<f32> = bin.iter().copied().filter(|v| !v.is_nan()).collect();
+        if covered.is_empty() {
+            assert!(binned[i].is_nan());
+        } else {
+            let mean = covered.iter().map(|v| *v as f64).sum::<f64>() / covered.len() as f64;
+            assert!(
+                (binned[i] as f64 - mean).abs() < 1e-3,
+                "{} {}",
+                binned[i],
+                mean
+            );
+        }
+    }
+
+    // Bins that are a multiple of a reduction level use that zoom level
+    let reduction_level = bwread.zoom_levels()[0];
+    let bin_size = reduction_level * 4;
+    let end = 83_257_441;
+    let zoomed = bwread.values_binned("chr17", 0, end, bin_size)?;
+    assert_eq!(zoomed.len(), end.div_ceil(bin_size) as usize);
+    assert!(zoomed.iter().any(|v| !v.is_nan()));
+
+    assert!(bwread.values_binned("chr17", 0, 100, 0)?.is_empty());
+
+    // Forcing the zoom level that would be chosen gives the same values
+    let chosen_level = bwread
+        .zoom_levels()
+        .into_iter()
+        .rev()
+        .find(|level| bin_size % level == 0)
+        .unwrap();
+    let forced = bwread.values_binned_at_level("chr17", 0, end, bin_size, chosen_level)?;
+    assert_eq!(forced.len(), zoomed.len());
+    assert!(forced
+        .iter()
+        .zip(&zoomed)
+        .all(|(a, b)| a == b || (a.is_nan() && b.is_nan())));
+
+    // A coarser zoom level can be forced, even if it doesn't divide bin_size
+    let coarse_level = *bwread.zoom_levels().last().unwrap();
+    let coarse = bwread.values_binned_at_level("chr17", 0, end, 1_000_000, coarse_level)?;
+    assert_eq!(coarse.len(), end.div_ceil(1_000_000) as usize);
+    assert!(coarse.iter().any(|v| !v.is_nan()));
+
+    let err = bwread
+        .values_binned_at_level("chr17", 0, end, bin_size, reduction_level + 1)
+        .unwrap_err();
+    assert!(matches!(err, ZoomIntervalError::ReductionLevelNotFound));
+
+    Ok(())
+}
+
+#[test]
+fn test_block_cache() -> Result<(), Box<dyn Error>> {
+    use std::cell::Cell;
+    use std::fs::File;
+    use std::io::{self, Read, Seek, SeekFrom};
+    use std::path::PathBuf;
+    use std::rc::Rc;
+
+    use bigtools::BigWigRead;
+
+    // Counts the number of reads from the underlying file
+    struct CountingRead {
+        file: File,
+        reads: Rc<Cell<usize>>,
+    }
+
+    impl Read for CountingRead {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reads.set(self.reads.get() + 1);
+            self.file.read(buf)
+        }
+    }
+
+    impl Seek for CountingRead {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.file.seek(pos)
+        }
+    }
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let reads = Rc::new(Cell::new(0));
+    let read = CountingRead {
+        file: File::open(&valid_bigwig)?,
+        reads: reads.clone(),
+    };
+    let mut bwread = BigWigRead::open(read)?.cached();
+
+    let expected = BigWigRead::open_file(&valid_bigwig)?.values("chr17", 59_000, 80_000)?;
+    let values = |bwread: &mut BigWigRead<_>| bwread.values("chr17", 59_000, 80_000);
+
+    // The first query reads the blocks, the second is served from the cache
+    let first = values(&mut bwread)?;
+    let reads_after_first = reads.get();
+    let second = values(&mut bwread)?;
+    assert_eq!(reads.get(), reads_after_first);
+
+    // After clearing, the blocks must be read again
+    bwread.clear_block_cache();
+    let third = values(&mut bwread)?;
+    assert!(reads.get() > reads_after_first);
+
+    // With no capacity, nothing is cached
+    bwread.set_block_cache_capacity(0);
+    let reads_before = reads.get();
+    let fourth = values(&mut bwread)?;
+    let reads_after_fourth = reads.get();
+    assert!(reads_after_fourth > reads_before);
+    let fifth = values(&mut bwread)?;
+    assert!(reads.get() > reads_after_fourth);
+
+    for actual in [first, second, third, fourth, fifth] {
+        assert_eq!(expected.len(), actual.len());
+        assert!(expected
+            .iter()
+            .zip(actual.iter())
+            .all(|(a, b)| a == b || (a.is_nan() && b.is_nan())));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_intervals_multi() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::{BBIReadError, BigWigRead};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let regions = [
+        ("chr17", 70_000, 90_000),
+        ("chr17", 59_000, 60_500),
+        ("chr17", 60_000, 75_000),
+        ("chr17", 0, 100),
+        ("chr17", 59_000, 60_500),
+        ("chr17", 59_000, 200_000),
+        ("chr17", 61_000, 61_100),
+    ];
+    let multi = bwread.get_intervals_multi(&regions)?;
+    assert_eq!(multi.len(), regions.len());
+    for ((chrom, start, end), values) in regions.iter().zip(multi.iter()) {
+        let expected: Vec<_> = bwread
+            .get_interval(chrom, *start, *end)?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(&expected, values);
+    }
+    assert!(!multi[1].is_empty());
+    assert!(multi[3].is_empty());
+
+    assert!(matches!(
+        bwread.get_intervals_multi(&[("chr1", 0, 100)]),
+        Err(BBIReadError::InvalidChromosome(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_overlapping_blocks() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let (start, end) = (59_000, 80_000);
+    let blocks = bwread.get_overlapping_blocks("chr17", start, end)?;
+    assert!(blocks.len() > 1);
+    assert!(blocks.windows(2).all(|b| b[0].offset() < b[1].offset()));
+
+    let mut values = vec![];
+    for block in blocks.iter() {
+        assert!(block.size() > 0);
+        values.extend(bwread.decode_block(block, "chr17", start, end)?);
+    }
+    let expected: Vec<_> = bwread
+        .get_interval("chr17", start, end)?
+        .collect::<Result<_, _>>()?;
+    assert_eq!(expected, values);
+
+    Ok(())
+}
+
+#[test]
+fn test_read_errors() -> Result<(), Box<dyn Error>> {
+    use std::io::{self, Cursor};
+    use std::path::PathBuf;
+
+    use bigtools::{BBIReadError, BigWigRead};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(&valid_bigwig).unwrap();
+    let err = bwread.get_interval("chrZ", 0, 100).err().unwrap();
+    assert!(matches!(err, BBIReadError::InvalidChromosome(_)));
+    assert_eq!(io::Error::from(err).kind(), io::ErrorKind::InvalidInput);
+
+    // Clobber the start of the first block so that it can't be decompressed.
+    let block = bwread.get_overlapping_blocks("chr17", 0, 83257441)?[0];
+    let mut data = std::fs::read(&valid_bigwig)?;
+    let offset = block.offset() as usize;
+    data[offset..offset + 8].fill(0xff);
+
+    let mut bwread = BigWigRead::open(Cursor::new(data)).unwrap();
+    let err = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .find_map(|v| v.err())
+        .unwrap();
+    assert!(matches!(err, BBIReadError::Decompression(_)));
+    assert_eq!(io::Error::from(err).kind(), io::ErrorKind::InvalidData);
+
+    Ok(())
+}
+
+#[test]
+fn test_raw_deflate_blocks() -> Result<(), Box<dyn Error>> {
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+    use libdeflater::{CompressionLvl, Compressor, Decompressor};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(&valid_bigwig).unwrap();
+    let expected: Vec<_> = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<_, _>>()?;
+
+    // Rewrite every block as raw deflate, without the zlib header and
+    // checksum. These are smaller, so the remainder of each block is zeroed.
+    let mut data = std::fs::read(&valid_bigwig)?;
+    let mut decompressor = Decompressor::new();
+    let mut compressor = Compressor::new(CompressionLvl::best());
+    for block in bwread.get_overlapping_blocks("chr17", 0, 83257441)? {
+        let (offset, size) = (block.offset() as usize, block.size() as usize);
+        let mut uncompressed = vec![0; 1 << 20];
+        let len = decompressor.zlib_decompress(&data[offset..offset + size], &mut uncompressed)?;
+        let mut compressed = vec![0; compressor.deflate_compress_bound(len)];
+        let len = compressor.deflate_compress(&uncompressed[..len], &mut compressed)?;
+        assert!(len <= size);
+        data[offset..offset + len].copy_from_slice(&compressed[..len]);
+        data[offset + len..offset + size].fill(0);
+    }
+
+    let mut bwread = BigWigRead::open(Cursor::new(data)).unwrap();
+    let values: Vec<_> = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<_, _>>()?;
+    assert_eq!(expected, values);
+
+    Ok(())
+}
+
+#[test]
+fn test_raw_deflate_blocks_with_zlib_like_header() -> Result<(), Box<dyn Error>> {
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+    use libdeflater::{CompressionLvl, Compressor, Decompressor};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(&valid_bigwig).unwrap();
+    let expected: Vec<_> = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<_, _>>()?;
+
+    // Rewrite blocks as raw deflate that starts with a stored (non-final)
+    // block of one byte. Its first two bytes (0x78 0x01) look like a zlib
+    // header, but the block can only be decoded as raw deflate.
+    let mut data = std::fs::read(&valid_bigwig)?;
+    let mut decompressor = Decompressor::new();
+    let mut compressor = Compressor::new(CompressionLvl::best());
+    let mut rewritten = 0;
+    for block in bwread.get_overlapping_blocks("chr17", 0, 83257441)? {
+        let (offset, size) = (block.offset() as usize, block.size() as usize);
+        let mut uncompressed = vec![0; 1 << 20];
+        let len = decompressor.zlib_decompress(&data[offset..offset + size], &mut uncompressed)?;
+        let mut compressed = vec![0x78, 0x01, 0x00, 0xfe, 0xff, uncompressed[0]];
+        let mut rest = vec![0; compressor.deflate_compress_bound(len - 1)];
+        let rest_len = compressor.deflate_compress(&uncompressed[1..len], &mut rest)?;
+        compressed.extend_from_slice(&rest[..rest_len]);
+        if compressed.len() > size {
+            continue;
+        }
+        data[offset..offset + compressed.len()].copy_from_slice(&compressed);
+        data[offset + compressed.len()..offset + size].fill(0);
+        rewritten += 1;
+    }
+    assert!(rewritten > 0);
+
+    let mut bwread = BigWigRead::open(Cursor::new(data)).unwrap();
+    let values: Vec<_> = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<_, _>>()?;
+    assert_eq!(expected, values);
+
+    Ok(())
+}
+
+#[test]
+fn test_chrom_ids() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+    let chrom_ids = bwread.chrom_ids();
+    assert_eq!(chrom_ids.len(), bwread.chroms().len());
+    assert_eq!(chrom_ids.get("chr17"), Some(&0));
+
+    Ok(())
+}
+
+#[test]
+fn test_scan_all() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+    let expected: Vec<_> = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .map(|v| v.map(|v| ("chr17".to_string(), v)))
+        .collect::<Result<_, _>>()?;
+    let scanned: Vec<_> = bwread.scan_all()?.collect::<Result<_, _>>()?;
+    assert!(!scanned.is_empty());
+    assert_eq!(expected, scanned);
+
+    // Reading one block at a time gives the same values
+    bwread.options.max_coalesce_bytes = 1;
+    let scanned: Vec<_> = bwread.scan_all()?.collect::<Result<_, _>>()?;
+    assert_eq!(expected, scanned);
+
+    Ok(())
+}
+
+#[test]
+fn test_count_overlapping_blocks() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let (start, end) = (59_000, 80_000);
+    let blocks = bwread.count_overlapping_blocks("chr17", start, end)?;
+    assert_eq!(
+        blocks,
+        bwread.get_overlapping_blocks("chr17", start, end)?.len()
+    );
+    assert_eq!(bwread.count_overlapping_blocks("chr17", 0, 100)?, 0);
+
+    let items = bwread.get_interval("chr17", start, end)?.count() as u64;
+    let estimate = bwread.estimate_items("chr17", start, end)?;
+    assert!(estimate >= items);
+    assert_eq!(bwread.estimate_items("chr17", 0, 100)?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_bedgraph() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let mut out = vec![];
+    bwread.write_bedgraph("chr17", 0, 59899, &mut out)?;
+    assert_eq!(String::from_utf8(out)?, "chr17\t59898\t59899\t0.06792\n");
+
+    let mut out = vec![];
+    bwread.write_bedgraph_all(&mut out)?;
+    let out = String::from_utf8(out)?;
+    let values: Vec<_> = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<_, _>>()?;
+    assert_eq!(out.lines().count(), values.len());
+    for (line, value) in out.lines().zip(values) {
+        let fields: Vec<&str> = line.split('\t').collect();
+        assert_eq!(fields[0], "chr17");
+        assert_eq!(fields[1].parse::<u32>()?, value.start);
+        assert_eq!(fields[2].parse::<u32>()?, value.end);
+        assert_eq!(fields[3].parse::<f32>()?, value.value);
+        assert!(!fields[3].ends_with(".0"));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_open_checked() -> Result<(), Box<dyn Error>> {
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    use bigtools::{BigWigRead, BigWigReadOpenError};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let bytes = std::fs::read(valid_bigwig)?;
+    BigWigRead::open_checked(Cursor::new(bytes.clone()))?;
+
+    // Truncated right before the full data index: the header and chromosomes
+    // are intact, so this opens, but can't be queried.
+    let full_index_offset = u64::from_le_bytes(bytes[24..32].try_into()?) as usize;
+    let truncated = bytes[..full_index_offset].to_vec();
+    BigWigRead::open(Cursor::new(truncated.clone()))?;
+    assert!(matches!(
+        BigWigRead::open_checked(Cursor::new(truncated)),
+        Err(BigWigReadOpenError::Truncated)
+    ));
+
+    let truncated = bytes[..100].to_vec();
+    assert!(matches!(
+        BigWigRead::open_checked(Cursor::new(truncated)),
+        Err(BigWigReadOpenError::Truncated)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_summary_stats() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::{BigWigRead, Summary};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+    let summary = bwread.get_summary()?;
+    assert_eq!(summary.mean(), summary.sum / summary.bases_covered as f64);
+    assert!(summary.std_dev() > 0.0);
+    let display = summary.to_string();
+    assert!(display.contains(&format!("basesCovered: {}", summary.bases_covered)));
+    assert!(display.contains(&format!("mean: {}", summary.mean())));
+
+    let empty = Summary {
+        total_items: 0,
+        bases_covered: 0,
+        min_val: 0.0,
+        max_val: 0.0,
+        sum: 0.0,
+        sum_squares: 0.0,
+    };
+    assert!(empty.mean().is_nan());
+    assert!(empty.std_dev().is_nan());
+
+    let values = [1.0, 2.0, 3.0, 4.0];
+    let summary = Summary {
+        total_items: 4,
+        bases_covered: 4,
+        min_val: 1.0,
+        max_val: 4.0,
+        sum: values.iter().sum(),
+        sum_squares: values.iter().map(|v| v * v).sum(),
+    };
+    assert_eq!(summary.mean(), 2.5);
+    assert!((summary.std_dev() - (5.0f64 / 3.0).sqrt()).abs() < 1e-12);
+
+    Ok(())
+}
+
+#[test]
+fn test_zoom_record_stats() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+    let level = bwread.zoom_levels()[0];
+    let records: Vec<_> = bwread
+        .get_zoom_interval("chr17", 0, 83257441, level)?
+        .collect::<Result<_, _>>()?;
+    assert!(!records.is_empty());
+    for record in records {
+        assert_eq!(
+            record.mean(),
+            record.summary.sum / record.summary.bases_covered as f64
+        );
+        assert_eq!(record.min_val(), record.summary.min_val);
+        assert_eq!(record.max_val(), record.summary.max_val);
+        // Zoom sums are stored as `f32`s, so allow for some rounding
+        assert!(record.min_val() - 1e-4 <= record.mean());
+        assert!(record.mean() <= record.max_val() + 1e-4);
+        assert!(record.coverage() > 0.0 && record.coverage() <= 1.0);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_max_values() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::{BBIReadError, BigWigRead};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+    assert_eq!(bwread.options.max_values, None);
+    assert_eq!(bwread.values("chr17", 0, 83257441)?.len(), 83257441);
+
+    bwread.options.max_values = Some(1000);
+    assert_eq!(bwread.values("chr17", 59000, 60000)?.len(), 1000);
+    match bwread.values("chr17", 0, 83257441) {
+        Err(BBIReadError::TooManyValues { requested, cap }) => {
+            assert_eq!(requested, 83257441);
+            assert_eq!(cap, 1000);
+        }
+        r => panic!("Expected TooManyValues, got {:?}", r.map(|v| v.len())),
+    }
+    assert!(matches!(
+        bwread.values_f64("chr17", 59000, 60001),
+        Err(BBIReadError::TooManyValues { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_value_eq() {
+    use std::collections::HashSet;
+
+    use bigtools::Value;
+
+    let value = |value| Value {
+        start: 10,
+        end: 20,
+        value,
+    };
+    assert_eq!(value(f32::NAN), value(f32::NAN));
+    assert_eq!(value(0.0), value(-0.0));
+    assert_ne!(value(1.0), value(f32::NAN));
+    assert_ne!(
+        value(1.0),
+        Value {
+            start: 10,
+            end: 21,
+            value: 1.0
+        }
+    );
+
+    let set: HashSet<Value> = [value(f32::NAN), value(-f32::NAN), value(0.0), value(-0.0)]
+        .into_iter()
+        .collect();
+    assert_eq!(set.len(), 2);
+
+    assert!(value(1.0).approx_eq(&value(1.05), 0.1));
+    assert!(!value(1.0).approx_eq(&value(1.2), 0.1));
+    assert!(value(f32::NAN).approx_eq(&value(f32::NAN), 0.1));
+    assert!(!value(1.0).approx_eq(&value(f32::NAN), 0.1));
+}
+
+#[test]
+fn test_values_into() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::{BBIReadError, BigWigRead};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    let mut buf = vec![0.0; 1000];
+    for start in [0, 59000, 59500, 1_000_000] {
+        bwread.values_into("chr17", start, start + 1000, &mut buf, -1.0)?;
+        let expected = bwread.values_with_fill("chr17", start, start + 1000, -1.0)?;
+        assert_eq!(buf, expected);
+    }
+
+    let mut short = vec![0.0; 10];
+    assert!(matches!(
+        bwread.values_into("chr17", 0, 1000, &mut short, 0.0),
+        Err(BBIReadError::InvalidBufferLength {
+            expected: 1000,
+            actual: 10
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_coverage_fraction() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+
+    for (start, end) in [(0, 100_000), (59_000, 60_000), (0, 83257441)] {
+        let values = bwread.values("chr17", start, end)?;
+        let covered = values.iter().filter(|v| !v.is_nan()).count();
+        let fraction = bwread.coverage_fraction("chr17", start, end)?;
+        assert_eq!(fraction, covered as f64 / (end - start) as f64);
+    }
+    assert_eq!(bwread.coverage_fraction("chr17", 0, 59898)?, 0.0);
+    assert_eq!(bwread.coverage_fraction("chr17", 59898, 59899)?, 1.0);
+    assert!(bwread.coverage_fraction("chr17", 10, 10)?.is_nan());
+
+    Ok(())
+}
+
+#[test]
+fn test_info_accessors() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+    let info = bwread.info();
+    assert_eq!(info.data_offset(), 344);
+    assert_eq!(info.header.data_offset(), 344);
+    assert!(info.header.index_offset() > info.data_offset());
+    assert!(info.summary_offset().unwrap() < info.data_offset());
+    assert_eq!(info.zoom_headers().len(), 10);
+    for zoom in info.zoom_headers() {
+        assert!(zoom.data_offset() > info.header.index_offset());
+        assert!(zoom.index_offset() > zoom.data_offset());
+    }
+
+    let total_items = bwread.item_count()?;
+    assert!(total_items > 0);
+    assert_eq!(total_items, bwread.get_summary()?.total_items);
+
+    Ok(())
+}
+
+#[test]
+fn test_zoom_interval_auto() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::BigWigRead;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig).unwrap();
+    let info = bwread.info();
+    assert_eq!(info.zoom_level_for_bins(1_000, 1_000), Some(10));
+    assert_eq!(info.zoom_level_for_bins(100_000, 1_000), Some(160));
+    assert_eq!(info.zoom_level_for_bins(100_000, 0), Some(2621440));
+
+    let (start, end) = (59_000, 159_000);
+    let auto: Vec<_> = bwread
+        .get_zoom_interval_auto("chr17", start, end, 1_000)?
+        .map(|r| r.map(|r| (r.start, r.end, r.summary.bases_covered)))
+        .collect::<Result<_, _>>()?;
+    let exact: Vec<_> = bwread
+        .get_zoom_interval("chr17", start, end, 160)?
+        .map(|r| r.map(|r| (r.start, r.end, r.summary.bases_covered)))
+        .collect::<Result<_, _>>()?;
+    assert!(!auto.is_empty());
+    assert!(auto.len() <= 1_000);
+    assert_eq!(auto, exact);
+
+    Ok(())
+}
+
+#[test]
+fn test_for_each_interval() -> Result<(), Box<dyn Error>> {
+    use std::collections::HashMap;
+
+    use bigtools::{BBIReadError, BBIWriteOptions, BigWigRead, BigWigWrite};
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 1_000), ("chr2".to_string(), 1_000)]);
+    let data = vec![
+        ("chr1".to_string(), 10, 20, 1.0),
+        ("chr1".to_string(), 30, 40, 2.0),
+        ("chr1".to_string(), 500, 600, 3.0),
+        ("chr2".to_string(), 0, 100, 4.0),
+    ];
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(tempfile.path(), chrom_map, data, BBIWriteOptions::default())?;
+
+    let mut bwread = BigWigRead::open_file(tempfile.path())?;
+    // Regions are visited in the given order, not sorted, and values are
+    // clipped to each region
+    let regions = [("chr1", 0, 35), ("chr2", 50, 60), ("chr1", 400, 1_000)];
+    let mut visited = vec![];
+    bwread.for_each_interval(&regions, |chrom, value| {
+        visited.push((chrom.to_string(), value.start, value.end, value.value));
+    })?;
+    assert_eq!(
+        visited,
+        [
+            ("chr1".to_string(), 10, 20, 1.0),
+            ("chr1".to_string(), 30, 35, 2.0),
+            ("chr2".to_string(), 50, 60, 4.0),
+            ("chr1".to_string(), 500, 600, 3.0),
+        ]
+    );
+
+    // Stops at the first error
+    let regions = [
+        ("chr1", 0, 1_000),
+        ("chrNone", 0, 1_000),
+        ("chr2", 0, 1_000),
+    ];
+    let mut count = 0;
+    let res = bwread.for_each_interval(&regions, |_, _| count += 1);
+    assert!(matches!(res, Err(BBIReadError::InvalidChromosome(_))));
+    assert_eq!(count, 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_file_stats() -> Result<(), Box<dyn Error>> {
+    use bigtools::BigWigRead;
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig()).unwrap();
+    let stats = bwread.file_stats()?;
+    assert_eq!(stats.chrom_count, 1);
+    assert!(stats.compressed);
+    assert!(stats.uncompress_buf_size > 0);
+    assert_eq!(stats.endianness, bwread.info().header.endianness);
+    assert_eq!(stats.summary.total_items, bwread.item_count()?);
+    let blocks = bwread.get_overlapping_blocks("chr17", 0, u32::MAX)?;
+    assert_eq!(stats.data_blocks, blocks.len() as u64);
+    let levels: Vec<u32> = stats.zooms.iter().map(|z| z.reduction_level).collect();
+    assert_eq!(levels, bwread.zoom_levels());
+    assert!(stats.zooms.iter().all(|z| z.blocks > 0));
+    // Coarser zooms have fewer records, so no more blocks
+    assert!(stats.zooms.windows(2).all(|w| w[0].blocks >= w[1].blocks));
+
+    Ok(())
+}
+
+#[test]
+fn test_open_file_error_path() -> Result<(), Box<dyn Error>> {
+    use std::path::PathBuf;
+
+    use bigtools::{
+        BigBedRead, BigBedReadOpenError, BigWigRead, BigWigReadOpenError, GenericBBIFileOpenError,
+        GenericBBIRead,
+    };
+
+    let missing = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/test/missing.bigWig");
+    match BigWigRead::open_file(&missing) {
+        Err(BigWigReadOpenError::FileError { path, source }) => {
+            assert_eq!(path, missing);
+            assert!(matches!(
+                *source,
+                BigWigReadOpenError::IoError(e) if e.kind() == std::io::ErrorKind::NotFound
+            ));
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for a missing file."),
+    }
+    match BigBedRead::open_file(&missing) {
+        Err(e @ BigBedReadOpenError::FileError { .. }) => {
+            assert!(e.to_string().contains("missing.bigWig"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for a missing file."),
+    }
+
+    // Errors other than I/O errors also have the path
+    let not_bbi =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/test/single_chrom.bedGraph");
+    let Err(e) = BigWigRead::open_file(&not_bbi) else {
+        panic!("Expected an error for a bedGraph.");
+    };
+    assert!(e.to_string().contains("single_chrom.bedGraph"), "{}", e);
+    match e {
+        BigWigReadOpenError::FileError { path, source } => {
+            assert_eq!(path, not_bbi);
+            assert!(matches!(*source, BigWigReadOpenError::NotABigWig));
+        }
+        e => panic!("Unexpected error: {}", e),
+    }
+    match BigBedRead::open_file(&not_bbi) {
+        Err(e @ BigBedReadOpenError::FileError { .. }) => {
+            assert!(e.to_string().contains("single_chrom.bedGraph"), "{}", e);
+            assert!(e.to_string().contains("not a bigBed"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for a bedGraph."),
+    }
+    match GenericBBIRead::open_file(not_bbi.to_str().unwrap()) {
+        Err(e @ GenericBBIFileOpenError::FileError { .. }) => {
+            assert!(e.to_string().contains("single_chrom.bedGraph"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for a bedGraph."),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_read_block_raw() -> Result<(), Box<dyn Error>> {
+    use bigtools::BigWigRead;
+    use libdeflater::Decompressor;
+
+    let valid_bigwig = valid_bigwig();
+    let file = std::fs::read(&valid_bigwig)?;
+    let mut bwread = BigWigRead::open_file(&valid_bigwig).unwrap();
+    let blocks = bwread.get_overlapping_blocks("chr17", 0, 83257441)?;
+    let mut decompressor = Decompressor::new();
+    for block in blocks.iter().take(5) {
+        let (offset, size) = (block.offset() as usize, block.size() as usize);
+        let raw = bwread.read_block_raw(block)?;
+        assert_eq!(raw, &file[offset..offset + size]);
+
+        let decompressed = bwread.read_block_decompressed(block)?;
+        let mut uncompressed = vec![0; 1 << 20];
+        let len = decompressor.zlib_decompress(&raw, &mut uncompressed)?;
+        assert_eq!(decompressed, &uncompressed[..len]);
+        // The section header starts with the chromosome id
+        assert_eq!(&decompressed[..4], &[0, 0, 0, 0]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_get_interval_for_pixels() -> Result<(), Box<dyn Error>> {
+    use std::collections::HashMap;
+
+    use bigtools::{BBIWriteOptions, BigWigRead, BigWigWrite, Value};
+    use itertools::Either;
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 10_000)]);
+    let data = vec![
+        ("chr1".to_string(), 0, 50, 1.0),
+        ("chr1".to_string(), 50, 150, 3.0),
+        ("chr1".to_string(), 2_000, 2_050, 2.0),
+    ];
+    let options = BBIWriteOptions {
+        manual_zoom_sizes: Some(vec![100, 1_000]),
+        ..Default::default()
+    };
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(tempfile.path(), chrom_map, data, options)?;
+
+    let mut bwread = BigWigRead::open_file(tempfile.path())?;
+    let mut zoom_records = |pixels| -> Result<Vec<_>, Box<dyn Error>> {
+        let mut records = vec![];
+        for item in bwread.get_interval_for_pixels("chr1", 0, 10_000, pixels)? {
+            match item? {
+                Either::Left(record) => records.push((
+                    record.start,
+                    record.end,
+                    record.summary.bases_covered,
+                    record.summary.sum,
+                )),
+                Either::Right(_) => panic!("Expected zoom records"),
+            }
+        }
+        Ok(records)
+    };
+    // 1000 bases per pixel uses the 1000 base zoom level
+    assert_eq!(
+        zoom_records(10)?,
+        [(0, 150, 150, 350.0), (2_000, 2_050, 50, 100.0)]
+    );
+    // 500 bases per pixel uses the coarsest zoom level that fits, 100 bases
+    assert_eq!(
+        zoom_records(20)?,
+        [
+            (0, 100, 100, 200.0),
+            (100, 150, 50, 150.0),
+            (2_000, 2_050, 50, 100.0)
+        ]
+    );
+
+    // Below the finest zoom level, full-resolution values are used
+    let values: Vec<_> = bwread
+        .get_interval_for_pixels("chr1", 0, 10_000, 1_000)?
+        .map(|item| item.map(|item| item.right().expect("Expected values")))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        values,
+        [
+            Value {
+                start: 0,
+                end: 50,
+                value: 1.0
+            },
+            Value {
+                start: 50,
+                end: 150,
+                value: 3.0
+            },
+            Value {
+                start: 2_000,
+                end: 2_050,
+                value: 2.0
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_chrom_info_id() -> Result<(), Box<dyn Error>> {
+    use bigtools::BigWigRead;
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig()).unwrap();
+    let chrom = bwread.chroms()[0].clone();
+    assert_eq!(chrom.name, "chr17");
+
+    // The chromosome id at the start of each block header is that of the chrom
+    let blocks = bwread.get_overlapping_blocks("chr17", 0, chrom.length)?;
+    for block in blocks.iter().take(5) {
+        let data = bwread.read_block_decompressed(block)?;
+        let block_chrom = u32::from_le_bytes(data[..4].try_into().unwrap());
+        assert_eq!(block_chrom, chrom.id());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_chrom_aliases() -> Result<(), Box<dyn Error>> {
+    use bigtools::{BBIReadError, BigWigRead};
+    use std::collections::HashMap;
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig()).unwrap();
+    let expected: Vec<_> = bwread
+        .get_interval("chr17", 59_000, 70_000)?
+        .collect::<Result<_, _>>()?;
+    assert!(matches!(
+        bwread.get_interval("17", 59_000, 70_000),
+        Err(BBIReadError::InvalidChromosome(_))
+    ));
+
+    bwread.set_chrom_aliases(HashMap::from([
+        ("17".to_string(), "chr17".to_string()),
+        ("18".to_string(), "chr18".to_string()),
+    ]));
+    let aliased: Vec<_> = bwread
+        .get_interval("17", 59_000, 70_000)?
+        .collect::<Result<_, _>>()?;
+    assert_eq!(aliased, expected);
+    assert_eq!(
+        bwread.get_overlapping_blocks("17", 59_000, 70_000)?,
+        bwread.get_overlapping_blocks("chr17", 59_000, 70_000)?
+    );
+    let reduction_level = bwread.zoom_levels()[0];
+    assert_eq!(
+        bwread
+            .get_zoom_interval("17", 0, 1_000_000, reduction_level)?
+            .count(),
+        bwread
+            .get_zoom_interval("chr17", 0, 1_000_000, reduction_level)?
+            .count()
+    );
+    assert_eq!(bwread.values("17", 59_000, 59_100)?.len(), 100);
+    assert_eq!(
+        bwread.get_intervals_multi(&[("17", 59_000, 70_000)])?,
+        vec![expected.clone()]
+    );
+
+    // The aliases are kept by the cached reader
+    let mut bwread = bwread.cached();
+    let aliased: Vec<_> = bwread
+        .get_interval("17", 59_000, 70_000)?
+        .collect::<Result<_, _>>()?;
+    assert_eq!(aliased, expected);
+
+    // Aliases of chromosomes that aren't in the file, and unknown names, are errors
+    assert!(matches!(
+        bwread.get_interval("18", 0, 100),
+        Err(BBIReadError::InvalidChromosome(_))
+    ));
+    assert!(matches!(
+        bwread.get_interval("X", 0, 100),
+        Err(BBIReadError::InvalidChromosome(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_chrom_errors() -> Result<(), Box<dyn Error>> {
+    use bigtools::{BBIReadError, BigWigRead, StatKind, ZoomIntervalError};
+
+    let mut bwread = BigWigRead::open_file(valid_bigwig()).unwrap();
+    let is_missing =
+        |e: BBIReadError| matches!(e, BBIReadError::InvalidChromosome(c) if c == "chrZ");
+
+    // A region without data is not an error
+    let values = bwread.values("chr17", 0, 100)?;
+    assert!(values.iter().all(|v| v.is_nan()));
+    assert_eq!(bwread.get_interval("chr17", 0, 100)?.count(), 0);
+
+    // But a missing chromosome is
+    assert!(is_missing(bwread.values("chrZ", 0, 100).unwrap_err()));
+    assert!(is_missing(bwread.values_f64("chrZ", 0, 100).unwrap_err()));
+    assert!(is_missing(
+        bwread.values_with_fill("chrZ", 0, 100, 0.0).unwrap_err()
+    ));
+    let mut buf = vec![0.0; 100];
+    assert!(is_missing(
+        bwread
+            .values_into("chrZ", 0, 100, &mut buf, 0.0)
+            .unwrap_err()
+    ));
+    assert!(is_missing(
+        bwread.get_interval("chrZ", 0, 100).err().unwrap()
+    ));
+    assert!(is_missing(bwread.get_interval_all("chrZ").err().unwrap()));
+    assert!(is_missing(
+        bwread.get_overlapping_blocks("chrZ", 0, 100).unwrap_err()
+    ));
+    assert!(is_missing(
+        bwread.values_binned("chrZ", 0, 100, 10).unwrap_err()
+    ));
+    assert!(is_missing(
+        bwread.stats("chrZ", 0, 100, StatKind::Mean).unwrap_err()
+    ));
+
+    let reduction_level = bwread.zoom_levels()[0];
+    match bwread.get_zoom_interval("chrZ", 0, 100, reduction_level) {
+        Err(ZoomIntervalError::BBIReadError(e)) => assert!(is_missing(e)),
+        _ => panic!("Expected a missing chromosome error"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_detect() -> Result<(), Box<dyn Error>> {
+    use std::fs::File;
+    use std::path::PathBuf;
+
+    use bigtools::BBIFile;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut valid_bigwig = dir.clone();
+    valid_bigwig.push("valid.bigWig");
+    let mut bigbed = dir.clone();
+    bigbed.push("bigGenePred.bb");
+    let mut bed = dir.clone();
+    bed.push("small.bed");
+
+    assert_eq!(
+        BBIFile::detect(&mut File::open(valid_bigwig)?)?,
+        Some(BBIFile::BigWig)
+    );
+    assert_eq!(
+        BBIFile::detect(&mut File::open(bigbed)?)?,
+        Some(BBIFile::BigBed)
+    );
+    assert_eq!(BBIFile::detect(&mut File::open(bed)?)?, None);
+    assert_eq!(BBIFile::detect(&mut &[0x26u8, 0xFC][..])?, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_iter_all_sorted_prefetch() -> Result<(), Box<dyn Error>> {
+    use std::collections::HashMap;
+
+    use bigtools::{BBIWriteOptions, BigWigRead, BigWigWrite, Value};
+
+    let chrom_map = HashMap::from([
+        ("chr1".to_string(), 2_000_000),
+        ("chr2".to_string(), 2_000_000),
+        ("chr3".to_string(), 2_000_000),
+        ("chr4".to_string(), 2_000_000),
+    ]);
+    // Uncompressed, chr2 spans several prefetch batches. chr3 has no values.
+    let mut expected = vec![];
+    for (chrom, count) in [("chr1", 5_000), ("chr2", 150_000), ("chr4", 5_000)] {
+        for i in 0..count {
+            let value = Value {
+                start: i * 10,
+                end: i * 10 + 5,
+                value: i as f32,
+            };
+            expected.push((chrom.to_string(), value));
+        }
+    }
+    let data = expected
+        .iter()
+        .map(|(chrom, v)| (chrom.clone(), v.start, v.end, v.value))
+        .collect();
+    let options = BBIWriteOptions {
+        compress: false,
+        ..Default::default()
+    };
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(tempfile.path(), chrom_map, data, options)?;
+
+    let mut bwread = BigWigRead::open_file(tempfile.path())?;
+    let mut iter = bwread.iter_all_sorted()?;
+    iter.set_prefetch(true)?;
+    let prefetched: Vec<_> = iter.collect::<Result<_, _>>()?;
+    assert_eq!(prefetched, expected);
+
+    // Prefetching can be turned off, or restarted, partway through a
+    // chromosome
+    let mut iter = bwread.iter_all_sorted()?;
+    iter.set_prefetch(true)?;
+    let mut partial: Vec<_> = iter.by_ref().take(60_000).collect::<Result<_, _>>()?;
+    iter.set_prefetch(false)?;
+    partial.extend(iter.by_ref().take(40_000).collect::<Result<Vec<_>, _>>()?);
+    iter.set_prefetch(true)?;
+    partial.extend(iter.by_ref().take(40_000).collect::<Result<Vec<_>, _>>()?);
+    iter.set_prefetch(true)?;
+    partial.extend(iter.collect::<Result<Vec<_>, _>>()?);
+    assert_eq!(partial, expected);
+
+    Ok(())
+}
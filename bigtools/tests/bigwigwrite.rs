@@ -191,3 +191,1935 @@ fn test_iter() {
         BigWigWrite::create_file(tempfile.path().to_string_lossy().to_string(), chrom_map).unwrap();
     outb.write(vals_iter, runtime).unwrap();
 }
+
+#[test]
+fn test_roundtrip_summary_and_zooms() -> Result<(), Box<dyn Error>> {
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut multi_chrom_bedgraph = dir.clone();
+    multi_chrom_bedgraph.push("multi_chrom.bedGraph");
+
+    let mut expected: Vec<(String, Value)> = vec![];
+    let mut vals_iter = BedFileStream::from_bedgraph_file(File::open(&multi_chrom_bedgraph)?);
+    while let Some(next) = vals_iter.next() {
+        let (chrom, value) = next?;
+        expected.push((chrom.to_string(), value));
+    }
+
+    let runtime = runtime::Builder::new_multi_thread()
+        .worker_threads(6)
+        .build()
+        .expect("Unable to create runtime.");
+
+    let infile = File::open(multi_chrom_bedgraph)?;
+    let tempfile = tempfile::NamedTempFile::new()?;
+
+    let mut chrom_map = HashMap::new();
+    chrom_map.insert("chr1".to_string(), 248956422);
+    chrom_map.insert("chr2".to_string(), 242193529);
+    chrom_map.insert("chr3".to_string(), 198295559);
+    chrom_map.insert("chr4".to_string(), 190214555);
+    chrom_map.insert("chr5".to_string(), 181538259);
+    chrom_map.insert("chr6".to_string(), 170805979);
+
+    let outb = BigWigWrite::create_file(tempfile.path(), chrom_map.clone())?;
+    let data = BedParserStreamingIterator::from_bedgraph_file(infile, false);
+    outb.write(data, runtime).unwrap();
+
+    let mut bwread = BigWigRead::open_file(tempfile.path()).unwrap();
+
+    // Every value is read back, in order
+    let mut actual: Vec<(String, Value)> = vec![];
+    let chroms: Vec<_> = bwread.chroms().iter().map(|c| c.name.clone()).collect();
+    for chrom in chroms {
+        let length = chrom_map[&chrom];
+        for value in bwread.get_interval(&chrom, 0, length)? {
+            actual.push((chrom.clone(), value?));
+        }
+    }
+    actual.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.start.cmp(&b.1.start)));
+    assert_eq!(expected, actual);
+
+    // The total summary matches the input
+    let summary = bwread.get_summary()?;
+    let bases_covered: u64 = expected
+        .iter()
+        .map(|(_, v)| u64::from(v.end - v.start))
+        .sum();
+    let sum: f64 = expected
+        .iter()
+        .map(|(_, v)| f64::from(v.end - v.start) * f64::from(v.value))
+        .sum();
+    let min_val = expected
+        .iter()
+        .map(|(_, v)| f64::from(v.value))
+        .fold(f64::INFINITY, f64::min);
+    let max_val = expected
+        .iter()
+        .map(|(_, v)| f64::from(v.value))
+        .fold(f64::NEG_INFINITY, f64::max);
+    assert_eq!(summary.bases_covered, bases_covered);
+    assert_eq!(summary.min_val, min_val);
+    assert_eq!(summary.max_val, max_val);
+    assert!((summary.sum - sum).abs() / sum < 1e-6);
+
+    // Each zoom level covers the same bases as the full data
+    let zoom_levels = bwread.zoom_levels();
+    assert!(!zoom_levels.is_empty());
+    for reduction_level in zoom_levels {
+        let mut zoom_bases_covered = 0;
+        for chrom in ["chr1", "chr2", "chr3", "chr4", "chr5", "chr6"] {
+            let length = chrom_map[chrom];
+            for record in bwread.get_zoom_interval(chrom, 0, length, reduction_level)? {
+                let record = record?;
+                assert!(record.end - record.start <= reduction_level);
+                zoom_bases_covered += record.summary.bases_covered;
+            }
+        }
+        assert_eq!(zoom_bases_covered, bases_covered);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_compression_level() -> Result<(), Box<dyn Error>> {
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut single_chrom_bedgraph = dir.clone();
+    single_chrom_bedgraph.push("single_chrom.bedGraph");
+
+    let mut outputs = vec![];
+    for compression_level in [0, 1, 9] {
+        let runtime = runtime::Builder::new_multi_thread()
+            .worker_threads(6)
+            .build()
+            .expect("Unable to create runtime.");
+
+        let infile = File::open(single_chrom_bedgraph.clone())?;
+        let tempfile = tempfile::NamedTempFile::new()?;
+
+        let mut chrom_map = HashMap::new();
+        chrom_map.insert("chr17".to_string(), 83257441);
+
+        let mut outb = BigWigWrite::create_file(tempfile.path(), chrom_map)?;
+        outb.options.compression_level = Some(compression_level);
+
+        let data = BedParserStreamingIterator::from_bedgraph_file(infile, false);
+        outb.write(data, runtime).unwrap();
+
+        let mut bwread = BigWigRead::open_file(tempfile.path()).unwrap();
+        assert_eq!(compression_level > 0, bwread.info().header.is_compressed());
+        let values: Vec<Value> = bwread
+            .get_interval("chr17", 0, 83257441)?
+            .collect::<Result<_, _>>()?;
+        let zoom_records = bwread
+            .get_zoom_interval("chr17", 0, 83257441, bwread.zoom_levels()[0])?
+            .count();
+        let size = tempfile.as_file().metadata()?.len();
+        outputs.push((values, zoom_records, size));
+    }
+
+    let (uncompressed_values, uncompressed_zooms, uncompressed_size) = &outputs[0];
+    assert!(!uncompressed_values.is_empty());
+    for (values, zooms, size) in &outputs[1..] {
+        assert_eq!(uncompressed_values, values);
+        assert_eq!(uncompressed_zooms, zooms);
+        assert!(size < uncompressed_size);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_endianness() -> Result<(), Box<dyn Error>> {
+    use byteordered::Endianness;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut single_chrom_bedgraph = dir.clone();
+    single_chrom_bedgraph.push("single_chrom.bedGraph");
+
+    let mut outputs = vec![];
+    for endianness in [Endianness::Little, Endianness::Big] {
+        let runtime = runtime::Builder::new_multi_thread()
+            .worker_threads(6)
+            .build()
+            .expect("Unable to create runtime.");
+
+        let infile = File::open(single_chrom_bedgraph.clone())?;
+        let tempfile = tempfile::NamedTempFile::new()?;
+
+        let mut chrom_map = HashMap::new();
+        chrom_map.insert("chr17".to_string(), 83257441);
+
+        let mut outb = BigWigWrite::create_file(tempfile.path(), chrom_map)?;
+        outb.options.endianness = endianness;
+
+        let data = BedParserStreamingIterator::from_bedgraph_file(infile, false);
+        outb.write(data, runtime).unwrap();
+
+        let mut bwread = BigWigRead::open_file(tempfile.path()).unwrap();
+        assert_eq!(endianness, bwread.info().header.endianness);
+        let chroms = bwread.chroms().to_vec();
+        let values: Vec<Value> = bwread
+            .get_interval("chr17", 0, 83257441)?
+            .collect::<Result<_, _>>()?;
+        let zooms = bwread
+            .get_zoom_interval("chr17", 0, 83257441, bwread.zoom_levels()[0])?
+            .map(|r| r.map(|r| (r.start, r.end, r.summary.sum)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let summary = bwread.get_summary()?;
+        outputs.push((chroms, values, zooms, summary));
+    }
+
+    let (little, big) = (&outputs[0], &outputs[1]);
+    assert!(!little.1.is_empty());
+    assert_eq!(little.0.len(), big.0.len());
+    assert_eq!(little.0[0].name, big.0[0].name);
+    assert_eq!(little.0[0].length, big.0[0].length);
+    assert_eq!(little.1, big.1);
+    assert_eq!(little.2, big.2);
+    assert_eq!(little.3.bases_covered, big.3.bases_covered);
+    assert_eq!(little.3.sum, big.3.sum);
+
+    Ok(())
+}
+
+#[test]
+fn test_manual_zoom_sizes() -> Result<(), Box<dyn Error>> {
+    use bigtools::BBIProcessError;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut single_chrom_bedgraph = dir.clone();
+    single_chrom_bedgraph.push("single_chrom.bedGraph");
+
+    let mut chrom_map = HashMap::new();
+    chrom_map.insert("chr17".to_string(), 83257441);
+
+    let zoom_sizes = vec![100, 1000, 25000];
+    for multipass in [false, true] {
+        let runtime = runtime::Builder::new_multi_thread()
+            .worker_threads(6)
+            .build()
+            .expect("Unable to create runtime.");
+        let tempfile = tempfile::NamedTempFile::new()?;
+        let mut outb = BigWigWrite::create_file(tempfile.path(), chrom_map.clone())?;
+        outb.options.manual_zoom_sizes = Some(zoom_sizes.clone());
+        let make_vals = || {
+            let infile = File::open(single_chrom_bedgraph.clone())?;
+            Ok(BedParserStreamingIterator::from_bedgraph_file(
+                infile, false,
+            ))
+        };
+        if multipass {
+            outb.write_multipass(make_vals, runtime).unwrap();
+        } else {
+            outb.write(make_vals().unwrap(), runtime).unwrap();
+        }
+
+        let bwread = BigWigRead::open_file(tempfile.path()).unwrap();
+        let reduction_levels: Vec<u32> = bwread
+            .info()
+            .zoom_headers
+            .iter()
+            .map(|z| z.reduction_level)
+            .collect();
+        assert_eq!(reduction_levels, zoom_sizes);
+    }
+
+    for invalid in [vec![1000, 100], vec![100, 100], vec![0, 100]] {
+        let runtime = runtime::Builder::new_multi_thread()
+            .worker_threads(6)
+            .build()
+            .expect("Unable to create runtime.");
+        let tempfile = tempfile::NamedTempFile::new()?;
+        let mut outb = BigWigWrite::create_file(tempfile.path(), chrom_map.clone())?;
+        outb.options.manual_zoom_sizes = Some(invalid);
+        let infile = File::open(single_chrom_bedgraph.clone())?;
+        let data = BedParserStreamingIterator::from_bedgraph_file(infile, false);
+        assert!(matches!(
+            outb.write(data, runtime),
+            Err(BBIProcessError::InvalidInput(_))
+        ));
+    }
+
+    // The first zoom size can't be smaller than the average item size (100)
+    let data: Vec<_> = (0..1000)
+        .map(|i| ("chr17".to_string(), i * 100, i * 100 + 100, i as f32))
+        .collect();
+    for (zooms, valid) in [(vec![50, 1000], false), (vec![100, 1000], true)] {
+        let tempfile = tempfile::NamedTempFile::new()?;
+        let mut options = bigtools::BBIWriteOptions::default();
+        options.manual_zoom_sizes = Some(zooms);
+        let result =
+            BigWigWrite::write_vec(tempfile.path(), chrom_map.clone(), data.clone(), options);
+        if valid {
+            result?;
+        } else {
+            assert!(matches!(result, Err(BBIProcessError::InvalidInput(_))));
+        }
+    }
+    // This is checked after the data pass, once all of it has been written
+    let runtime = runtime::Builder::new_multi_thread()
+        .worker_threads(6)
+        .build()
+        .expect("Unable to create runtime.");
+    let tempfile = tempfile::NamedTempFile::new()?;
+    let mut outb = BigWigWrite::create_file(tempfile.path(), chrom_map.clone())?;
+    outb.options.manual_zoom_sizes = Some(vec![50, 1000]);
+    let make_vals = || {
+        let vals = data
+            .clone()
+            .into_iter()
+            .map(|(chrom, start, end, value)| (chrom, Value { start, end, value }));
+        Ok(BedParserStreamingIterator::wrap_infallible_iter(
+            vals, false,
+        ))
+    };
+    assert!(matches!(
+        outb.write_multipass(make_vals, runtime),
+        Err(BBIProcessError::InvalidInput(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_unsorted_starts() {
+    use bigtools::BBIProcessError;
+
+    let vals = vec![
+        ("chr1", 100, 200),
+        ("chr1", 300, 400),
+        ("chr1", 250, 260),
+        ("chr1", 500, 600),
+    ];
+    let iter = vals.into_iter().map(|(chrom, start, end)| {
+        (
+            chrom,
+            Value {
+                start,
+                end,
+                value: 1.0,
+            },
+        )
+    });
+    let vals_iter = BedParserStreamingIterator::wrap_infallible_iter(iter, false);
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 10_000)]);
+
+    let runtime = runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to create runtime.");
+
+    let tempfile = tempfile::NamedTempFile::new().unwrap();
+    let outb = BigWigWrite::create_file(tempfile.path(), chrom_map).unwrap();
+    match outb.write(vals_iter, runtime) {
+        Err(BBIProcessError::InvalidInput(e)) => {
+            assert!(e.contains("not sorted on chromosome chr1"), "{}", e);
+            assert!(e.contains("250-260"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for unsorted input."),
+    }
+}
+
+#[test]
+fn test_overlapping_values() {
+    use bigtools::{BBIProcessError, OnOverlap};
+
+    let vals = vec![("chr1", 100, 200), ("chr1", 150, 250)];
+    let iter = vals.into_iter().map(|(chrom, start, end)| {
+        (
+            chrom,
+            Value {
+                start,
+                end,
+                value: 1.0,
+            },
+        )
+    });
+    let vals_iter = BedParserStreamingIterator::wrap_infallible_iter(iter, false);
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 10_000)]);
+
+    let runtime = runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to create runtime.");
+
+    let tempfile = tempfile::NamedTempFile::new().unwrap();
+    let outb = BigWigWrite::create_file(tempfile.path(), chrom_map).unwrap();
+    assert_eq!(outb.options.on_overlap, OnOverlap::Error);
+    match outb.write(vals_iter, runtime) {
+        Err(BBIProcessError::InvalidInput(e)) => {
+            assert!(e.contains("overlapping values on chromosome chr1"), "{}", e);
+            assert!(e.contains("100-200 and 150-250"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for overlapping input."),
+    }
+}
+
+#[test]
+fn test_estimate_size() -> Result<(), Box<dyn Error>> {
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut single_chrom_bedgraph = dir.clone();
+    single_chrom_bedgraph.push("single_chrom.bedGraph");
+
+    let num_records = {
+        let infile = File::open(single_chrom_bedgraph.clone())?;
+        let mut vals_iter = BedFileStream::from_bedgraph_file(infile);
+        let mut count = 0;
+        while let Some(v) = vals_iter.next() {
+            v?;
+            count += 1;
+        }
+        count
+    };
+
+    let runtime = runtime::Builder::new_multi_thread()
+        .worker_threads(6)
+        .build()
+        .expect("Unable to create runtime.");
+
+    let infile = File::open(single_chrom_bedgraph)?;
+    let tempfile = tempfile::NamedTempFile::new()?;
+
+    let mut chrom_map = HashMap::new();
+    chrom_map.insert("chr17".to_string(), 83257441);
+
+    let outb = BigWigWrite::create_file(tempfile.path(), chrom_map)?;
+    let estimate = outb.estimate_size(num_records);
+    assert_eq!(
+        estimate.estimated_blocks,
+        num_records.div_ceil(outb.options.items_per_slot as u64)
+    );
+    assert!(estimate.estimated_zoom_records > 0);
+
+    let data = BedParserStreamingIterator::from_bedgraph_file(infile, false);
+    outb.write(data, runtime).unwrap();
+
+    // The estimate should at least be the right order of magnitude
+    let size = tempfile.as_file().metadata()?.len();
+    assert!(
+        estimate.estimated_bytes > size / 10,
+        "{:?} {}",
+        estimate,
+        size
+    );
+    assert!(
+        estimate.estimated_bytes < size * 10,
+        "{:?} {}",
+        estimate,
+        size
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_multi_pass_inconsistent() {
+    use std::cell::Cell;
+
+    use bigtools::BBIProcessError;
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 10_000), ("chr2".to_string(), 10_000)]);
+
+    let runtime = runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to create runtime.");
+
+    // The second pass yields a chromosome that wasn't in the first
+    let pass = Cell::new(0);
+    let make_vals = || {
+        let chrom = if pass.replace(pass.get() + 1) == 0 {
+            "chr1"
+        } else {
+            "chr2"
+        };
+        let iter = (0..100).map(move |i| {
+            (
+                chrom,
+                Value {
+                    start: i * 10,
+                    end: i * 10 + 5,
+                    value: 1.0,
+                },
+            )
+        });
+        Ok(BedParserStreamingIterator::wrap_infallible_iter(
+            iter, false,
+        ))
+    };
+
+    let tempfile = tempfile::NamedTempFile::new().unwrap();
+    let outb = BigWigWrite::create_file(tempfile.path(), chrom_map).unwrap();
+    match outb.write_multipass(make_vals, runtime) {
+        Err(BBIProcessError::InvalidChromosome(e)) => {
+            assert!(e.contains("chr2"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for inconsistent passes."),
+    }
+}
+
+#[test]
+fn test_skip_unknown_sections() -> Result<(), Box<dyn Error>> {
+    use bigtools::BBIReadError;
+
+    let vals = vec![(100, 200), (300, 400), (500, 600)];
+    let iter = vals.into_iter().map(|(start, end)| {
+        (
+            "chr1",
+            Value {
+                start,
+                end,
+                value: 1.0,
+            },
+        )
+    });
+    let vals_iter = BedParserStreamingIterator::wrap_infallible_iter(iter, false);
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 10_000)]);
+
+    let runtime = runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to create runtime.");
+
+    let tempfile = tempfile::NamedTempFile::new()?;
+    let mut outb = BigWigWrite::create_file(tempfile.path(), chrom_map)?;
+    outb.options.compression_level = Some(0);
+    outb.options.manual_zoom_sizes = Some(vec![]);
+    outb.write(vals_iter, runtime).unwrap();
+
+    // Find the (uncompressed) data block by its header, and give it an
+    // unknown section type.
+    let mut bytes = std::fs::read(tempfile.path())?;
+    let mut header = vec![];
+    header.extend_from_slice(&0u32.to_ne_bytes());
+    header.extend_from_slice(&100u32.to_ne_bytes());
+    header.extend_from_slice(&600u32.to_ne_bytes());
+    header.extend_from_slice(&[0; 8]);
+    header.extend_from_slice(&[1, 0]);
+    let block_start = bytes
+        .windows(header.len())
+        .position(|w| w == header)
+        .unwrap();
+    bytes[block_start + 20] = 9;
+    std::fs::write(tempfile.path(), bytes)?;
+
+    let mut bwread = BigWigRead::open_file(tempfile.path())?;
+    let strict: Result<Vec<_>, _> = bwread.get_interval("chr1", 0, 10_000)?.collect();
+    assert!(matches!(strict, Err(BBIReadError::InvalidSection(_))));
+
+    bwread.options.skip_unknown_sections = true;
+    let lenient: Vec<_> = bwread
+        .get_interval("chr1", 0, 10_000)?
+        .collect::<Result<_, _>>()?;
+    assert!(lenient.is_empty());
+    let blocks = bwread.get_overlapping_blocks("chr1", 0, 10_000)?;
+    assert_eq!(bwread.skipped_blocks(), &blocks[..]);
+    assert!(bwread.values("chr1", 0, 1000)?.iter().all(|v| v.is_nan()));
+    assert_eq!(bwread.take_skipped_blocks().len(), 2);
+    assert!(bwread.skipped_blocks().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_write_wig_roundtrip() -> Result<(), Box<dyn Error>> {
+    fn write_and_export(vals: Vec<Value>) -> Result<String, Box<dyn Error>> {
+        let iter = vals.into_iter().map(|v| ("chr1", v));
+        let vals_iter = BedParserStreamingIterator::wrap_infallible_iter(iter, false);
+        let chrom_map = HashMap::from([("chr1".to_string(), 10_000)]);
+        let runtime = runtime::Builder::new_current_thread()
+            .build()
+            .expect("Unable to create runtime.");
+
+        let tempfile = tempfile::NamedTempFile::new()?;
+        let outb = BigWigWrite::create_file(tempfile.path(), chrom_map)?;
+        outb.write(vals_iter, runtime)?;
+
+        let mut bwread = BigWigRead::open_file(tempfile.path())?;
+        let mut out = vec![];
+        bwread.write_wig("chr1", 0, 10_000, &mut out, None)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    fn parse_wig(wig: &str) -> Vec<Value> {
+        let mut vals = vec![];
+        // (next start, step, span) for fixedStep, or (0, 0, span) for variableStep
+        let mut state = (0, 0, 0);
+        for line in wig.lines() {
+            let mut fields = line.split_whitespace();
+            let first = fields.next().unwrap();
+            let params: HashMap<&str, u32> = fields
+                .clone()
+                .filter_map(|f| f.split_once('='))
+                .filter_map(|(k, v)| v.parse().ok().map(|v| (k, v)))
+                .collect();
+            match first {
+                "fixedStep" => state = (params["start"] - 1, params["step"], params["span"]),
+                "variableStep" => state = (0, 0, params["span"]),
+                _ if state.1 > 0 => {
+                    vals.push(Value {
+                        start: state.0,
+                        end: state.0 + state.2,
+                        value: first.parse().unwrap(),
+                    });
+                    state.0 += state.1;
+                }
+                _ => {
+                    let start = first.parse::<u32>().unwrap() - 1;
+                    vals.push(Value {
+                        start,
+                        end: start + state.2,
+                        value: fields.next().unwrap().parse().unwrap(),
+                    });
+                }
+            }
+        }
+        vals
+    }
+
+    let mut vals: Vec<Value> = (0..5)
+        .map(|i| Value {
+            start: i * 10,
+            end: i * 10 + 5,
+            value: i as f32,
+        })
+        .collect();
+    vals.extend([
+        Value {
+            start: 100,
+            end: 150,
+            value: 0.5,
+        },
+        Value {
+            start: 200,
+            end: 250,
+            value: 1.5,
+        },
+        Value {
+            start: 300,
+            end: 301,
+            value: 2.0,
+        },
+    ]);
+
+    let wig = write_and_export(vals.clone())?;
+    assert_eq!(
+        wig,
+        "fixedStep chrom=chr1 start=1 step=10 span=5\n0\n1\n2\n3\n4\n\
+         variableStep chrom=chr1 span=50\n101\t0.5\n201\t1.5\n\
+         variableStep chrom=chr1 span=1\n301\t2\n"
+    );
+    assert_eq!(parse_wig(&wig), vals);
+
+    let roundtripped = write_and_export(parse_wig(&wig))?;
+    assert_eq!(roundtripped, wig);
+
+    // Zero-width values can't be written as wig, so are skipped (rather than
+    // written with `span=0` or `step=0`)
+    let mut with_zero_width = vals.clone();
+    with_zero_width.extend((0..3).map(|_| Value {
+        start: 500,
+        end: 500,
+        value: 3.0,
+    }));
+    assert_eq!(write_and_export(with_zero_width)?, wig);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_vec() -> Result<(), Box<dyn Error>> {
+    use bigtools::{BBIProcessError, BBIWriteOptions};
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 10_000), ("chr2".to_string(), 5_000)]);
+    let data = vec![
+        ("chr2".to_string(), 10, 20, 3.0),
+        ("chr1".to_string(), 300, 400, 2.0),
+        ("chr1".to_string(), 100, 200, 1.0),
+    ];
+
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(
+        tempfile.path(),
+        chrom_map.clone(),
+        data,
+        BBIWriteOptions::default(),
+    )?;
+
+    let mut bwread = BigWigRead::open_file(tempfile.path())?;
+    let chr1: Vec<_> = bwread
+        .get_interval("chr1", 0, 10_000)?
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        chr1,
+        vec![
+            Value {
+                start: 100,
+                end: 200,
+                value: 1.0
+            },
+            Value {
+                start: 300,
+                end: 400,
+                value: 2.0
+            },
+        ]
+    );
+    let chr2: Vec<_> = bwread
+        .get_interval("chr2", 0, 5_000)?
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        chr2,
+        vec![Value {
+            start: 10,
+            end: 20,
+            value: 3.0
+        }]
+    );
+
+    let overlapping = vec![
+        ("chr1".to_string(), 150, 250, 1.0),
+        ("chr1".to_string(), 100, 200, 1.0),
+    ];
+    let tempfile = tempfile::NamedTempFile::new()?;
+    let res = BigWigWrite::write_vec(
+        tempfile.path(),
+        chrom_map,
+        overlapping,
+        BBIWriteOptions::default(),
+    );
+    assert!(matches!(res, Err(BBIProcessError::InvalidInput(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_interval_boundaries() -> Result<(), Box<dyn Error>> {
+    let vals = vec![(0, 10, 1.0), (10, 20, 2.0), (25, 25, 3.0), (30, 40, 4.0)];
+    let iter = vals
+        .into_iter()
+        .map(|(start, end, value)| ("chr1", Value { start, end, value }));
+    let vals_iter = BedParserStreamingIterator::wrap_infallible_iter(iter, false);
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 10_000)]);
+
+    let runtime = runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to create runtime.");
+
+    let tempfile = tempfile::NamedTempFile::new()?;
+    let outb = BigWigWrite::create_file(tempfile.path(), chrom_map)?;
+    outb.write(vals_iter, runtime)?;
+
+    let mut bwread = BigWigRead::open_file(tempfile.path())?;
+    let mut query = |start, end| -> Result<Vec<(u32, u32, f32)>, Box<dyn Error>> {
+        Ok(bwread
+            .get_interval("chr1", start, end)?
+            .map(|v| v.map(|v| (v.start, v.end, v.value)))
+            .collect::<Result<_, _>>()?)
+    };
+
+    // Values that only touch the query boundaries aren't returned
+    assert_eq!(query(10, 20)?, vec![(10, 20, 2.0)]);
+    assert_eq!(query(0, 10)?, vec![(0, 10, 1.0)]);
+    assert_eq!(query(9, 11)?, vec![(9, 10, 1.0), (10, 11, 2.0)]);
+    // Zero-width values are returned only if within the query
+    assert_eq!(query(25, 26)?, vec![(25, 25, 3.0)]);
+    assert_eq!(query(20, 25)?, vec![]);
+    assert_eq!(query(20, 30)?, vec![(25, 25, 3.0)]);
+    // Empty queries don't return anything
+    assert_eq!(query(5, 5)?, vec![]);
+    assert_eq!(query(25, 25)?, vec![]);
+
+    let mut query = |start, end| -> Result<Vec<(u32, u32, f32)>, Box<dyn Error>> {
+        Ok(bwread
+            .get_interval_inclusive("chr1", start, end)?
+            .map(|v| v.map(|v| (v.start, v.end, v.value)))
+            .collect::<Result<_, _>>()?)
+    };
+    assert_eq!(query(0, 10)?, vec![(0, 10, 1.0), (10, 11, 2.0)]);
+    assert_eq!(query(5, 5)?, vec![(5, 6, 1.0)]);
+    assert_eq!(query(25, 25)?, vec![(25, 25, 3.0)]);
+
+    Ok(())
+}
+
+#[test]
+fn test_write_rezoomed() -> Result<(), Box<dyn Error>> {
+    use bigtools::BBIProcessError;
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test");
+
+    let mut single_chrom_bedgraph = dir.clone();
+    single_chrom_bedgraph.push("single_chrom.bedGraph");
+
+    let chrom_map = HashMap::from([("chr17".to_string(), 83257441)]);
+
+    // First, write a bigWig with only a couple of zoom levels
+    let runtime = runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to create runtime.");
+    let original = tempfile::NamedTempFile::new()?;
+    let mut outb = BigWigWrite::create_file(original.path(), chrom_map.clone())?;
+    outb.options.manual_zoom_sizes = Some(vec![10, 1000]);
+    let data = BedParserStreamingIterator::from_bedgraph_file(
+        File::open(single_chrom_bedgraph.clone())?,
+        false,
+    );
+    outb.write(data, runtime)?;
+
+    let mut bwread = BigWigRead::open_file(original.path())?;
+    let runtime = runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .build()
+        .expect("Unable to create runtime.");
+    let rezoomed = tempfile::NamedTempFile::new()?;
+    let outb = BigWigWrite::create_file(rezoomed.path(), chrom_map.clone())?;
+    outb.write_rezoomed(&mut bwread, &[100, 10_000, 1000], runtime)?;
+
+    let mut rezoomed = BigWigRead::open_file(rezoomed.path())?;
+    assert_eq!(rezoomed.zoom_levels(), vec![10, 100, 1000, 10_000]);
+
+    let values: Vec<Value> = bwread
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<_, _>>()?;
+    let rezoomed_values: Vec<Value> = rezoomed
+        .get_interval("chr17", 0, 83257441)?
+        .collect::<Result<_, _>>()?;
+    assert_eq!(values, rezoomed_values);
+
+    // The existing zooms are unchanged, and the new ones match writing them
+    // from the bedGraph
+    let zoom = |bw: &mut BigWigRead<_>, level| -> Result<Vec<_>, Box<dyn Error>> {
+        Ok(bw
+            .get_zoom_interval("chr17", 0, 83257441, level)?
+            .map(|r| r.map(|r| (r.start, r.end, r.summary.bases_covered)))
+            .collect::<Result<_, _>>()?)
+    };
+    assert_eq!(zoom(&mut bwread, 1000)?, zoom(&mut rezoomed, 1000)?);
+    let runtime = runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to create runtime.");
+    let direct = tempfile::NamedTempFile::new()?;
+    let mut outb = BigWigWrite::create_file(direct.path(), chrom_map.clone())?;
+    outb.options.manual_zoom_sizes = Some(vec![10_000]);
+    let data =
+        BedParserStreamingIterator::from_bedgraph_file(File::open(single_chrom_bedgraph)?, false);
+    outb.write(data, runtime)?;
+    let mut direct = BigWigRead::open_file(direct.path())?;
+    assert_eq!(zoom(&mut direct, 10_000)?, zoom(&mut rezoomed, 10_000)?);
+
+    // Files can't have more than 10 zoom levels
+    let runtime = runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to create runtime.");
+    let too_many = tempfile::NamedTempFile::new()?;
+    let outb = BigWigWrite::create_file(too_many.path(), chrom_map)?;
+    let zooms: Vec<u32> = (1..=9).map(|i| i * 100).collect();
+    let res = outb.write_rezoomed(&mut bwread, &zooms, runtime);
+    assert!(matches!(res, Err(BBIProcessError::InvalidInput(_))));
+
+    Ok(())
+}
+
+#[test]
+fn test_strict_chroms() -> Result<(), Box<dyn Error>> {
+    use bigtools::{BBIProcessError, BBIWriteOptions};
+
+    // The data uses `chr1`, but the chrom sizes use `1`
+    let chrom_map = HashMap::from([("chr1".to_string(), 10_000), ("1".to_string(), 10_000)]);
+    let data = vec![("chr1".to_string(), 100, 200, 1.0)];
+
+    // By default, chromosomes without data are fine
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(
+        tempfile.path(),
+        chrom_map.clone(),
+        data.clone(),
+        BBIWriteOptions::default(),
+    )?;
+
+    let mut options = BBIWriteOptions::default();
+    options.strict_chroms = true;
+    let tempfile = tempfile::NamedTempFile::new()?;
+    match BigWigWrite::write_vec(tempfile.path(), chrom_map, data, options) {
+        Err(BBIProcessError::InvalidChromosome(e)) => {
+            assert!(e.ends_with(": 1"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for a chromosome without data."),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_write_options_builder() -> Result<(), Box<dyn Error>> {
+    use bigtools::{BBIWriteOptions, BBIWriteOptionsError};
+
+    let options = BBIWriteOptions::builder()
+        .compress(false)
+        .block_size(64)
+        .manual_zoom_sizes(vec![100, 1000])
+        .build()?;
+    assert!(!options.compress);
+    assert_eq!(options.block_size, 64);
+    assert_eq!(
+        options.items_per_slot,
+        BBIWriteOptions::default().items_per_slot
+    );
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 10_000)]);
+    let data = vec![("chr1".to_string(), 100, 200, 1.0)];
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(tempfile.path(), chrom_map, data, options)?;
+    let bwread = BigWigRead::open_file(tempfile.path())?;
+    let zooms: Vec<_> = bwread
+        .info()
+        .zoom_headers
+        .iter()
+        .map(|z| z.reduction_level)
+        .collect();
+    assert_eq!(zooms, [100, 1000]);
+
+    let invalid = [
+        BBIWriteOptions::builder().block_size(0),
+        BBIWriteOptions::builder().items_per_slot(0),
+        BBIWriteOptions::builder().manual_zoom_sizes(vec![0, 10]),
+        BBIWriteOptions::builder().manual_zoom_sizes(vec![100, 10]),
+        BBIWriteOptions::builder().manual_zoom_sizes((1..=11).collect()),
+    ];
+    for builder in invalid {
+        assert!(matches!(
+            builder.build(),
+            Err(BBIWriteOptionsError::InvalidOption(_))
+        ));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_iter_all_sorted() -> Result<(), Box<dyn Error>> {
+    use bigtools::BBIWriteOptions;
+
+    let chrom_map = HashMap::from([
+        ("chr1".to_string(), 10_000),
+        ("chr2".to_string(), 10_000),
+        ("chr3".to_string(), 10_000),
+        ("chr10".to_string(), 10_000),
+    ]);
+    let data = vec![
+        ("chr2".to_string(), 10, 20, 3.0),
+        ("chr10".to_string(), 0, 5, 4.0),
+        ("chr1".to_string(), 300, 400, 2.0),
+        ("chr1".to_string(), 100, 200, 1.0),
+    ];
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(tempfile.path(), chrom_map, data, BBIWriteOptions::default())?;
+
+    let mut bwread = BigWigRead::open_file(tempfile.path())?;
+    let sorted: Vec<_> = bwread.iter_all_sorted()?.collect::<Result<_, _>>()?;
+    // Chromosomes are sorted by name in the file, so chr10 comes before chr2
+    let value = |chrom: &str, start, end, value| (chrom.to_string(), Value { start, end, value });
+    assert_eq!(
+        sorted,
+        [
+            value("chr1", 100, 200, 1.0),
+            value("chr1", 300, 400, 2.0),
+            value("chr10", 0, 5, 4.0),
+            value("chr2", 10, 20, 3.0),
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_diff() -> Result<(), Box<dyn Error>> {
+    use bigtools::BBIWriteOptions;
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 1_000)]);
+    let write = |data: Vec<(String, u32, u32, f32)>| -> Result<_, Box<dyn Error>> {
+        let tempfile = tempfile::NamedTempFile::new()?;
+        BigWigWrite::write_vec(
+            tempfile.path(),
+            chrom_map.clone(),
+            data,
+            BBIWriteOptions::default(),
+        )?;
+        Ok(tempfile)
+    };
+    let a = write(vec![
+        ("chr1".to_string(), 10, 20, 1.0),
+        ("chr1".to_string(), 30, 32, 2.0),
+    ])?;
+    let b = write(vec![
+        ("chr1".to_string(), 10, 15, 1.0),
+        ("chr1".to_string(), 15, 20, 1.05),
+        ("chr1".to_string(), 31, 33, 2.0),
+    ])?;
+    let mut a = BigWigRead::open_file(a.path())?;
+    let mut b = BigWigRead::open_file(b.path())?;
+
+    // Only missing data in one file differs within the tolerance
+    let diffs = a.diff(&mut b, "chr1", 0, 100, 0.1)?;
+    assert_eq!(diffs.len(), 2);
+    assert_eq!(diffs[0].0, 30);
+    assert_eq!(diffs[0].1, 2.0);
+    assert!(diffs[0].2.is_nan());
+    assert_eq!(diffs[1].0, 32);
+    assert!(diffs[1].1.is_nan());
+    assert_eq!(diffs[1].2, 2.0);
+
+    let diffs = a.diff(&mut b, "chr1", 0, 100, 0.01)?;
+    let positions: Vec<_> = diffs.iter().map(|d| d.0).collect();
+    assert_eq!(positions, [15, 16, 17, 18, 19, 30, 32]);
+    assert_eq!(diffs[0].1, 1.0);
+    assert_eq!(diffs[0].2, 1.05);
+
+    assert!(a.diff(&mut b, "chr1", 0, 10, 0.0)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_write_combined() -> Result<(), Box<dyn Error>> {
+    use bigtools::{BBIProcessError, BBIWriteOptions};
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 100_000), ("chr2".to_string(), 50)]);
+    let write = |chrom_map: HashMap<String, u32>,
+                 data: Vec<(String, u32, u32, f32)>|
+     -> Result<_, Box<dyn Error>> {
+        let tempfile = tempfile::NamedTempFile::new()?;
+        BigWigWrite::write_vec(tempfile.path(), chrom_map, data, BBIWriteOptions::default())?;
+        Ok(tempfile)
+    };
+    // The values span the window boundary at 65536
+    let a = write(
+        chrom_map.clone(),
+        vec![
+            ("chr1".to_string(), 65_000, 66_000, 1.0),
+            ("chr2".to_string(), 0, 10, 1.0),
+        ],
+    )?;
+    let b = write(
+        chrom_map.clone(),
+        vec![
+            ("chr1".to_string(), 65_500, 70_000, 2.0),
+            ("chr2".to_string(), 5, 10, 3.0),
+        ],
+    )?;
+    let mut inputs = vec![
+        BigWigRead::open_file(a.path())?,
+        BigWigRead::open_file(b.path())?,
+    ];
+
+    let runtime = runtime::Builder::new_current_thread().build()?;
+    let combined = tempfile::NamedTempFile::new()?;
+    let outb = BigWigWrite::create_file(combined.path(), chrom_map.clone())?;
+    // The sum, where missing data is zero, but a base missing in both is missing
+    let sum = |values: &[f32]| {
+        if values.iter().all(|v| v.is_nan()) {
+            return f32::NAN;
+        }
+        values.iter().filter(|v| !v.is_nan()).sum()
+    };
+    outb.write_combined(&mut inputs, sum, runtime)?;
+
+    let mut combined = BigWigRead::open_file(combined.path())?;
+    let chr1: Vec<_> = combined
+        .get_interval_all("chr1")?
+        .map(|v| v.map(|v| (v.start, v.end, v.value)))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        chr1,
+        [
+            (65_000, 65_500, 1.0),
+            (65_500, 66_000, 3.0),
+            (66_000, 70_000, 2.0)
+        ]
+    );
+    let chr2: Vec<_> = combined
+        .get_interval_all("chr2")?
+        .map(|v| v.map(|v| (v.start, v.end, v.value)))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(chr2, [(0, 5, 1.0), (5, 10, 4.0)]);
+
+    // Inputs must have the same chromosomes
+    let c = write(
+        HashMap::from([("chr1".to_string(), 100_000)]),
+        vec![("chr1".to_string(), 0, 10, 1.0)],
+    )?;
+    inputs.push(BigWigRead::open_file(c.path())?);
+    let runtime = runtime::Builder::new_current_thread().build()?;
+    let combined = tempfile::NamedTempFile::new()?;
+    let outb = BigWigWrite::create_file(combined.path(), chrom_map)?;
+    assert!(matches!(
+        outb.write_combined(&mut inputs, sum, runtime),
+        Err(BBIProcessError::InvalidChromosome(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_validate() -> Result<(), Box<dyn Error>> {
+    use std::io::Cursor;
+
+    use bigtools::{BBIWriteOptions, ValidationIssue};
+
+    let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    dir.push("resources/test/valid.bigWig");
+    let mut bwread = BigWigRead::open_file(&dir)?;
+    let report = bwread.validate()?;
+    assert!(report.is_valid(), "{:?}", report.issues);
+    assert!(report.blocks > 0);
+    let values = bwread.get_interval_all("chr17")?.count();
+    assert_eq!(report.values, values as u64);
+
+    // Write an uncompressed file, so that its block can be modified
+    let chrom_map = HashMap::from([("chr1".to_string(), 1_000)]);
+    let data = vec![
+        ("chr1".to_string(), 10, 20, 1.0),
+        ("chr1".to_string(), 30, 40, 2.0),
+    ];
+    let mut options = BBIWriteOptions::default();
+    options.compress = false;
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(tempfile.path(), chrom_map, data, options)?;
+    let bytes = std::fs::read(tempfile.path())?;
+    let (block_offset, leaf_offset) = {
+        let bwread = BigWigRead::open_file(tempfile.path())?;
+        // The data section starts with the item count, and the index with a
+        // 48 byte header. The single block is in the root (leaf) node, after
+        // its 4 byte header.
+        (
+            bwread.info().data_offset() as usize + 8,
+            bwread.info().header.index_offset() as usize + 48 + 4,
+        )
+    };
+    let validate = |modify: &dyn Fn(&mut Vec<u8>)| -> Result<_, Box<dyn Error>> {
+        let mut bytes = bytes.clone();
+        modify(&mut bytes);
+        let mut bwread = BigWigRead::open(Cursor::new(bytes))?;
+        Ok(bwread.validate()?.issues)
+    };
+    // Files are written in the native byte order by default
+    let write_u32 = |bytes: &mut Vec<u8>, at: usize, value: u32| {
+        bytes[at..at + 4].copy_from_slice(&value.to_ne_bytes());
+    };
+
+    assert!(validate(&|_| {})?.is_empty());
+    assert_eq!(
+        validate(&|b| b[block_offset + 20] = 9)?,
+        [ValidationIssue::InvalidSection {
+            offset: block_offset as u64,
+            section_type: 9
+        }]
+    );
+    assert_eq!(
+        validate(&|b| write_u32(b, block_offset + 4, 5_000))?,
+        [ValidationIssue::BlockOutOfRange {
+            offset: block_offset as u64,
+            chrom: "chr1".to_string(),
+            start: 5_000
+        }]
+    );
+    // Blocks are found even if the index places them past the end of their
+    // chromosome, or on a chromosome that isn't in the chromosome tree
+    assert_eq!(
+        validate(&|b| {
+            write_u32(b, leaf_offset + 4, 5_000);
+            write_u32(b, leaf_offset + 12, 5_030);
+            write_u32(b, block_offset + 4, 5_000);
+        })?,
+        [ValidationIssue::BlockOutOfRange {
+            offset: block_offset as u64,
+            chrom: "chr1".to_string(),
+            start: 5_000
+        }]
+    );
+    assert_eq!(
+        validate(&|b| {
+            write_u32(b, leaf_offset, 7);
+            write_u32(b, leaf_offset + 8, 7);
+            write_u32(b, block_offset, 7);
+        })?,
+        [ValidationIssue::UnknownChromosome {
+            offset: block_offset as u64,
+            chrom_id: 7
+        }]
+    );
+    // The second value ends past the end of the chromosome
+    assert_eq!(
+        validate(&|b| write_u32(b, block_offset + 24 + 12 + 4, 2_000))?,
+        [ValidationIssue::ValueOutOfRange {
+            offset: block_offset as u64,
+            chrom: "chr1".to_string(),
+            start: 30,
+            end: 2_000
+        }]
+    );
+    let issues = validate(&|b| {
+        b[block_offset + 22..block_offset + 24].copy_from_slice(&3u16.to_ne_bytes())
+    })?;
+    assert!(matches!(
+        issues[..],
+        [ValidationIssue::ItemCountMismatch {
+            declared: 3,
+            actual: 2,
+            ..
+        }]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_zero_width_and_inverted() -> Result<(), Box<dyn Error>> {
+    use bigtools::{BBIProcessError, BBIWriteOptions};
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 1_000)]);
+    let zero_width = vec![
+        ("chr1".to_string(), 10, 20, 1.0),
+        ("chr1".to_string(), 25, 25, 2.0),
+        ("chr1".to_string(), 30, 40, 3.0),
+        ("chr1".to_string(), 45, 45, 4.0),
+    ];
+    let read_all = |path: &std::path::Path| -> Result<Vec<_>, Box<dyn Error>> {
+        let mut bwread = BigWigRead::open_file(path)?;
+        Ok(bwread
+            .get_interval_all("chr1")?
+            .map(|v| v.map(|v| (v.start, v.end)))
+            .collect::<Result<_, _>>()?)
+    };
+
+    // By default, zero-width values are written
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(
+        tempfile.path(),
+        chrom_map.clone(),
+        zero_width.clone(),
+        BBIWriteOptions::default(),
+    )?;
+    assert_eq!(
+        read_all(tempfile.path())?,
+        [(10, 20), (25, 25), (30, 40), (45, 45)]
+    );
+
+    let options = BBIWriteOptions::builder().drop_zero_width(true).build()?;
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(tempfile.path(), chrom_map.clone(), zero_width, options)?;
+    assert_eq!(read_all(tempfile.path())?, [(10, 20), (30, 40)]);
+
+    // Inverted values are always an error
+    let inverted = vec![
+        ("chr1".to_string(), 10, 20, 1.0),
+        ("chr1".to_string(), 30, 25, 2.0),
+    ];
+    let tempfile = tempfile::NamedTempFile::new()?;
+    let options = BBIWriteOptions::builder().drop_zero_width(true).build()?;
+    match BigWigWrite::write_vec(tempfile.path(), chrom_map, inverted, options) {
+        Err(BBIProcessError::InvalidInput(e)) => {
+            assert!(
+                e.contains("(30)") && e.contains("(25)") && e.contains("chr1"),
+                "{}",
+                e
+            );
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for an inverted value."),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_parallel_zooms() -> Result<(), Box<dyn Error>> {
+    use bigtools::BBIWriteOptions;
+
+    let chrom_map = HashMap::from([
+        ("chr1".to_string(), 10_000_000),
+        ("chr2".to_string(), 10_000_000),
+    ]);
+    // Enough values that zooms are batched more than once per chromosome
+    let vals = || {
+        let iter = ["chr1", "chr2"].into_iter().flat_map(|chrom| {
+            (0..40_000u32).map(move |i| {
+                let start = i * 50 + (i % 7) * 3;
+                (
+                    chrom,
+                    Value {
+                        start,
+                        end: start + 20 + i % 11,
+                        value: (i % 13) as f32 * 0.5,
+                    },
+                )
+            })
+        });
+        BedParserStreamingIterator::wrap_infallible_iter(iter, false)
+    };
+    let write = |parallel_zooms: bool, multipass: bool| -> Result<Vec<u8>, Box<dyn Error>> {
+        let runtime = runtime::Builder::new_multi_thread()
+            .worker_threads(4)
+            .build()?;
+        let tempfile = tempfile::NamedTempFile::new()?;
+        let mut outb = BigWigWrite::create_file(tempfile.path(), chrom_map.clone())?;
+        outb.options = BBIWriteOptions::builder()
+            .parallel_zooms(parallel_zooms)
+            .build()?;
+        if multipass {
+            outb.write_multipass(|| Ok(vals()), runtime)?;
+        } else {
+            outb.write(vals(), runtime)?;
+        }
+        Ok(std::fs::read(tempfile.path())?)
+    };
+
+    for multipass in [false, true] {
+        let serial = write(false, multipass)?;
+        let parallel = write(true, multipass)?;
+        assert!(serial == parallel, "multipass: {}", multipass);
+        let mut bwread = BigWigRead::open(io::Cursor::new(parallel))?;
+        assert!(bwread.info().zoom_headers.len() > 1);
+        let zoom_records = bwread
+            .get_zoom_interval("chr2", 0, 10_000_000, 640)?
+            .count();
+        assert!(zoom_records > 0);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_merged_shards() -> Result<(), Box<dyn Error>> {
+    use bigtools::bed::bedparser::MergedBedStream;
+    use bigtools::OnOverlap;
+
+    let shards = [
+        "chr1\t0\t100\t1.0\nchr1\t200\t300\t2.0\n",
+        "chr1\t100\t200\t3.0\nchr2\t0\t50\t4.0\n",
+    ];
+    let sources = shards
+        .iter()
+        .map(|shard| BedFileStream::from_bedgraph_file(shard.as_bytes()))
+        .collect();
+    let vals = BedParserStreamingIterator::new(MergedBedStream::new(sources), false);
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 1_000), ("chr2".to_string(), 1_000)]);
+    let tempfile = tempfile::NamedTempFile::new()?;
+    let outb = BigWigWrite::create_file(tempfile.path(), chrom_map.clone())?;
+    let runtime = runtime::Builder::new_current_thread().build()?;
+    outb.write(vals, runtime)?;
+
+    let mut bwread = BigWigRead::open_file(tempfile.path())?;
+    let chr1: Vec<_> = bwread
+        .get_interval_all("chr1")?
+        .map(|v| v.map(|v| (v.start, v.end, v.value)))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(chr1, [(0, 100, 1.0), (100, 200, 3.0), (200, 300, 2.0)]);
+    assert_eq!(bwread.get_interval_all("chr2")?.count(), 1);
+
+    // Overlaps across shards are handled by `on_overlap`
+    let shards = ["chr1\t0\t100\t1.0\n", "chr1\t50\t150\t2.0\n"];
+    let write = |on_overlap: OnOverlap| {
+        let sources = shards
+            .iter()
+            .map(|shard| BedFileStream::from_bedgraph_file(shard.as_bytes()))
+            .collect();
+        let vals = BedParserStreamingIterator::new(MergedBedStream::new(sources), false);
+        let tempfile = tempfile::NamedTempFile::new()?;
+        let mut outb = BigWigWrite::create_file(tempfile.path(), chrom_map.clone())?;
+        outb.options.on_overlap = on_overlap;
+        let runtime = runtime::Builder::new_current_thread().build()?;
+        outb.write(vals, runtime)?;
+        let mut bwread = BigWigRead::open_file(tempfile.path())?;
+        let chr1: Vec<_> = bwread
+            .get_interval_all("chr1")?
+            .map(|v| v.map(|v| (v.start, v.end, v.value)))
+            .collect::<Result<_, _>>()?;
+        Ok::<_, Box<dyn Error>>(chr1)
+    };
+    assert_eq!(
+        write(OnOverlap::Sum)?,
+        [(0, 50, 1.0), (50, 100, 3.0), (100, 150, 2.0)]
+    );
+    let err = write(OnOverlap::Error).unwrap_err().to_string();
+    assert!(
+        err.contains("overlapping values on chromosome chr1"),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_overlap_aggregation() -> Result<(), Box<dyn Error>> {
+    use bigtools::{BBIProcessError, BBIWriteOptions, OnOverlap};
+
+    let chrom_map = HashMap::from([("chr1".to_string(), 1_000), ("chr2".to_string(), 1_000)]);
+    let vals = vec![
+        ("chr1".to_string(), 0, 100, 1.0),
+        ("chr1".to_string(), 50, 150, 3.0),
+        ("chr1".to_string(), 60, 70, 2.0),
+        ("chr1".to_string(), 200, 300, 5.0),
+        ("chr2".to_string(), 10, 20, 1.0),
+    ];
+    let write = |on_overlap: OnOverlap| {
+        let tempfile = tempfile::NamedTempFile::new()?;
+        let options = BBIWriteOptions::builder().on_overlap(on_overlap).build()?;
+        BigWigWrite::write_vec(tempfile.path(), chrom_map.clone(), vals.clone(), options)?;
+        let mut bwread = BigWigRead::open_file(tempfile.path())?;
+        let chr1: Vec<_> = bwread
+            .get_interval_all("chr1")?
+            .map(|v| v.map(|v| (v.start, v.end, v.value)))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(bwread.get_interval_all("chr2")?.count(), 1);
+        Ok::<_, Box<dyn Error>>(chr1)
+    };
+
+    let breaks = [
+        (0, 50),
+        (50, 60),
+        (60, 70),
+        (70, 100),
+        (100, 150),
+        (200, 300),
+    ];
+    let expect = |values: [f32; 6]| -> Vec<(u32, u32, f32)> {
+        breaks
+            .iter()
+            .zip(values)
+            .map(|((start, end), value)| (*start, *end, value))
+            .collect()
+    };
+    assert_eq!(
+        write(OnOverlap::Max)?,
+        expect([1.0, 3.0, 3.0, 3.0, 3.0, 5.0])
+    );
+    assert_eq!(
+        write(OnOverlap::Sum)?,
+        expect([1.0, 4.0, 6.0, 4.0, 3.0, 5.0])
+    );
+    assert_eq!(
+        write(OnOverlap::Mean)?,
+        expect([1.0, 2.0, 2.0, 2.0, 3.0, 5.0])
+    );
+    assert_eq!(
+        write(OnOverlap::Last)?,
+        expect([1.0, 3.0, 2.0, 3.0, 3.0, 5.0])
+    );
+
+    // The default is still an error
+    let tempfile = tempfile::NamedTempFile::new()?;
+    match BigWigWrite::write_vec(
+        tempfile.path(),
+        chrom_map.clone(),
+        vals.clone(),
+        BBIWriteOptions::default(),
+    ) {
+        Err(BBIProcessError::InvalidInput(e)) => {
+            assert!(e.contains("overlapping values"), "{}", e);
+        }
+        Err(e) => panic!("Unexpected error: {}", e),
+        Ok(_) => panic!("Expected an error for overlapping values."),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_position_overflow() -> Result<(), Box<dyn Error>> {
+    use std::io::Cursor;
+
+    use bigtools::{BBIReadError, BBIWriteOptions, ValidationIssue};
+
+    // Write an uncompressed file, so that its block can be modified. Zoom
+    // records of the last value would end past `u32::MAX`, so there are none.
+    let chrom_map = HashMap::from([("chr1".to_string(), u32::MAX)]);
+    let data = vec![
+        ("chr1".to_string(), 10, 20, 1.0),
+        ("chr1".to_string(), 30, 40, 2.0),
+        ("chr1".to_string(), u32::MAX - 10, u32::MAX, 3.0),
+    ];
+    let mut options = BBIWriteOptions::default();
+    options.compress = false;
+    options.max_zooms = 0;
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(tempfile.path(), chrom_map, data, options)?;
+    let bytes = std::fs::read(tempfile.path())?;
+    let block_offset = {
+        let mut bwread = BigWigRead::open_file(tempfile.path())?;
+        let vals: Vec<_> = bwread
+            .get_interval_all("chr1")?
+            .map(|v| v.map(|v| (v.start, v.end)))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(vals, [(10, 20), (30, 40), (u32::MAX - 10, u32::MAX)]);
+        // The data section starts with the item count
+        bwread.info().data_offset() as usize + 8
+    };
+    // Files are written in the native byte order by default
+    let read_all = |modify: &dyn Fn(&mut Vec<u8>)| -> Result<_, Box<dyn Error>> {
+        let mut bytes = bytes.clone();
+        modify(&mut bytes);
+        let mut bwread = BigWigRead::open(Cursor::new(bytes))?;
+        Ok(bwread
+            .get_interval_all("chr1")?
+            .collect::<Result<Vec<_>, _>>())
+    };
+
+    // A fixed step section whose second item would start past `u32::MAX`
+    let fixed_step = read_all(&|b| {
+        b[block_offset + 20] = 3;
+        b[block_offset + 12..block_offset + 16].copy_from_slice(&u32::MAX.to_ne_bytes());
+    })?;
+    match fixed_step {
+        Err(BBIReadError::InvalidFile(e)) => assert!(e.contains("maximum position"), "{}", e),
+        other => panic!("Expected an error for overflowing positions: {:?}", other),
+    }
+    // A variable step section whose items would end past `u32::MAX`
+    let variable_step = read_all(&|b| {
+        b[block_offset + 20] = 2;
+        b[block_offset + 16..block_offset + 20].copy_from_slice(&u32::MAX.to_ne_bytes());
+    })?;
+    assert!(matches!(variable_step, Err(BBIReadError::InvalidFile(_))));
+
+    // Validation reports the blocks that can't be decoded
+    let mut overflowing = bytes.clone();
+    overflowing[block_offset + 20] = 3;
+    overflowing[block_offset + 12..block_offset + 16].copy_from_slice(&u32::MAX.to_ne_bytes());
+    let report = BigWigRead::open(Cursor::new(overflowing))?.validate()?;
+    let undecodable: Vec<_> = report
+        .issues
+        .iter()
+        .filter_map(|issue| match issue {
+            ValidationIssue::UndecodableBlock { offset, error } => Some((*offset, error)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(undecodable.len(), 1, "{:?}", report.issues);
+    assert_eq!(undecodable[0].0 as usize, block_offset);
+    assert!(undecodable[0].1.contains("maximum position"));
+
+    Ok(())
+}
+
+#[test]
+fn test_reproducible_output() -> Result<(), Box<dyn Error>> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    use bigtools::bed::bedparser::parse_bedgraph;
+    use bigtools::bed::indexer::index_chroms;
+    use bigtools::beddata::BedParserParallelStreamingIterator;
+
+    let mut bedgraph = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    bedgraph.push("resources/test/multi_chrom.bedGraph");
+    let chrom_indices = index_chroms(File::open(&bedgraph)?)?.unwrap();
+
+    let write_hash = |threads: usize, parallel: bool| -> Result<u64, Box<dyn Error>> {
+        let runtime = if threads == 1 {
+            runtime::Builder::new_current_thread().build()?
+        } else {
+            runtime::Builder::new_multi_thread()
+                .worker_threads(threads)
+                .build()?
+        };
+        let chrom_map: HashMap<String, u32> = (1..=6)
+            .map(|i| (format!("chr{}", i), 300_000_000))
+            .collect();
+        let tempfile = tempfile::NamedTempFile::new()?;
+        let mut outb = BigWigWrite::create_file(tempfile.path(), chrom_map)?;
+        // Small blocks, so that there are many sections to encode in parallel
+        outb.options.items_per_slot = 16;
+        if parallel {
+            let data = BedParserParallelStreamingIterator::new(
+                chrom_indices.clone(),
+                false,
+                bedgraph.clone(),
+                parse_bedgraph,
+            );
+            outb.write(data, runtime)?;
+        } else {
+            let data =
+                BedParserStreamingIterator::from_bedgraph_file(File::open(&bedgraph)?, false);
+            outb.write(data, runtime)?;
+        }
+        let mut hasher = DefaultHasher::new();
+        std::fs::read(tempfile.path())?.hash(&mut hasher);
+        Ok(hasher.finish())
+    };
+
+    // The same input gives the same bytes, regardless of threads or how the
+    // input is read
+    let expected = write_hash(1, false)?;
+    for _ in 0..5 {
+        assert_eq!(write_hash(6, false)?, expected);
+        assert_eq!(write_hash(6, true)?, expected);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_no_trailing_newline() -> Result<(), Box<dyn Error>> {
+    use std::io::Write;
+
+    use bigtools::bed::bedparser::parse_bedgraph;
+    use bigtools::bed::indexer::index_chroms;
+    use bigtools::beddata::BedParserParallelStreamingIterator;
+
+    let mut bedgraph = tempfile::NamedTempFile::new()?;
+    bedgraph.write_all(b"chr1\t0\t100\t1.0\nchr2\t0\t100\t2.0\nchr2\t100\t200\t3.0")?;
+    bedgraph.flush()?;
+    let chrom_map = HashMap::from([("chr1".to_string(), 1_000), ("chr2".to_string(), 1_000)]);
+
+    let read_chr2 = |path: &std::path::Path| -> Result<Vec<_>, Box<dyn Error>> {
+        let mut bwread = BigWigRead::open_file(path)?;
+        Ok(bwread
+            .get_interval_all("chr2")?
+            .map(|v| v.map(|v| (v.start, v.end, v.value)))
+            .collect::<Result<_, _>>()?)
+    };
+    let expected = [(0, 100, 2.0), (100, 200, 3.0)];
+
+    let tempfile = tempfile::NamedTempFile::new()?;
+    let outb = BigWigWrite::create_file(tempfile.path(), chrom_map.clone())?;
+    let data = BedParserStreamingIterator::from_bedgraph_file(File::open(bedgraph.path())?, false);
+    outb.write(data, runtime::Builder::new_current_thread().build()?)?;
+    assert_eq!(read_chr2(tempfile.path())?, expected);
+
+    // Each chromosome is read separately when reading in parallel
+    let chrom_indices = index_chroms(File::open(bedgraph.path())?)?.unwrap();
+    assert_eq!(chrom_indices.len(), 2);
+    let tempfile = tempfile::NamedTempFile::new()?;
+    let outb = BigWigWrite::create_file(tempfile.path(), chrom_map)?;
+    let data = BedParserParallelStreamingIterator::new(
+        chrom_indices,
+        false,
+        bedgraph.path().to_owned(),
+        parse_bedgraph,
+    );
+    outb.write(data, runtime::Builder::new_multi_thread().build()?)?;
+    assert_eq!(read_chr2(tempfile.path())?, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_zoom_interval_no_coverage() -> Result<(), Box<dyn Error>> {
+    use bigtools::{BBIReadError, BBIWriteOptions, ZoomIntervalError};
+
+    let chrom_map = HashMap::from([
+        ("chr1".to_string(), 1_000_000),
+        ("chr2".to_string(), 1_000_000),
+        ("chr3".to_string(), 1_000_000),
+    ]);
+    let mut data = vec![];
+    for i in 0..10_000 {
+        data.push(("chr1".to_string(), i * 10, i * 10 + 5, 1.0));
+    }
+    // chr2 only has a few values near its start, and chr3 has none
+    data.push(("chr2".to_string(), 10, 20, 2.0));
+    data.push(("chr2".to_string(), 100, 150, 3.0));
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(tempfile.path(), chrom_map, data, BBIWriteOptions::default())?;
+
+    let mut bwread = BigWigRead::open_file(tempfile.path())?;
+    let zoom_levels = bwread.zoom_levels();
+    assert!(!zoom_levels.is_empty());
+    for reduction_level in zoom_levels {
+        // Chromosomes without any data aren't written, so aren't in the file
+        assert!(matches!(
+            bwread.get_zoom_interval("chr3", 0, 1_000_000, reduction_level),
+            Err(ZoomIntervalError::BBIReadError(
+                BBIReadError::InvalidChromosome(_)
+            ))
+        ));
+
+        // A region of a sparse chromosome away from its data has no records
+        let records = bwread
+            .get_zoom_interval("chr2", 500_000, 600_000, reduction_level)?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert!(records.is_empty());
+
+        let records = bwread
+            .get_zoom_interval("chr2", 0, 1_000_000, reduction_level)?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert!(!records.is_empty());
+        assert!(records.iter().all(|r| r.start < 150));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_extend_with_chrom() -> Result<(), Box<dyn Error>> {
+    use bigtools::{BBIWriteOptions, ProcessDataError, Value};
+
+    let values = |chrom: &str, n: u32| -> Vec<(String, u32, u32, f32)> {
+        (0..n)
+            .map(|i| (chrom.to_string(), i * 10, i * 10 + 5, (i % 7) as f32))
+            .collect()
+    };
+    let as_values = |data: &[(String, u32, u32, f32)]| -> Vec<Value> {
+        data.iter()
+            .map(|(_, start, end, value)| Value {
+                start: *start,
+                end: *end,
+                value: *value,
+            })
+            .collect()
+    };
+    let chr1 = values("chr1", 20_000);
+    let chr2 = values("chr2", 5_000);
+    let chr3 = values("chr3", 3);
+
+    let extended = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(
+        extended.path(),
+        HashMap::from([("chr1".to_string(), 1_000_000)]),
+        chr1.clone(),
+        BBIWriteOptions::default(),
+    )?;
+    let original_zooms = BigWigRead::open_file(extended.path())?.zoom_levels();
+    let options = BBIWriteOptions::default();
+    BigWigWrite::extend_with_chrom(
+        extended.path(),
+        "chr2",
+        1_000_000,
+        as_values(&chr2),
+        &options,
+    )?;
+    BigWigWrite::extend_with_chrom(extended.path(), "chr3", 100, as_values(&chr3), &options)?;
+
+    // Chromosomes that don't sort last would need to be interleaved
+    let err =
+        BigWigWrite::extend_with_chrom(extended.path(), "chr0", 100, as_values(&chr3), &options)
+            .unwrap_err();
+    assert!(matches!(err, ProcessDataError::InvalidChromosome(_)));
+    let err =
+        BigWigWrite::extend_with_chrom(extended.path(), "chr3", 100, as_values(&chr3), &options)
+            .unwrap_err();
+    assert!(matches!(err, ProcessDataError::InvalidChromosome(_)));
+
+    let full = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(
+        full.path(),
+        HashMap::from([
+            ("chr1".to_string(), 1_000_000),
+            ("chr2".to_string(), 1_000_000),
+            ("chr3".to_string(), 100),
+        ]),
+        [chr1, chr2, chr3].concat(),
+        BBIWriteOptions::default(),
+    )?;
+
+    let mut extended = BigWigRead::open_file(extended.path())?;
+    let mut full = BigWigRead::open_file(full.path())?;
+    assert!(extended.validate()?.issues.is_empty());
+    assert_eq!(extended.zoom_levels(), original_zooms);
+    let chroms: Vec<_> = extended
+        .chroms()
+        .iter()
+        .map(|c| (c.name.clone(), c.length))
+        .collect();
+    assert_eq!(
+        chroms,
+        [
+            ("chr1".to_string(), 1_000_000),
+            ("chr2".to_string(), 1_000_000),
+            ("chr3".to_string(), 100)
+        ]
+    );
+    let extended_values: Vec<_> = extended.iter_all_sorted()?.collect::<Result<_, _>>()?;
+    let full_values: Vec<_> = full.iter_all_sorted()?.collect::<Result<_, _>>()?;
+    assert_eq!(extended_values, full_values);
+
+    let extended_summary = extended.get_summary()?;
+    let full_summary = full.get_summary()?;
+    assert_eq!(extended_summary.bases_covered, full_summary.bases_covered);
+    assert_eq!(extended_summary.min_val, full_summary.min_val);
+    assert_eq!(extended_summary.max_val, full_summary.max_val);
+    assert!((extended_summary.sum - full_summary.sum).abs() < 1e-6);
+
+    // The zoom records of the appended chromosome are there
+    for reduction_level in extended.zoom_levels() {
+        let records = extended
+            .get_zoom_interval("chr2", 0, 1_000_000, reduction_level)?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert!(!records.is_empty());
+        let bases: u64 = records.iter().map(|r| r.summary.bases_covered).sum();
+        assert_eq!(bases, 5_000 * 5);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_sparse_blocks_are_full() -> Result<(), Box<dyn Error>> {
+    use bigtools::BBIWriteOptions;
+
+    // Widely spaced values on several chromosomes of a sparse track
+    let mut data = vec![];
+    let mut chrom_map = HashMap::new();
+    for (chrom, count) in [("chr1", 5_000), ("chr2", 10), ("chr3", 2_048)] {
+        chrom_map.insert(chrom.to_string(), 100_000_000);
+        for i in 0..count {
+            data.push((chrom.to_string(), i * 10_000, i * 10_000 + 1, i as f32));
+        }
+    }
+    let options = BBIWriteOptions::default();
+    let items_per_slot = options.items_per_slot as usize;
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(tempfile.path(), chrom_map, data, options)?;
+
+    let mut bwread = BigWigRead::open_file(tempfile.path())?;
+    for (chrom, count) in [("chr1", 5_000), ("chr2", 10), ("chr3", 2_048)] {
+        let blocks = bwread.get_overlapping_blocks(chrom, 0, 100_000_000)?;
+        assert_eq!(blocks.len(), (count as usize).div_ceil(items_per_slot));
+        let mut item_counts = vec![];
+        for block in blocks.iter() {
+            // The item count is the last field of the block header
+            let data = bwread.read_block_decompressed(block)?;
+            item_counts.push(u16::from_ne_bytes([data[22], data[23]]) as usize);
+        }
+        let (last, full) = item_counts.split_last().unwrap();
+        assert!(full.iter().all(|c| *c == items_per_slot));
+        assert_eq!(full.len() * items_per_slot + last, count as usize);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_step_sections_roundtrip() -> Result<(), Box<dyn Error>> {
+    use bigtools::BBIWriteOptions;
+
+    let mut chrom_map = HashMap::new();
+    chrom_map.insert("chr1".to_string(), 1_000_000);
+    chrom_map.insert("chr2".to_string(), 1_000_000);
+    chrom_map.insert("chr3".to_string(), 1_000_000);
+    let mut data = vec![];
+    // fixedStep: evenly spaced values with a span of 25 and a step of 50
+    for i in 0..3_000u32 {
+        data.push((
+            "chr1".to_string(),
+            100 + i * 50,
+            100 + i * 50 + 25,
+            i as f32,
+        ));
+    }
+    // variableStep: a span of 10, but irregularly spaced
+    for i in 0..100u32 {
+        let start = i * i * 20;
+        data.push(("chr2".to_string(), start, start + 10, i as f32));
+    }
+    // bedGraph: varying spans
+    for i in 0..100u32 {
+        data.push(("chr3".to_string(), i * 100, i * 100 + 1 + i % 7, i as f32));
+    }
+    let options = BBIWriteOptions::builder().step_sections(true).build()?;
+    let tempfile = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(tempfile.path(), chrom_map.clone(), data.clone(), options)?;
+
+    let mut bwread = BigWigRead::open_file(tempfile.path())?;
+    for (chrom, section_type, step, span) in
+        [("chr1", 3, 50, 25), ("chr2", 2, 0, 10), ("chr3", 1, 0, 0)]
+    {
+        let blocks = bwread.get_overlapping_blocks(chrom, 0, 1_000_000)?;
+        assert!(!blocks.is_empty());
+        for block in blocks.iter() {
+            let data = bwread.read_block_decompressed(block)?;
+            let item_step = u32::from_ne_bytes(data[12..16].try_into().unwrap());
+            let item_span = u32::from_ne_bytes(data[16..20].try_into().unwrap());
+            assert_eq!(data[20], section_type);
+            assert_eq!(item_step, step);
+            assert_eq!(item_span, span);
+        }
+    }
+
+    // The values read back are the same as with bedGraph sections
+    let read_values = bwread.iter_all_sorted()?.collect::<Result<Vec<_>, _>>()?;
+    let tempfile_bedgraph = tempfile::NamedTempFile::new()?;
+    BigWigWrite::write_vec(
+        tempfile_bedgraph.path(),
+        chrom_map,
+        data,
+        BBIWriteOptions::default(),
+    )?;
+    let mut bedgraph_read = BigWigRead::open_file(tempfile_bedgraph.path())?;
+    let bedgraph_values = bedgraph_read
+        .iter_all_sorted()?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(read_values, bedgraph_values);
+    assert!(
+        std::fs::metadata(tempfile.path())?.len()
+            < std::fs::metadata(tempfile_bedgraph.path())?.len()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_write_position_overflow() -> Result<(), Box<dyn Error>> {
+    use bigtools::{BBIProcessError, BBIWriteOptions};
+
+    let write = |chrom_size: u32, data: Vec<(u32, u32)>, options: BBIWriteOptions| {
+        let chrom_map = HashMap::from([("chr1".to_string(), chrom_size)]);
+        let data = data
+            .into_iter()
+            .map(|(start, end)| ("chr1".to_string(), start, end, 1.0))
+            .collect();
+        let tempfile = tempfile::NamedTempFile::new().unwrap();
+        BigWigWrite::write_vec(tempfile.path(), chrom_map, data, options)
+    };
+
+    // A zoom record of the last value would end past `u32::MAX`
+    let data = vec![(10, 20), (u32::MAX - 10, u32::MAX)];
+    match write(u32::MAX, data.clone(), BBIWriteOptions::default()) {
+        Err(BBIProcessError::InvalidInput(e)) => assert!(e.contains("maximum position"), "{}", e),
+        other => panic!("Expected an error for an overflowing zoom: {:?}", other),
+    }
+    let mut options = BBIWriteOptions::default();
+    options.parallel_zooms = true;
+    assert!(matches!(
+        write(u32::MAX, data.clone(), options),
+        Err(BBIProcessError::InvalidInput(_))
+    ));
+    // Without zooms, the same data is valid
+    let mut options = BBIWriteOptions::default();
+    options.max_zooms = 0;
+    write(u32::MAX, data, options)?;
+
+    // Values past the end of the chromosome
+    match write(
+        1_000,
+        vec![(10, 20), (990, 1_010)],
+        BBIWriteOptions::default(),
+    ) {
+        Err(BBIProcessError::InvalidInput(e)) => assert!(e.contains("length (1000)"), "{}", e),
+        other => panic!(
+            "Expected an error for a value past the chromosome: {:?}",
+            other
+        ),
+    }
+
+    Ok(())
+}